@@ -1,3 +1,5 @@
+#![allow(clippy::manual_div_ceil, clippy::unnecessary_cast)]
+
 use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
 use ranged_mmap::MmapFile;
 use tempfile::tempdir;
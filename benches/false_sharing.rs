@@ -0,0 +1,55 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use ranged_mmap::allocator::fixed::Allocator;
+use ranged_mmap::MmapFileInner;
+use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tempfile::tempdir;
+
+/// 测试参数
+const THREADS: usize = 4;
+const INCREMENTS: u64 = 100_000;
+const FILE_SIZE: u64 = 4096;
+
+/// 每个线程递增映射中属于自己的计数器
+fn run_counters(file: &MmapFileInner, offsets: &[u64]) {
+    std::thread::scope(|s| {
+        for &offset in offsets {
+            s.spawn(move || {
+                // Safety: 每个线程拥有自己的 8 字节记录，且记录按 8 字节对齐
+                let counter = unsafe { &*(file.as_mut_ptr().add(offset as usize) as *const AtomicU64) };
+                for _ in 0..INCREMENTS {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                }
+            });
+        }
+    });
+}
+
+/// 比较紧密排列与缓存行对齐的每线程计数器
+fn false_sharing_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("false_sharing");
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("counters.bin");
+    let file = MmapFileInner::create(&path, NonZeroU64::new(FILE_SIZE).unwrap()).unwrap();
+
+    let total = NonZeroU64::new(FILE_SIZE).unwrap();
+    let record = NonZeroU64::new(8).unwrap();
+
+    for (name, mut allocator) in [
+        ("packed", Allocator::new(total, record)),
+        ("cache_line", Allocator::cache_line_aligned(total, record)),
+    ] {
+        let offsets: Vec<u64> = (0..THREADS)
+            .map(|_| allocator.allocate().unwrap().start())
+            .collect();
+
+        group.bench_function(BenchmarkId::new(name, format!("{}threads", THREADS)), |b| {
+            b.iter(|| run_counters(&file, &offsets));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, false_sharing_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use ranged_mmap::MmapFileInner;
+use tempfile::tempdir;
+use std::num::NonZeroU64;
+
+/// 测试参数
+const FILE_SIZE: u64 = 1024 * 1024 * 64; // 64MB
+const WRITE_SIZE: usize = 1024 * 1024; // 1MB
+
+/// 创建新文件，可选地预先提交写入区域
+fn setup(dir: &tempfile::TempDir, commit: bool) -> MmapFileInner {
+    let path = dir.path().join("first_write.bin");
+    let file = MmapFileInner::create(&path, NonZeroU64::new(FILE_SIZE).unwrap()).unwrap();
+    if commit {
+        unsafe { file.commit_range(0, WRITE_SIZE).unwrap(); }
+    }
+    file
+}
+
+/// 比较新文件首次写入的延迟（有无 commit_range）
+fn first_write_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("first_write");
+    let dir = tempdir().unwrap();
+    let data = vec![42u8; WRITE_SIZE];
+
+    for commit in [false, true] {
+        let name = if commit { "committed" } else { "lazy" };
+        group.bench_function(
+            BenchmarkId::new(name, format!("{}MB", WRITE_SIZE / (1024 * 1024))),
+            |b| {
+                b.iter_batched(
+                    || setup(&dir, commit),
+                    |file| {
+                        unsafe { file.write_all_at(0, &data); }
+                        // 返回文件，使解除映射不计入测量
+                        file
+                    },
+                    BatchSize::PerIteration,
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, first_write_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,45 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use ranged_mmap::MmapFileInner;
+use tempfile::tempdir;
+use std::hint::black_box;
+use std::num::NonZeroU64;
+
+/// 测试参数
+const FILE_SIZE: u64 = 1024 * 1024 * 64; // 64MB
+const READ_SIZE: usize = 1024 * 1024 * 16; // 16MB
+
+/// 比较读取到清零缓冲区和未初始化缓冲区的开销
+fn uninit_read_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("uninit_read");
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("uninit_read.bin");
+    let file = MmapFileInner::create(&path, NonZeroU64::new(FILE_SIZE).unwrap()).unwrap();
+    unsafe { file.write_all_at(0, &vec![42u8; READ_SIZE]); }
+
+    group.throughput(Throughput::Bytes(READ_SIZE as u64));
+    let size = format!("{}MB", READ_SIZE / (1024 * 1024));
+
+    group.bench_function(BenchmarkId::new("zeroed", &size), |b| {
+        b.iter(|| {
+            let mut buf = vec![0u8; READ_SIZE];
+            unsafe { file.read_at(0, &mut buf).unwrap(); }
+            black_box(buf)
+        });
+    });
+
+    group.bench_function(BenchmarkId::new("uninit", &size), |b| {
+        b.iter(|| {
+            let mut buf = Vec::with_capacity(READ_SIZE);
+            unsafe {
+                let read = file.read_into_uninit(0, buf.spare_capacity_mut()).unwrap().len();
+                buf.set_len(read);
+            }
+            black_box(buf)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, uninit_read_benchmark);
+criterion_main!(benches);
@@ -40,20 +40,19 @@
 //! 通过编译期类型检查保证并发安全：
 //!
 //! ```
-//! # use ranged_mmap::{MmapFile, Result, allocator::ALIGNMENT};
+//! # use ranged_mmap::{MmapFile, Result, nz, allocator::ALIGNMENT};
 //! # use tempfile::tempdir;
 //! # fn main() -> Result<()> {
 //! # let dir = tempdir()?;
 //! # let path = dir.path().join("output.bin");
-//! # use std::num::NonZeroU64;
 //! // Create file and allocator (4K aligned)
 //! // 创建文件和分配器（4K对齐）
-//! let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap())?;
+//! let (file, mut allocator) = MmapFile::create_default(&path, nz(ALIGNMENT * 2))?;
 //!
 //! // Allocate ranges in the main thread (4K aligned)
 //! // 在主线程分配范围（4K对齐）
-//! let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-//! let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+//! let range1 = allocator.allocate(nz(ALIGNMENT)).unwrap();
+//! let range2 = allocator.allocate(nz(ALIGNMENT)).unwrap();
 //!
 //! // Concurrent writes to different ranges (compile-time safe!)
 //! // 并发写入不同范围（编译期安全！）
@@ -79,13 +78,12 @@
 //! 如果你需要最大性能并且能够保证并发安全，可以使用 [`MmapFileInner`]：
 //!
 //! ```
-//! # use ranged_mmap::{MmapFileInner, Result};
+//! # use ranged_mmap::{MmapFileInner, Result, nz};
 //! # use tempfile::tempdir;
 //! # fn main() -> Result<()> {
 //! # let dir = tempdir()?;
 //! # let path = dir.path().join("download.bin");
-//! # use std::num::NonZeroU64;
-//! let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap())?;
+//! let file = MmapFileInner::create(&path, nz(1024))?;
 //!
 //! // ⚠️ Users must ensure concurrent writes do not overlap
 //! // ⚠️ 用户需自行保证不会并发写入重叠区域
@@ -104,18 +102,50 @@
 //! # }
 //! ```
 
+#[macro_use]
+mod trace;
+
 pub mod allocator;
+#[cfg(feature = "tokio")]
+mod async_writer;
+mod batched_writer;
+pub mod coverage;
 mod error;
+mod layout;
+mod mapping;
 mod mmap_file;
 mod mmap_file_inner;
+mod nonzero;
+mod protection;
 mod range;
+mod raw_mmap;
+mod read_only;
+mod rotating_writer;
+mod seq_writer;
+#[cfg(feature = "bytemuck")]
+mod typed;
+#[cfg(all(unix, feature = "sigbus-guard"))]
+mod sigbus;
+mod sys;
 
 #[cfg(test)]
 mod tests;
 
 // Re-export public API
 // 重新导出公共 API
+#[cfg(feature = "tokio")]
+pub use async_writer::AsyncRangeWriter;
+pub use batched_writer::BatchedWriter;
 pub use error::{Error, Result};
+pub use layout::LayoutBuilder;
 pub use mmap_file::MmapFile;
 pub use mmap_file_inner::MmapFileInner;
+pub use nonzero::{nz, try_nz};
+pub use protection::Protection;
 pub use range::{AllocatedRange, WriteReceipt, SplitUpResult, SplitDownResult};
+pub use raw_mmap::RawMmapFile;
+pub use read_only::ReadOnlyMmapFile;
+pub use rotating_writer::{FileIndex, RotatingMmapWriter};
+pub use seq_writer::SeqWriter;
+#[cfg(feature = "bytemuck")]
+pub use typed::TypedHandle;
@@ -3,9 +3,15 @@
 //! Range 分配器实现
 
 pub mod concurrent;
+pub mod fixed;
+pub mod ring;
 pub mod sequential;
+pub mod shared;
+pub mod stack;
 
+use std::fmt;
 use std::num::NonZeroU64;
+use super::range::AllocatedRange;
 
 /// 4K alignment size in bytes (4096 = 0x1000)
 ///
@@ -16,6 +22,11 @@ pub const ALIGNMENT: u64 = 4096;
 ///
 /// 将值向上对齐到最近的4K边界
 ///
+/// Overflows for values within 4K of `u64::MAX`; use [`checked_align_up`] when the value
+/// may come from untrusted input.
+///
+/// 对于距 `u64::MAX` 不足 4K 的值会溢出；当值可能来自不可信输入时，请使用 [`checked_align_up`]。
+///
 /// # Examples
 ///
 /// ```
@@ -35,6 +46,25 @@ pub const fn align_up(value: u64) -> u64 {
     }
 }
 
+/// Align a value up to the nearest 4K boundary, returning `None` on overflow
+///
+/// 将值向上对齐到最近的4K边界，溢出时返回 `None`
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::allocator::checked_align_up;
+/// assert_eq!(checked_align_up(4097), Some(8192));
+/// assert_eq!(checked_align_up(u64::MAX - 1), None);
+/// ```
+#[inline]
+pub const fn checked_align_up(value: u64) -> Option<u64> {
+    match value % ALIGNMENT {
+        0 => Some(value),
+        remainder => value.checked_add(ALIGNMENT - remainder),
+    }
+}
+
 /// Align a value down to the nearest 4K boundary
 ///
 /// 将值向下对齐到最近的4K边界
@@ -55,6 +85,68 @@ pub const fn align_down(value: u64) -> u64 {
     value & !(ALIGNMENT - 1)
 }
 
+/// Reason an allocation failed
+///
+/// 分配失败的原因
+///
+/// Returned by the `try_allocate` methods of the allocators, and by
+/// [`sequential::Allocator::from_bytes`] for corrupt state.
+///
+/// 由各分配器的 `try_allocate` 方法返回，
+/// 以及在状态损坏时由 [`sequential::Allocator::from_bytes`] 返回。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AllocError {
+    /// No space is left in the file
+    ///
+    /// 文件中没有剩余空间
+    Exhausted {
+        /// Bytes remaining when the allocation was attempted
+        ///
+        /// 尝试分配时剩余的字节数
+        remaining: u64,
+    },
+
+    /// Requested size was zero
+    ///
+    /// 请求的大小为零
+    ZeroSize,
+
+    /// Serialized allocator state is inconsistent
+    ///
+    /// 序列化的分配器状态不一致
+    InvalidState {
+        /// Stored allocation position
+        ///
+        /// 保存的分配位置
+        next_pos: u64,
+
+        /// Stored total size
+        ///
+        /// 保存的总大小
+        total_size: u64,
+    },
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AllocError::Exhausted { remaining } => write!(
+                f,
+                "Allocator exhausted, {} bytes remaining / 分配器空间耗尽，剩余 {} 字节",
+                remaining, remaining
+            ),
+            AllocError::ZeroSize => write!(f, "Cannot allocate zero bytes / 无法分配零字节"),
+            AllocError::InvalidState { next_pos, total_size } => write!(
+                f,
+                "Invalid allocator state: position {} with total size {} / 无效的分配器状态：位置 {}，总大小 {}",
+                next_pos, total_size, next_pos, total_size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for AllocError {}
+
 /// Trait for range allocators
 ///
 /// 范围分配器 trait
@@ -65,7 +157,7 @@ pub const fn align_down(value: u64) -> u64 {
 ///
 /// 此 trait 定义了从文件中分配不重叠范围的接口。
 /// 实现必须保证所有分配的范围都是有效且不重叠的。
-pub trait RangeAllocator: Sized {
+pub trait RangeAllocator {
     /// Create a new range allocator
     ///
     /// 创建新的范围分配器
@@ -75,12 +167,129 @@ pub trait RangeAllocator: Sized {
     ///
     /// # 参数
     /// - `total_size`: 文件总大小（字节）
-    fn new(total_size: NonZeroU64) -> Self;
+    fn new(total_size: NonZeroU64) -> Self
+    where
+        Self: Sized;
 
     /// Get the total size
     ///
     /// 获取总大小
     fn total_size(&self) -> NonZeroU64;
+
+    /// Allocate a range for a request of `size` bytes
+    ///
+    /// 为 `size` 字节的请求分配范围
+    ///
+    /// Lets code be generic over the allocator. The 4K allocators return a range of at most
+    /// `align_up(size)` bytes, which is shorter at the end of the file when less than that
+    /// remains. Each allocator keeps its own alignment and exhaustion rules (see its inherent
+    /// `allocate`; the fixed-size allocator, for instance, always returns one whole record);
+    /// allocators that can allocate through `&self` also implement [`ConcurrentRangeAllocator`].
+    ///
+    /// 使代码可以对分配器泛型化。4K 分配器返回最多 `align_up(size)` 字节的范围，
+    /// 当剩余空间不足时，文件末尾的范围会更短。每个分配器保持自己的对齐和耗尽规则
+    /// （见其固有的 `allocate`；例如定长记录分配器总是返回一条完整的记录）；
+    /// 可以通过 `&self` 分配的分配器还实现了 [`ConcurrentRangeAllocator`]。
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数
+    ///
+    /// # Returns
+    /// Returns `None` when the allocator is exhausted or the request cannot be satisfied
+    ///
+    /// # 返回值
+    /// 分配器耗尽或无法满足请求时返回 `None`
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange>;
+
+    /// Get the number of bytes still available for allocation
+    ///
+    /// 获取仍可分配的字节数
+    ///
+    /// Concurrent allocators return a relaxed snapshot.
+    ///
+    /// 并发分配器返回一个宽松的快照。
+    fn remaining(&self) -> u64;
+
+    /// Get how many full aligned chunks of `chunk_size` the remaining space can hold
+    ///
+    /// 获取剩余空间可以容纳多少个完整的、对齐后大小为 `chunk_size` 的块
+    ///
+    /// Computes `remaining() / align_up(chunk_size)`, which is convenient for splitting work.
+    /// Allocators that do not hand out 4K chunks override it.
+    ///
+    /// 计算 `remaining() / align_up(chunk_size)`，便于划分工作。不按 4K 块分配的分配器会重写此方法。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let allocator = Allocator::new(NonZeroU64::new(41 * 1024).unwrap());
+    /// assert_eq!(allocator.capacity_for(NonZeroU64::new(ALIGNMENT).unwrap()), 10);
+    /// ```
+    #[inline]
+    fn capacity_for(&self, chunk_size: NonZeroU64) -> u64 {
+        checked_align_up(chunk_size.get()).map_or(0, |chunk| self.remaining() / chunk)
+    }
+}
+
+/// Range allocator that can be shared between threads
+///
+/// 可在线程之间共享的范围分配器
+///
+/// Allocates through `&self`, so one allocator behind an `Arc` (or a `&dyn ConcurrentRangeAllocator`)
+/// can serve every worker thread, each writing the ranges it gets to its own clone of the file.
+///
+/// 通过 `&self` 分配，因此一个位于 `Arc` 之后（或 `&dyn ConcurrentRangeAllocator`）的分配器
+/// 可以服务所有工作线程，每个线程将得到的范围写入自己的文件克隆。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{MmapFile, Result, allocator::{ConcurrentRangeAllocator, ALIGNMENT}};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("shared.bin");
+/// # use std::num::NonZeroU64;
+/// let (file, allocator) = MmapFile::create_concurrent(&path, NonZeroU64::new(ALIGNMENT * 8).unwrap())?;
+/// let allocator: &dyn ConcurrentRangeAllocator = &*allocator;
+///
+/// std::thread::scope(|s| {
+///     for _ in 0..4 {
+///         let file = file.clone();
+///         s.spawn(move || {
+///             while let Some(range) = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()) {
+///                 file.write_range(range, vec![1u8; range.len() as usize]);
+///             }
+///         });
+///     }
+/// });
+/// # Ok(())
+/// # }
+/// ```
+pub trait ConcurrentRangeAllocator: RangeAllocator + Send + Sync {
+    /// Allocate a range of at most `align_up(size)` bytes (4K aligned)
+    ///
+    /// 分配最多 `align_up(size)` 字节的范围（4K对齐）
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数（会向上对齐到4K）
+    ///
+    /// # Returns
+    /// Returns a range of `align_up(size)` bytes, or a shorter one holding the rest of the file
+    /// when less than that remains; returns `None` when no space is left
+    ///
+    /// # 返回值
+    /// 返回 `align_up(size)` 字节的范围；剩余空间不足时返回包含文件剩余部分的较短范围；
+    /// 没有剩余空间时返回 `None`
+    fn allocate(&self, size: NonZeroU64) -> Option<AllocatedRange>;
 }
 
 #[cfg(test)]
@@ -123,6 +332,42 @@ mod tests {
         assert_eq!(aligned % ALIGNMENT, 0);
     }
 
+    // ========== checked_align_up tests ==========
+
+    #[test]
+    fn test_checked_align_up_normal_values() {
+        assert_eq!(checked_align_up(0), Some(0));
+        assert_eq!(checked_align_up(1), Some(ALIGNMENT));
+        assert_eq!(checked_align_up(ALIGNMENT), Some(ALIGNMENT));
+        assert_eq!(checked_align_up(ALIGNMENT + 1), Some(2 * ALIGNMENT));
+        assert_eq!(checked_align_up(align_down(u64::MAX)), Some(align_down(u64::MAX)));
+    }
+
+    #[test]
+    fn test_checked_align_up_overflow() {
+        assert_eq!(checked_align_up(u64::MAX - 1), None);
+        assert_eq!(checked_align_up(u64::MAX), None);
+        assert_eq!(checked_align_up(align_down(u64::MAX) + 1), None);
+    }
+
+    #[test]
+    fn test_allocators_reject_overflowing_size() {
+        let total = NonZeroU64::new(ALIGNMENT * 4).unwrap();
+        let huge = NonZeroU64::new(u64::MAX - 1).unwrap();
+
+        assert!(sequential::Allocator::new(total).allocate(huge).is_none());
+        assert!(stack::Allocator::new(total).allocate(huge).is_none());
+
+        let conc = concurrent::Allocator::new(total);
+        assert!(conc.allocate(huge).is_none());
+        assert!(conc.allocate_bounded(huge).is_none());
+        // A rejected request leaves the space untouched
+        assert_eq!(conc.remaining(), ALIGNMENT * 4);
+
+        // The ring allocator never fails; it clamps to the file size instead
+        assert_eq!(ring::Allocator::new(total).allocate(huge), 0..ALIGNMENT * 4);
+    }
+
     // ========== align_down tests ==========
 
     #[test]
@@ -173,6 +418,36 @@ mod tests {
         }
     }
 
+    // ========== capacity_for tests ==========
+
+    #[test]
+    fn test_capacity_for_even_and_uneven() {
+        let chunk = NonZeroU64::new(ALIGNMENT).unwrap();
+
+        let even = sequential::Allocator::new(NonZeroU64::new(40 * 1024).unwrap());
+        assert_eq!(even.capacity_for(chunk), 10);
+
+        let uneven = concurrent::Allocator::new(NonZeroU64::new(41 * 1024).unwrap());
+        assert_eq!(uneven.capacity_for(chunk), 10);
+
+        // Chunk sizes are aligned up before dividing
+        assert_eq!(even.capacity_for(NonZeroU64::new(100).unwrap()), 10);
+        assert_eq!(even.capacity_for(NonZeroU64::new(ALIGNMENT + 1).unwrap()), 5);
+    }
+
+    #[test]
+    fn test_capacity_for_uses_remaining() {
+        let chunk = NonZeroU64::new(ALIGNMENT).unwrap();
+
+        let mut seq = sequential::Allocator::new(NonZeroU64::new(ALIGNMENT * 10).unwrap());
+        seq.allocate(NonZeroU64::new(ALIGNMENT * 3).unwrap()).unwrap();
+        assert_eq!(seq.capacity_for(chunk), 7);
+
+        let conc = concurrent::Allocator::new(NonZeroU64::new(ALIGNMENT * 10).unwrap());
+        conc.allocate(NonZeroU64::new(ALIGNMENT * 4).unwrap()).unwrap();
+        assert_eq!(conc.capacity_for(chunk), 6);
+    }
+
     #[test]
     fn test_align_round_trip() {
         // align_down(align_up(x)) == align_up(x) for all x
@@ -181,4 +456,41 @@ mod tests {
             assert_eq!(align_down(up), up);
         }
     }
+
+    /// Allocate `chunk`-sized ranges until the allocator runs out
+    fn fill<A: RangeAllocator>(allocator: &mut A, chunk: NonZeroU64) -> Vec<AllocatedRange> {
+        let mut ranges = Vec::new();
+        while let Some(range) = allocator.allocate(chunk) {
+            ranges.push(range);
+        }
+        ranges
+    }
+
+    #[test]
+    fn test_generic_fill() {
+        let total = NonZeroU64::new(ALIGNMENT * 8).unwrap();
+        let chunk = NonZeroU64::new(ALIGNMENT * 2).unwrap();
+
+        let seq = fill(&mut sequential::Allocator::new(total), chunk);
+        let conc = fill(&mut concurrent::Allocator::new(total), chunk);
+
+        // Both allocators hand out the same back-to-back ranges through the trait
+        assert_eq!(seq.len(), 4);
+        assert_eq!(seq, conc);
+        for (i, range) in seq.iter().enumerate() {
+            assert_eq!(range.start(), i as u64 * ALIGNMENT * 2);
+            assert_eq!(range.len(), ALIGNMENT * 2);
+        }
+    }
+
+    #[test]
+    fn test_trait_allocate_fixed_rejects_oversized() {
+        let mut fixed = fixed::Allocator::new(
+            NonZeroU64::new(ALIGNMENT).unwrap(),
+            NonZeroU64::new(512).unwrap(),
+        );
+        assert!(RangeAllocator::allocate(&mut fixed, NonZeroU64::new(513).unwrap()).is_none());
+        assert_eq!(fill(&mut fixed, NonZeroU64::new(512).unwrap()).len(), 8);
+    }
+
 }
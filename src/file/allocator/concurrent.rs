@@ -1,23 +1,23 @@
-//! Concurrent (wait-free) range allocator implementation
+//! Concurrent (lock-free) range allocator implementation
 //!
-//! 并发（无等待）范围分配器实现
+//! 并发（无锁）范围分配器实现
 
-use super::{align_up, RangeAllocator};
+use super::{checked_align_up, AllocError, ConcurrentRangeAllocator, RangeAllocator, ALIGNMENT};
 use crate::file::range::AllocatedRange;
 use std::cmp;
 use std::num::NonZeroU64;
 use std::sync::atomic::{AtomicU64, Ordering};
 
-/// Concurrent (wait-free) range allocator for file regions
+/// Concurrent (lock-free) range allocator for file regions
 ///
-/// 文件区域的并发（无等待）范围分配器
+/// 文件区域的并发（无锁）范围分配器
 ///
 /// This allocator uses atomic operations to allow concurrent allocation
-/// from multiple threads without locking. It is wait-free, meaning every
-/// operation completes in a bounded number of steps.
+/// from multiple threads without locking. It is lock-free, meaning some
+/// thread always makes progress even when others contend for the position.
 ///
 /// 此分配器使用原子操作，允许多个线程并发分配而无需加锁。
-/// 它是无等待的，意味着每个操作都在有限步骤内完成。
+/// 它是无锁的，意味着即使多个线程竞争分配位置，也总有线程能够取得进展。
 ///
 /// # Example
 ///
@@ -87,9 +87,9 @@ impl<'de> serde::Deserialize<'de> for Allocator {
 }
 
 impl Allocator {
-    /// Allocate a range concurrently (wait-free, 4K aligned)
+    /// Allocate a range concurrently (lock-free, 4K aligned)
     ///
-    /// 并发分配范围（无等待，4K对齐）
+    /// 并发分配范围（无锁，4K对齐）
     ///
     /// This method is safe to call from multiple threads simultaneously.
     /// The allocation size is rounded up to 4K boundary to ensure alignment.
@@ -123,16 +123,65 @@ impl Allocator {
     /// 例如，请求100字节将分配4096字节。
     #[inline]
     pub fn allocate(&self, requested_size: NonZeroU64) -> Option<AllocatedRange> {
+        self.allocate_ordered(requested_size, Ordering::Relaxed)
+    }
+
+    /// Allocate a range from a plain byte count
+    ///
+    /// 按普通字节数分配范围
+    ///
+    /// Same as [`allocate`](Self::allocate), but takes a `u64` and returns `None` for 0 instead of
+    /// requiring the caller to build a [`NonZeroU64`].
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但接受 `u64`，并在为 0 时返回 `None`，
+    /// 而不需要调用者构造 [`NonZeroU64`]。
+    ///
+    /// # Parameters
+    /// - `bytes`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `bytes`: 要分配的字节数（会向上对齐到4K）
+    #[inline]
+    pub fn allocate_bytes(&self, bytes: u64) -> Option<AllocatedRange> {
+        self.allocate(NonZeroU64::new(bytes)?)
+    }
+
+    /// Allocate a range concurrently with an explicit memory ordering
+    ///
+    /// 以显式的内存序并发分配范围
+    ///
+    /// Same as [`allocate`](Self::allocate), but uses `order` for the atomic increment of the
+    /// allocation position. `Relaxed` (what `allocate` uses) is enough to guarantee that ranges
+    /// never overlap, but it does not publish anything: data a thread wrote before allocating
+    /// is not guaranteed to be visible to a thread that allocates after it. Use `AcqRel` when
+    /// the allocation position itself is used to hand written data over between threads.
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但分配位置的原子递增使用 `order`。
+    /// `Relaxed`（`allocate` 所用）足以保证范围不重叠，但不会发布任何数据：
+    /// 某个线程在分配之前写入的数据，不保证对之后分配的线程可见。
+    /// 当分配位置本身被用于在线程之间传递已写入的数据时，请使用 `AcqRel`。
+    ///
+    /// # Parameters
+    /// - `requested_size`: Number of bytes to allocate (will be aligned to 4K)
+    /// - `order`: Memory ordering of the increment
+    ///
+    /// # 参数
+    /// - `requested_size`: 要分配的字节数（会向上对齐到4K）
+    /// - `order`: 递增操作的内存序
+    #[inline]
+    pub fn allocate_ordered(&self, requested_size: NonZeroU64, order: Ordering) -> Option<AllocatedRange> {
         // Align the requested size up to 4K boundary
         // 将请求大小向上对齐到4K边界
-        let size = align_up(requested_size.get());
+        let size = checked_align_up(requested_size.get())?;
         let total = self.total_size.get();
 
-        // 1. Optimistically increment counter (Wait-Free)
+        // 1. Optimistically increment counter, saturating instead of wrapping past zero
         // Even if this causes next_pos to exceed total_size, we handle truncation below
-        // 1. 乐观地增加计数器 (Wait-Free)
+        // 1. 乐观地增加计数器，饱和而不是回绕到零
         // 哪怕这会导致 next_pos 超过 total_size 也没关系，我们在后面处理截断
-        let start = self.next_pos.fetch_add(size, Ordering::Relaxed);
+        let (Ok(start) | Err(start)) = self
+            .next_pos
+            .fetch_update(order, Ordering::Relaxed, |pos| Some(pos.saturating_add(size)));
 
         // 2. First check: if start position already exceeds file size,
         // space was already exhausted before this call
@@ -154,8 +203,223 @@ impl Allocator {
         // At this point, end - start is the actual allocated size,
         // which may be smaller than the aligned requested_size
         // 此时，end - start 就是实际分配到的大小，它可能小于对齐后的 requested_size
+        trace_event!(start, len = end - start, "allocate");
         Some(AllocatedRange::from_range_unchecked(start, end))
     }
+
+    /// Allocate a range concurrently without overshooting the end (lock-free, 4K aligned)
+    ///
+    /// 并发分配范围且不越过末尾（无锁，4K对齐）
+    ///
+    /// Same result as [`allocate`](Self::allocate), but failed calls leave the position
+    /// unchanged. When only a partial 4K tail remains, the position is clamped exactly to
+    /// `total_size` rather than being bumped past it, so [`next_pos`](Self::next_pos) always
+    /// stays consistent.
+    ///
+    /// 结果与 [`allocate`](Self::allocate) 相同，但失败的调用不会改变位置。当只剩部分 4K 尾部时，
+    /// 位置会被精确钳位到 `total_size`，而不会越过它，因此 [`next_pos`](Self::next_pos) 始终保持一致。
+    ///
+    /// # Parameters
+    /// - `requested_size`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `requested_size`: 要分配的字节数（会向上对齐到4K）
+    #[inline]
+    pub fn allocate_bounded(&self, requested_size: NonZeroU64) -> Option<AllocatedRange> {
+        let size = checked_align_up(requested_size.get())?;
+        let total = self.total_size.get();
+
+        let mut start = self.next_pos.load(Ordering::Relaxed);
+        loop {
+            if start >= total {
+                return None;
+            }
+
+            let end = cmp::min(start.saturating_add(size), total);
+            match self.next_pos.compare_exchange_weak(start, end, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return Some(AllocatedRange::from_range_unchecked(start, end)),
+                Err(current) => start = current,
+            }
+        }
+    }
+
+    /// Allocate between `min` and `max` bytes concurrently (lock-free, 4K aligned)
+    ///
+    /// 并发分配介于 `min` 与 `max` 字节之间的范围（无锁，4K对齐）
+    ///
+    /// Reserves `max` rounded up to 4K when enough space remains, otherwise whatever is left,
+    /// as long as that is at least `min` bytes. Suited to workers that prefer large chunks but
+    /// accept smaller ones near the end of the file. Like
+    /// [`allocate_bounded`](Self::allocate_bounded), the position is advanced with a
+    /// compare-and-swap loop and never moves past `total_size`; a failed call leaves it unchanged.
+    ///
+    /// 当剩余空间足够时，预留向上对齐到 4K 的 `max`；否则分配剩下的全部空间，只要不少于 `min` 字节。
+    /// 适用于偏好大块、但在文件末尾附近也能接受较小块的工作线程。与 [`allocate_bounded`](Self::allocate_bounded)
+    /// 相同，位置通过比较交换循环推进，且永远不会越过 `total_size`；失败的调用不会改变位置。
+    ///
+    /// # Parameters
+    /// - `min`: Smallest acceptable number of bytes
+    /// - `max`: Preferred number of bytes (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `min`: 可接受的最小字节数
+    /// - `max`: 期望的字节数（会向上对齐到4K）
+    ///
+    /// # Returns
+    /// Returns `None` if fewer than `min` bytes remain
+    ///
+    /// # 返回值
+    /// 如果剩余不足 `min` 字节，返回 `None`
+    ///
+    /// # Panics
+    /// Panics if `min` is greater than `max`
+    ///
+    /// # Panics
+    /// 如果 `min` 大于 `max`，则 panic
+    #[inline]
+    pub fn allocate_between(&self, min: NonZeroU64, max: NonZeroU64) -> Option<AllocatedRange> {
+        assert!(min <= max, "min {} must not exceed max {}", min, max);
+
+        let size = checked_align_up(max.get())?;
+        let total = self.total_size.get();
+
+        let mut start = self.next_pos.load(Ordering::Relaxed);
+        loop {
+            if total.saturating_sub(start) < min.get() {
+                return None;
+            }
+
+            let end = cmp::min(start.saturating_add(size), total);
+            match self.next_pos.compare_exchange_weak(start, end, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => {
+                    trace_event!(start, len = end - start, "allocate");
+                    return Some(AllocatedRange::from_range_unchecked(start, end));
+                }
+                Err(current) => start = current,
+            }
+        }
+    }
+
+    /// Allocate a range concurrently with a custom alignment (lock-free)
+    ///
+    /// 以自定义对齐并发分配范围（无锁）
+    ///
+    /// Rounds the current position up to `align`, skipping the gap, and reserves `size`
+    /// rounded up to `align`, all in one compare-and-swap loop. Useful for mixing regular
+    /// 4K ranges with e.g. 2M-aligned ranges for huge pages in one file. Like
+    /// [`allocate_bounded`](Self::allocate_bounded), a tail shorter than the aligned size is
+    /// still handed out, clamped to `total_size`.
+    ///
+    /// 将当前位置向上对齐到 `align`（跳过间隙），并预留向上对齐到 `align` 的 `size`，
+    /// 全部在一个比较交换循环中完成。适用于在同一文件中混合常规 4K 范围和用于大页的 2M 对齐范围等场景。
+    /// 与 [`allocate_bounded`](Self::allocate_bounded) 相同，短于对齐大小的尾部仍会被分配，并钳位到 `total_size`。
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate (will be aligned to `align`)
+    /// - `align`: Alignment of the start and size, a power of two of at least [`ALIGNMENT`](super::ALIGNMENT)
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数（会向上对齐到 `align`）
+    /// - `align`: 起始位置和大小的对齐，必须是不小于 [`ALIGNMENT`](super::ALIGNMENT) 的 2 的幂
+    ///
+    /// # Panics
+    /// Panics if `align` is not a power of two or is smaller than [`ALIGNMENT`](super::ALIGNMENT)
+    ///
+    /// # Panics
+    /// 如果 `align` 不是 2 的幂或小于 [`ALIGNMENT`](super::ALIGNMENT)，则 panic
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::{concurrent::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// const HUGE_PAGE: u64 = 2 * 1024 * 1024;
+    /// let allocator = Allocator::new(NonZeroU64::new(HUGE_PAGE * 4).unwrap());
+    ///
+    /// let small = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    /// let huge = allocator.allocate_aligned(NonZeroU64::new(HUGE_PAGE).unwrap(), HUGE_PAGE).unwrap();
+    /// assert_eq!(small.start(), 0);
+    /// assert_eq!(huge.start(), HUGE_PAGE);
+    /// ```
+    #[inline]
+    pub fn allocate_aligned(&self, size: NonZeroU64, align: u64) -> Option<AllocatedRange> {
+        assert!(
+            align.is_power_of_two() && align >= ALIGNMENT,
+            "alignment {} must be a power of two of at least {}",
+            align, ALIGNMENT
+        );
+
+        let mask = align - 1;
+        let size = size.get().checked_add(mask)? & !mask;
+        let total = self.total_size.get();
+
+        let mut current = self.next_pos.load(Ordering::Relaxed);
+        loop {
+            let start = current.checked_add(mask)? & !mask;
+            if start >= total {
+                return None;
+            }
+
+            let end = cmp::min(start.saturating_add(size), total);
+            match self.next_pos.compare_exchange_weak(current, end, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => return Some(AllocatedRange::from_range_unchecked(start, end)),
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Get the number of remaining allocatable bytes
+    ///
+    /// 获取剩余可分配字节数
+    ///
+    /// This is a relaxed snapshot; other threads may allocate concurrently.
+    ///
+    /// 这是一个宽松的快照；其他线程可能正在并发分配。
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.total_size
+            .get()
+            .saturating_sub(self.next_pos.load(Ordering::Relaxed))
+    }
+
+    /// Get the next allocation position
+    ///
+    /// 获取下一个分配位置
+    ///
+    /// May exceed the total size after [`allocate`](Self::allocate) hands out the last range;
+    /// never does when only [`allocate_bounded`](Self::allocate_bounded) is used.
+    ///
+    /// 在 [`allocate`](Self::allocate) 分配出最后一个范围后可能超过总大小；
+    /// 只使用 [`allocate_bounded`](Self::allocate_bounded) 时永远不会。
+    #[inline]
+    pub fn next_pos(&self) -> u64 {
+        self.next_pos.load(Ordering::Relaxed)
+    }
+
+    /// Allocate a range concurrently, reporting why allocation failed
+    ///
+    /// 并发分配范围，并报告分配失败的原因
+    ///
+    /// Same as [`allocate`](Self::allocate), but returns an [`AllocError`] instead of `None`,
+    /// distinguishing a zero-sized request from an exhausted file.
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但返回 [`AllocError`] 而不是 `None`，
+    /// 以区分零大小请求和文件空间耗尽。
+    ///
+    /// # Errors
+    /// - `ZeroSize` if `requested_size` is 0
+    /// - `Exhausted { remaining }` if no space is left
+    ///
+    /// # Errors
+    /// - 如果 `requested_size` 为 0，返回 `ZeroSize`
+    /// - 如果没有剩余空间，返回 `Exhausted { remaining }`
+    #[inline]
+    pub fn try_allocate(&self, requested_size: u64) -> Result<AllocatedRange, AllocError> {
+        let size = NonZeroU64::new(requested_size).ok_or(AllocError::ZeroSize)?;
+        self.allocate(size).ok_or_else(|| AllocError::Exhausted {
+            remaining: self.remaining(),
+        })
+    }
 }
 
 impl RangeAllocator for Allocator {
@@ -171,6 +435,23 @@ impl RangeAllocator for Allocator {
     fn total_size(&self) -> NonZeroU64 {
         self.total_size
     }
+
+    #[inline]
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        Allocator::allocate(self, size)
+    }
+
+    #[inline]
+    fn remaining(&self) -> u64 {
+        Allocator::remaining(self)
+    }
+}
+
+impl ConcurrentRangeAllocator for Allocator {
+    #[inline]
+    fn allocate(&self, size: NonZeroU64) -> Option<AllocatedRange> {
+        Allocator::allocate(self, size)
+    }
 }
 
 #[cfg(test)]
@@ -261,6 +542,95 @@ mod tests {
         assert!(allocator.allocate(non_zero(1)).is_none());
     }
 
+    #[test]
+    fn test_concurrent_huge_requests_do_not_wrap() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 4));
+
+        // Two 2^63 requests would wrap an unchecked position back past zero
+        let first = allocator.allocate(non_zero(1 << 63)).unwrap();
+        assert_eq!(first.start(), 0);
+        assert_eq!(first.end(), ALIGNMENT * 4);
+        assert!(allocator.allocate(non_zero(1 << 63)).is_none());
+
+        // Later allocations must not overlap the first range
+        assert!(allocator.allocate(non_zero(ALIGNMENT)).is_none());
+        assert!(allocator.allocate_ordered(non_zero(ALIGNMENT), Ordering::AcqRel).is_none());
+        assert_eq!(allocator.next_pos(), u64::MAX);
+    }
+
+    #[test]
+    fn test_concurrent_try_allocate() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+
+        assert_eq!(allocator.try_allocate(0), Err(AllocError::ZeroSize));
+
+        allocator.try_allocate(ALIGNMENT).unwrap();
+
+        // Partial allocation near the end
+        let range = allocator.try_allocate(ALIGNMENT * 4).unwrap();
+        assert_eq!(range.start(), ALIGNMENT);
+        assert_eq!(range.end(), ALIGNMENT * 3);
+
+        assert_eq!(
+            allocator.try_allocate(1),
+            Err(AllocError::Exhausted { remaining: 0 })
+        );
+    }
+
+    #[test]
+    fn test_concurrent_bounded_tail() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 2 + 100));
+
+        allocator.allocate_bounded(non_zero(ALIGNMENT * 2)).unwrap();
+
+        // Partial tail clamps next_pos exactly to total_size
+        let range = allocator.allocate_bounded(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(range.start(), ALIGNMENT * 2);
+        assert_eq!(range.end(), ALIGNMENT * 2 + 100);
+        assert_eq!(allocator.next_pos(), ALIGNMENT * 2 + 100);
+
+        assert!(allocator.allocate_bounded(non_zero(1)).is_none());
+        assert_eq!(allocator.next_pos(), ALIGNMENT * 2 + 100);
+    }
+
+    #[test]
+    fn test_concurrent_bounded_stress_last_pages() {
+        // Unaligned total so the final allocation is a partial page
+        const TOTAL_SIZE: u64 = ALIGNMENT * 8 + 123;
+        const NUM_THREADS: usize = 16;
+
+        for _ in 0..100 {
+            let allocator = Arc::new(Allocator::new(non_zero(TOTAL_SIZE)));
+            let handles: Vec<_> = (0..NUM_THREADS)
+                .map(|_| {
+                    let alloc = Arc::clone(&allocator);
+                    thread::spawn(move || {
+                        let mut ranges = Vec::new();
+                        while let Some(range) = alloc.allocate_bounded(non_zero(ALIGNMENT)) {
+                            ranges.push((range.start(), range.end()));
+                        }
+                        ranges
+                    })
+                })
+                .collect();
+
+            let mut all_ranges: Vec<(u64, u64)> = handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap())
+                .collect();
+            all_ranges.sort_by_key(|r| r.0);
+
+            // Coverage is exact and contiguous
+            let mut expected_start = 0u64;
+            for (start, end) in &all_ranges {
+                assert_eq!(*start, expected_start);
+                expected_start = *end;
+            }
+            assert_eq!(expected_start, TOTAL_SIZE);
+            assert_eq!(allocator.next_pos(), TOTAL_SIZE);
+        }
+    }
+
     #[test]
     fn test_concurrent_total_size() {
         let allocator = Allocator::new(non_zero(12345));
@@ -313,6 +683,39 @@ mod tests {
         assert_eq!(expected_start, TOTAL_SIZE);
     }
 
+    #[test]
+    fn test_concurrent_allocate_ordered_acq_rel() {
+        use crate::MmapFileInner;
+
+        const TOTAL_SIZE: u64 = ALIGNMENT * 64;
+        const NUM_THREADS: usize = 4;
+
+        let dir = tempfile::tempdir().unwrap();
+        let file = MmapFileInner::create(dir.path().join("ordered.bin"), non_zero(TOTAL_SIZE)).unwrap();
+        let allocator = Allocator::new(non_zero(TOTAL_SIZE));
+
+        // Each thread fills its ranges with its id and reads them back
+        thread::scope(|s| {
+            for id in 0..NUM_THREADS {
+                let (file, allocator) = (&file, &allocator);
+                s.spawn(move || {
+                    while let Some(range) = allocator.allocate_ordered(non_zero(ALIGNMENT), Ordering::AcqRel) {
+                        let data = vec![id as u8 + 1; range.len() as usize];
+                        unsafe {
+                            file.write_at(range.start(), &data);
+                            assert_eq!(file.read_slice(range.start(), data.len()).unwrap(), data);
+                        }
+                    }
+                });
+            }
+        });
+
+        // Every byte was written by exactly one thread
+        assert_eq!(allocator.next_pos(), TOTAL_SIZE + ALIGNMENT * NUM_THREADS as u64);
+        let bytes = unsafe { file.read_slice(0, TOTAL_SIZE as usize).unwrap() };
+        assert!(bytes.iter().all(|&b| (1..=NUM_THREADS as u8).contains(&b)));
+    }
+
     #[test]
     fn test_concurrent_stress() {
         // Use 4K aligned total size
@@ -342,4 +745,86 @@ mod tests {
         // Total allocated should equal file size
         assert_eq!(total, TOTAL_SIZE);
     }
+
+    #[test]
+    fn test_concurrent_allocate_aligned_skips_gap() {
+        const HUGE_PAGE: u64 = 2 * 1024 * 1024;
+        let allocator = Allocator::new(non_zero(HUGE_PAGE * 3));
+
+        let small = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!((small.start(), small.end()), (0, ALIGNMENT));
+
+        // The gap [4K, 2M) is skipped and the size is rounded up to 2M
+        let huge = allocator.allocate_aligned(non_zero(HUGE_PAGE - 1), HUGE_PAGE).unwrap();
+        assert_eq!(huge.start() % HUGE_PAGE, 0);
+        assert_eq!((huge.start(), huge.end()), (HUGE_PAGE, HUGE_PAGE * 2));
+
+        // Regular allocations continue after the aligned range
+        let next = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(next.start(), HUGE_PAGE * 2);
+
+        // No aligned start left before the end
+        assert!(allocator.allocate_aligned(non_zero(HUGE_PAGE), HUGE_PAGE * 4).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_concurrent_allocate_aligned_rejects_small_alignment() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 4));
+        allocator.allocate_aligned(non_zero(ALIGNMENT), 512);
+    }
+
+    #[test]
+    fn test_concurrent_allocate_bytes() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 2));
+
+        // Zero returns None without consuming space
+        assert!(allocator.allocate_bytes(0).is_none());
+        assert_eq!(allocator.next_pos(), 0);
+
+        let range = allocator.allocate_bytes(ALIGNMENT + 1).unwrap();
+        assert_eq!(range.as_range_tuple(), (0, ALIGNMENT * 2));
+        assert!(allocator.allocate_bytes(1).is_none());
+    }
+
+    #[test]
+    fn test_concurrent_allocate_between_plenty() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 16));
+
+        // Plenty of space yields max
+        let range = allocator.allocate_between(non_zero(ALIGNMENT), non_zero(ALIGNMENT * 4)).unwrap();
+        assert_eq!(range.as_range_tuple(), (0, ALIGNMENT * 4));
+    }
+
+    #[test]
+    fn test_concurrent_allocate_between_tight_tail() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 6));
+        allocator.allocate_between(non_zero(ALIGNMENT), non_zero(ALIGNMENT * 4)).unwrap();
+
+        // Only 2 pages remain: between min and max
+        let range = allocator.allocate_between(non_zero(ALIGNMENT), non_zero(ALIGNMENT * 4)).unwrap();
+        assert_eq!(range.as_range_tuple(), (ALIGNMENT * 4, ALIGNMENT * 6));
+        assert_eq!(allocator.next_pos(), ALIGNMENT * 6);
+    }
+
+    #[test]
+    fn test_concurrent_allocate_between_too_little() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+        allocator.allocate(non_zero(ALIGNMENT * 2)).unwrap();
+
+        // One page left, but at least two required
+        assert!(allocator.allocate_between(non_zero(ALIGNMENT * 2), non_zero(ALIGNMENT * 4)).is_none());
+        assert_eq!(allocator.next_pos(), ALIGNMENT * 2);
+
+        // A smaller minimum still fits
+        let range = allocator.allocate_between(non_zero(1), non_zero(ALIGNMENT * 4)).unwrap();
+        assert_eq!(range.as_range_tuple(), (ALIGNMENT * 2, ALIGNMENT * 3));
+    }
+
+    #[test]
+    #[should_panic(expected = "must not exceed")]
+    fn test_concurrent_allocate_between_rejects_min_above_max() {
+        let allocator = Allocator::new(non_zero(ALIGNMENT * 4));
+        allocator.allocate_between(non_zero(ALIGNMENT * 2), non_zero(ALIGNMENT));
+    }
 }
@@ -0,0 +1,328 @@
+//! Fixed-size record allocator implementation
+//!
+//! 定长记录分配器实现
+
+use super::{ALIGNMENT, RangeAllocator};
+use crate::file::range::AllocatedRange;
+use std::num::NonZeroU64;
+
+/// Cache line size in bytes assumed by [`Allocator::cache_line_aligned`]
+///
+/// [`Allocator::cache_line_aligned`] 假定的缓存行大小（字节）
+pub const CACHE_LINE: u64 = 64;
+
+/// Fixed-size record allocator for file regions
+///
+/// 文件区域的定长记录分配器
+///
+/// Hands out back-to-back ranges of exactly `record_size` bytes, each aligned to
+/// `record_size` instead of 4K. This packs uniform records densely: 512-byte records
+/// use the whole file rather than one 4K slot each. A tail shorter than a record is
+/// never allocated.
+///
+/// 连续分配恰好 `record_size` 字节的范围，每个范围按 `record_size` 而不是 4K 对齐。
+/// 这使得定长记录可以紧密排列：512 字节的记录会用满整个文件，而不是每条占用一个 4K 槽位。
+/// 不足一条记录的尾部永远不会被分配。
+///
+/// Constructing through [`RangeAllocator::new`] uses a record size of [`ALIGNMENT`].
+/// Use [`cache_line_aligned`](Allocator::cache_line_aligned) to keep small per-thread
+/// records on separate cache lines.
+///
+/// 通过 [`RangeAllocator::new`] 构造时使用 [`ALIGNMENT`] 作为记录大小。
+/// 使用 [`cache_line_aligned`](Allocator::cache_line_aligned) 可以让每线程的小记录位于不同的缓存行。
+///
+/// # Example
+///
+/// ```
+/// # use ranged_mmap::allocator::fixed::Allocator;
+/// # use std::num::NonZeroU64;
+/// let mut allocator = Allocator::new(
+///     NonZeroU64::new(2048).unwrap(),
+///     NonZeroU64::new(512).unwrap(),
+/// );
+///
+/// let range1 = allocator.allocate().unwrap();
+/// assert_eq!((range1.start(), range1.end()), (0, 512));
+///
+/// let range2 = allocator.allocate().unwrap();
+/// assert_eq!((range2.start(), range2.end()), (512, 1024));
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Allocator {
+    /// Next allocation position
+    ///
+    /// 下一个分配位置
+    next_pos: u64,
+
+    /// Total file size
+    ///
+    /// 文件总大小
+    total_size: NonZeroU64,
+
+    /// Size of every record
+    ///
+    /// 每条记录的大小
+    record_size: NonZeroU64,
+
+    /// Distance between the starts of consecutive records (also their alignment)
+    ///
+    /// 相邻记录起始位置之间的距离（也是它们的对齐）
+    stride: NonZeroU64,
+}
+
+impl Allocator {
+    /// Create a new allocator for records of `record_size` bytes
+    ///
+    /// 为 `record_size` 字节的记录创建新的分配器
+    ///
+    /// # Parameters
+    /// - `total_size`: Total file size in bytes
+    /// - `record_size`: Size and alignment of every record
+    ///
+    /// # 参数
+    /// - `total_size`: 文件总大小（字节）
+    /// - `record_size`: 每条记录的大小和对齐
+    #[inline]
+    pub fn new(total_size: NonZeroU64, record_size: NonZeroU64) -> Self {
+        Self {
+            next_pos: 0,
+            total_size,
+            record_size,
+            stride: record_size,
+        }
+    }
+
+    /// Create a new allocator whose records each start on their own cache line
+    ///
+    /// 创建新的分配器，使每条记录都从独立的缓存行开始
+    ///
+    /// Records are still `record_size` bytes long, but consecutive records are spaced
+    /// `record_size` rounded up to a multiple of [`CACHE_LINE`] apart. The goal is avoiding
+    /// false sharing between threads that update neighboring records (e.g. per-thread
+    /// counters), not page alignment: records may share a page, and writes to them still
+    /// require each thread to own its range.
+    ///
+    /// 记录长度仍为 `record_size` 字节，但相邻记录的间距为 `record_size` 向上取整到 [`CACHE_LINE`] 的倍数。
+    /// 目的是避免更新相邻记录（例如每线程计数器）的线程之间的伪共享，而不是页对齐：
+    /// 记录可能位于同一页面，写入时仍要求每个线程拥有各自的范围。
+    ///
+    /// # Parameters
+    /// - `total_size`: Total file size in bytes
+    /// - `record_size`: Size of every record
+    ///
+    /// # 参数
+    /// - `total_size`: 文件总大小（字节）
+    /// - `record_size`: 每条记录的大小
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::fixed::{Allocator, CACHE_LINE};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::cache_line_aligned(
+    ///     NonZeroU64::new(4096).unwrap(),
+    ///     NonZeroU64::new(8).unwrap(),
+    /// );
+    ///
+    /// let counter1 = allocator.allocate().unwrap();
+    /// let counter2 = allocator.allocate().unwrap();
+    /// assert_eq!((counter1.start(), counter1.len()), (0, 8));
+    /// assert_eq!((counter2.start(), counter2.len()), (CACHE_LINE, 8));
+    /// ```
+    #[inline]
+    pub fn cache_line_aligned(total_size: NonZeroU64, record_size: NonZeroU64) -> Self {
+        let stride = record_size.get().div_ceil(CACHE_LINE) * CACHE_LINE;
+        Self {
+            next_pos: 0,
+            total_size,
+            record_size,
+            // Rounding a non-zero value up to a multiple of CACHE_LINE stays non-zero
+            // 将非零值向上取整到 CACHE_LINE 的倍数结果仍非零
+            stride: NonZeroU64::new(stride).unwrap(),
+        }
+    }
+
+    /// Allocate the next record
+    ///
+    /// 分配下一条记录
+    ///
+    /// # Returns
+    /// Returns `Some(AllocatedRange)` of exactly `record_size` bytes,
+    /// or `None` if no whole record is left.
+    ///
+    /// # 返回值
+    /// 返回恰好 `record_size` 字节的 `Some(AllocatedRange)`，
+    /// 没有完整的剩余记录时返回 `None`
+    #[inline]
+    pub fn allocate(&mut self) -> Option<AllocatedRange> {
+        let start = self.next_pos;
+        let end = start.checked_add(self.record_size.get())?;
+        if end > self.total_size.get() {
+            return None;
+        }
+
+        self.next_pos = start.saturating_add(self.stride.get()).min(self.total_size.get());
+        trace_event!(start, len = self.record_size.get(), "allocate");
+        Some(AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    /// Get the number of records that can still be allocated
+    ///
+    /// 获取仍可分配的记录数
+    #[inline]
+    pub fn remaining_records(&self) -> u64 {
+        // The last record needs only `record_size` bytes, not a full stride
+        // 最后一条记录只需要 `record_size` 字节，而不是完整的间距
+        match self.total_size.get().saturating_sub(self.next_pos).checked_sub(self.record_size.get()) {
+            Some(rest) => rest / self.stride.get() + 1,
+            None => 0,
+        }
+    }
+
+    /// Get the record size
+    ///
+    /// 获取记录大小
+    #[inline]
+    pub fn record_size(&self) -> NonZeroU64 {
+        self.record_size
+    }
+
+    /// Get the distance between the starts of consecutive records
+    ///
+    /// 获取相邻记录起始位置之间的距离
+    #[inline]
+    pub fn stride(&self) -> NonZeroU64 {
+        self.stride
+    }
+
+    /// Get the next allocation position
+    ///
+    /// 获取下一个分配位置
+    #[inline]
+    pub fn next_pos(&self) -> u64 {
+        self.next_pos
+    }
+}
+
+impl RangeAllocator for Allocator {
+    #[inline]
+    fn new(total_size: NonZeroU64) -> Self {
+        Self::new(total_size, NonZeroU64::new(ALIGNMENT).unwrap())
+    }
+
+    #[inline]
+    fn total_size(&self) -> NonZeroU64 {
+        self.total_size
+    }
+
+    #[inline]
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        if size > self.record_size {
+            return None;
+        }
+        Allocator::allocate(self)
+    }
+
+    #[inline]
+    fn remaining(&self) -> u64 {
+        self.total_size.get().saturating_sub(self.next_pos)
+    }
+
+    /// Every allocation hands out one record, so this counts records rather than 4K chunks
+    ///
+    /// 每次分配给出一条记录，因此按记录而不是 4K 块计数
+    #[inline]
+    fn capacity_for(&self, chunk_size: NonZeroU64) -> u64 {
+        if chunk_size > self.record_size {
+            return 0;
+        }
+        self.remaining_records()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn non_zero(val: u64) -> NonZeroU64 {
+        NonZeroU64::new(val).unwrap()
+    }
+
+    #[test]
+    fn test_fixed_back_to_back_records() {
+        let mut allocator = Allocator::new(non_zero(512 * 8), non_zero(512));
+
+        // Records are packed with no gaps
+        let mut expected_start = 0;
+        while let Some(range) = allocator.allocate() {
+            assert_eq!(range.start(), expected_start);
+            assert_eq!(range.len(), 512);
+            assert_eq!(range.start() % 512, 0);
+            expected_start = range.end();
+        }
+
+        assert_eq!(expected_start, 512 * 8);
+        assert_eq!(allocator.remaining_records(), 0);
+    }
+
+    #[test]
+    fn test_fixed_partial_tail_not_allocated() {
+        // 3.5 records worth of space
+        let mut allocator = Allocator::new(non_zero(512 * 3 + 256), non_zero(512));
+        assert_eq!(allocator.remaining_records(), 3);
+
+        for _ in 0..3 {
+            allocator.allocate().unwrap();
+        }
+        assert!(allocator.allocate().is_none());
+        assert_eq!(allocator.next_pos(), 512 * 3);
+    }
+
+    #[test]
+    fn test_fixed_trait_new_uses_alignment() {
+        let mut allocator = <Allocator as RangeAllocator>::new(non_zero(ALIGNMENT * 2));
+        assert_eq!(allocator.record_size().get(), ALIGNMENT);
+        assert_eq!(allocator.total_size().get(), ALIGNMENT * 2);
+        assert_eq!(allocator.allocate().unwrap().len(), ALIGNMENT);
+    }
+
+    #[test]
+    fn test_fixed_capacity_for_counts_records() {
+        let allocator = Allocator::new(non_zero(512 * 8), non_zero(512));
+        assert_eq!(RangeAllocator::capacity_for(&allocator, non_zero(512)), 8);
+        assert_eq!(RangeAllocator::capacity_for(&allocator, non_zero(100)), 8);
+        // Requests larger than a record are never satisfied
+        assert_eq!(RangeAllocator::capacity_for(&allocator, non_zero(513)), 0);
+
+        let allocator = Allocator::cache_line_aligned(non_zero(1000), non_zero(8));
+        assert_eq!(RangeAllocator::capacity_for(&allocator, non_zero(8)), 16);
+    }
+
+    #[test]
+    fn test_fixed_cache_line_aligned() {
+        let mut allocator = Allocator::cache_line_aligned(non_zero(1000), non_zero(8));
+        assert_eq!(allocator.stride().get(), CACHE_LINE);
+        // 15 full strides plus a 40-byte tail that still fits one record
+        assert_eq!(allocator.remaining_records(), 16);
+
+        let mut count = 0;
+        while let Some(range) = allocator.allocate() {
+            assert_eq!(range.start() % CACHE_LINE, 0);
+            assert_eq!(range.len(), 8);
+            count += 1;
+        }
+        assert_eq!(count, 16);
+        assert_eq!(allocator.remaining_records(), 0);
+    }
+
+    #[test]
+    fn test_fixed_cache_line_rounds_record_size() {
+        let allocator = Allocator::cache_line_aligned(non_zero(4096), non_zero(65));
+        assert_eq!(allocator.stride().get(), 2 * CACHE_LINE);
+        assert_eq!(allocator.record_size().get(), 65);
+
+        // Records that are already a multiple of a cache line are packed back to back
+        let allocator = Allocator::cache_line_aligned(non_zero(4096), non_zero(128));
+        assert_eq!(allocator.stride().get(), 128);
+    }
+}
@@ -0,0 +1,229 @@
+//! Ring-buffer range allocator implementation
+//!
+//! 环形缓冲区范围分配器实现
+
+use super::checked_align_up;
+use std::num::NonZeroU64;
+use std::ops::Range;
+
+/// Ring-buffer range allocator for bounded circular logs
+///
+/// 用于有界循环日志的环形缓冲区范围分配器
+///
+/// Allocates sequentially like [`sequential::Allocator`](super::sequential::Allocator), but
+/// once the end of the file is reached it wraps around to offset 0 and keeps going,
+/// overwriting the oldest data. A range never straddles the wrap boundary: if the aligned
+/// request doesn't fit before the end, the tail is skipped and the range starts at 0 instead.
+/// Requests larger than the whole file are clamped to the file size.
+///
+/// 与 [`sequential::Allocator`](super::sequential::Allocator) 一样顺序分配，
+/// 但到达文件末尾后会回绕到偏移 0 继续分配，覆盖最旧的数据。
+/// 范围永远不会跨越回绕边界：如果对齐后的请求在末尾之前放不下，
+/// 则跳过尾部，从 0 开始分配。超过整个文件大小的请求会被截断为文件大小。
+///
+/// # ⚠️ Overlap
+///
+/// Unlike the other allocators, ranges returned after wrapping overlap previously returned
+/// ranges. Writing a new range while an old overlapping range is still being written or read
+/// is a data race, so this allocator is meant for single-writer logs where each range is
+/// finished before the allocator laps it. For the same reason it is not a
+/// [`RangeAllocator`](super::RangeAllocator): it hands out plain offsets for the `unsafe`
+/// [`MmapFileInner`](crate::MmapFileInner) API instead of [`AllocatedRange`](crate::AllocatedRange)s
+/// for the safe [`MmapFile`](crate::MmapFile) API, which relies on ranges never overlapping.
+///
+/// # ⚠️ 重叠
+///
+/// 与其他分配器不同，回绕之后返回的范围会与之前返回的范围重叠。
+/// 在旧的重叠范围仍在写入或读取时写入新范围会导致数据竞争，
+/// 因此此分配器适用于单写入者日志，每个范围都在分配器追上它之前完成。
+/// 出于同样的原因，它不是 [`RangeAllocator`](super::RangeAllocator)：它为 `unsafe` 的
+/// [`MmapFileInner`](crate::MmapFileInner) API 分配普通偏移，而不是为依赖范围永不重叠的安全
+/// [`MmapFile`](crate::MmapFile) API 分配 [`AllocatedRange`](crate::AllocatedRange)。
+///
+/// # Example
+///
+/// ```
+/// # use ranged_mmap::allocator::{ring::Allocator, ALIGNMENT};
+/// # use std::num::NonZeroU64;
+/// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 2).unwrap());
+/// let size = NonZeroU64::new(ALIGNMENT).unwrap();
+///
+/// assert_eq!(allocator.allocate(size).start, 0);
+/// assert_eq!(allocator.allocate(size).start, ALIGNMENT);
+///
+/// // Wraps around to the beginning
+/// // 回绕到开头
+/// assert_eq!(allocator.allocate(size).start, 0);
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Allocator {
+    /// Next allocation position
+    ///
+    /// 下一个分配位置
+    next_pos: u64,
+
+    /// Total file size
+    ///
+    /// 文件总大小
+    total_size: NonZeroU64,
+
+    /// Number of times allocation wrapped around
+    ///
+    /// 分配回绕的次数
+    laps: u64,
+}
+
+impl Allocator {
+    /// Create a new ring allocator over `total_size` bytes
+    ///
+    /// 创建覆盖 `total_size` 字节的环形分配器
+    ///
+    /// # Parameters
+    /// - `total_size`: Total file size
+    ///
+    /// # 参数
+    /// - `total_size`: 文件总大小
+    #[inline]
+    pub fn new(total_size: NonZeroU64) -> Self {
+        Self {
+            next_pos: 0,
+            total_size,
+            laps: 0,
+        }
+    }
+
+    /// Allocate a range of the specified size (4K aligned), wrapping at the end
+    ///
+    /// 分配指定大小的范围（4K对齐），到达末尾时回绕
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate (will be aligned to 4K, clamped to the file size)
+    ///
+    /// # Returns
+    /// Always returns a range of offsets; it starts at 0 when the request doesn't fit before the end
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数（会向上对齐到4K，并截断为文件大小）
+    ///
+    /// # 返回值
+    /// 总是返回一个偏移范围；当请求在末尾之前放不下时，范围从 0 开始
+    #[inline]
+    pub fn allocate(&mut self, size: NonZeroU64) -> Range<u64> {
+        let total = self.total_size.get();
+        let size = checked_align_up(size.get()).map_or(total, |size| size.min(total));
+
+        if total - self.next_pos < size {
+            self.next_pos = 0;
+            self.laps += 1;
+        }
+
+        let start = self.next_pos;
+        let end = start + size;
+        self.next_pos = end;
+        trace_event!(start, len = size, laps = self.laps, "allocate");
+
+        start..end
+    }
+
+    /// Allocate a range from a plain byte count
+    ///
+    /// 按普通字节数分配范围
+    ///
+    /// Same as [`allocate`](Self::allocate), but takes a `u64` and returns `None` for 0 instead of
+    /// requiring the caller to build a [`NonZeroU64`].
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但接受 `u64`，并在为 0 时返回 `None`，
+    /// 而不需要调用者构造 [`NonZeroU64`]。
+    ///
+    /// # Parameters
+    /// - `bytes`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `bytes`: 要分配的字节数（会向上对齐到4K）
+    #[inline]
+    pub fn allocate_bytes(&mut self, bytes: u64) -> Option<Range<u64>> {
+        NonZeroU64::new(bytes).map(|size| self.allocate(size))
+    }
+
+    /// Get the next allocation position
+    ///
+    /// 获取下一个分配位置
+    #[inline]
+    pub fn next_pos(&self) -> u64 {
+        self.next_pos
+    }
+
+    /// Get the total file size
+    ///
+    /// 获取文件总大小
+    #[inline]
+    pub fn total_size(&self) -> NonZeroU64 {
+        self.total_size
+    }
+
+    /// Get the number of times allocation wrapped around
+    ///
+    /// 获取分配回绕的次数
+    #[inline]
+    pub fn laps(&self) -> u64 {
+        self.laps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::allocator::ALIGNMENT;
+    use super::*;
+
+    fn non_zero(val: u64) -> NonZeroU64 {
+        NonZeroU64::new(val).unwrap()
+    }
+
+    #[test]
+    fn test_ring_wraps_to_zero() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+
+        for i in 0..3 {
+            assert_eq!(allocator.allocate(non_zero(100)).start, ALIGNMENT * i);
+        }
+        assert_eq!(allocator.laps(), 0);
+
+        // Writing past the end wraps to offset 0
+        let range = allocator.allocate(non_zero(100));
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, ALIGNMENT);
+        assert_eq!(allocator.laps(), 1);
+    }
+
+    #[test]
+    fn test_ring_never_straddles_boundary() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+
+        allocator.allocate(non_zero(ALIGNMENT * 2));
+
+        // Only 4K left, 8K requested: the tail is skipped
+        let range = allocator.allocate(non_zero(ALIGNMENT * 2));
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, ALIGNMENT * 2);
+    }
+
+    #[test]
+    fn test_ring_clamps_oversized_request() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 2));
+
+        let range = allocator.allocate(non_zero(ALIGNMENT * 10));
+        assert_eq!(range.start, 0);
+        assert_eq!(range.end, ALIGNMENT * 2);
+
+        let range = allocator.allocate(non_zero(ALIGNMENT * 10));
+        assert_eq!(range.start, 0);
+        assert_eq!(allocator.laps(), 1);
+    }
+
+    #[test]
+    fn test_ring_allocate_bytes() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 2));
+        assert!(allocator.allocate_bytes(0).is_none());
+        assert_eq!(allocator.allocate_bytes(1), Some(0..ALIGNMENT));
+    }
+}
@@ -2,10 +2,15 @@
 //!
 //! 顺序范围分配器实现
 
-use super::{align_up, RangeAllocator};
+use super::{align_down, checked_align_up, AllocError, RangeAllocator};
 use crate::file::range::AllocatedRange;
 use std::num::NonZeroU64;
 
+/// Huge page size used by [`Allocator::allocate_huge`] (2 MiB)
+///
+/// [`Allocator::allocate_huge`] 使用的大页大小（2 MiB）
+pub const HUGE_PAGE: u64 = 2 * 1024 * 1024;
+
 /// Sequential range allocator for file regions
 ///
 /// 文件区域的顺序范围分配器
@@ -58,6 +63,21 @@ pub struct Allocator {
 }
 
 impl Allocator {
+    /// Create an allocator whose first allocation starts at `start`
+    ///
+    /// 创建首次分配从 `start` 开始的分配器
+    ///
+    /// Used to keep a reserved prefix (such as a header) out of the allocatable space.
+    ///
+    /// 用于将保留的前缀（例如头部）排除在可分配空间之外。
+    #[inline]
+    pub(crate) fn starting_at(total_size: NonZeroU64, start: u64) -> Self {
+        Self {
+            next_pos: start,
+            total_size,
+        }
+    }
+
     /// Allocate a range of the specified size (4K aligned)
     ///
     /// 分配指定大小的范围（4K对齐）
@@ -65,11 +85,11 @@ impl Allocator {
     /// Allocates from the current unallocated position. The allocation size is
     /// rounded up to 4K boundary to ensure alignment. When remaining space
     /// is less than the aligned requested size, allocates all remaining space instead.
-    /// Returns `None` only when no space is left.
+    /// Returns `None` when no space is left, or when aligning `size` would overflow `u64`.
     ///
     /// 从当前未分配位置开始分配。分配大小会向上对齐到4K边界以确保对齐。
     /// 当剩余空间小于对齐后的请求大小时，分配所有剩余空间。
-    /// 仅当没有剩余空间时返回 `None`。
+    /// 当没有剩余空间，或对齐 `size` 会溢出 `u64` 时返回 `None`。
     ///
     /// # Note
     /// The actual allocated size may be larger than requested due to 4K alignment.
@@ -80,6 +100,199 @@ impl Allocator {
     /// 例如，请求100字节将分配4096字节。
     #[inline]
     pub fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        let range = self.peek(size)?;
+        self.next_pos = range.end();
+        trace_event!(start = range.start(), len = range.len(), "allocate");
+        Some(range)
+    }
+
+    /// Allocate a range from a plain byte count
+    ///
+    /// 按普通字节数分配范围
+    ///
+    /// Same as [`allocate`](Self::allocate), but takes a `u64` and returns `None` for 0 instead of
+    /// requiring the caller to build a [`NonZeroU64`].
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但接受 `u64`，并在为 0 时返回 `None`，
+    /// 而不需要调用者构造 [`NonZeroU64`]。
+    ///
+    /// # Parameters
+    /// - `bytes`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `bytes`: 要分配的字节数（会向上对齐到4K）
+    #[inline]
+    pub fn allocate_bytes(&mut self, bytes: u64) -> Option<AllocatedRange> {
+        self.allocate(NonZeroU64::new(bytes)?)
+    }
+
+    /// Allocate all remaining space as one range
+    ///
+    /// 将剩余的全部空间分配为一个范围
+    ///
+    /// Returns `[next_pos, total_size)` and moves the allocation position to the end, so later
+    /// allocations return `None`. The start is already 4K aligned; the end is `total_size`.
+    ///
+    /// 返回 `[next_pos, total_size)`，并将分配位置移到末尾，之后的分配都将返回 `None`。
+    /// 起始位置已是4K对齐的；结束位置为 `total_size`。
+    ///
+    /// # Returns
+    /// Returns `None` if no space remains
+    ///
+    /// # 返回值
+    /// 如果没有剩余空间，返回 `None`
+    #[inline]
+    pub fn allocate_rest(&mut self) -> Option<AllocatedRange> {
+        let start = self.next_pos;
+        let end = self.total_size.get();
+        if start >= end {
+            return None;
+        }
+
+        self.next_pos = end;
+        trace_event!(start, len = end - start, "allocate");
+        Some(AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    /// Get the unallocated tail without allocating it
+    ///
+    /// 获取未分配的尾部，但不进行分配
+    ///
+    /// Returns `[next_pos, total_size)` and leaves the allocation position unchanged. Together
+    /// with [`commit`](Self::commit) this supports streaming writers that write straight into
+    /// the tail and only then decide how much of it they used.
+    ///
+    /// 返回 `[next_pos, total_size)`，且不改变分配位置。与 [`commit`](Self::commit) 配合，
+    /// 可支持先直接写入尾部、之后再决定使用了多少的流式写入者。
+    ///
+    /// # Returns
+    /// Returns `None` if no space remains
+    ///
+    /// # 返回值
+    /// 如果没有剩余空间，返回 `None`
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap());
+    ///
+    /// let tail = allocator.tail().unwrap();
+    /// assert_eq!(tail.len(), ALIGNMENT * 4);
+    ///
+    /// // Wrote 3000 bytes into the tail
+    /// // 向尾部写入了 3000 字节
+    /// allocator.commit(3000);
+    /// assert_eq!(allocator.next_pos(), ALIGNMENT);
+    /// ```
+    #[inline]
+    pub fn tail(&self) -> Option<AllocatedRange> {
+        let start = self.next_pos;
+        let end = self.total_size.get();
+        (start < end).then(|| AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    /// Mark `bytes` at the start of the tail as used
+    ///
+    /// 将尾部开头的 `bytes` 字节标记为已使用
+    ///
+    /// Advances the allocation position by `bytes` rounded up to 4K (clamped to the total
+    /// size), so the next allocation stays aligned and never overlaps the committed bytes.
+    ///
+    /// 将分配位置前移 `bytes` 向上对齐到4K 后的长度（不超过总大小），
+    /// 使下一次分配保持对齐，且不会与已提交的字节重叠。
+    ///
+    /// # Parameters
+    /// - `bytes`: Number of bytes written into the [`tail`](Self::tail)
+    ///
+    /// # 参数
+    /// - `bytes`: 写入 [`tail`](Self::tail) 的字节数
+    ///
+    /// # Panics
+    /// Panics if `bytes` exceeds the remaining space
+    ///
+    /// # Panics
+    /// 如果 `bytes` 超出剩余空间，则 panic
+    #[inline]
+    pub fn commit(&mut self, bytes: u64) {
+        let remaining = self.remaining();
+        assert!(
+            bytes <= remaining,
+            "committed {} bytes but only {} remain",
+            bytes, remaining
+        );
+
+        let len = checked_align_up(bytes).map_or(remaining, |len| len.min(remaining));
+        trace_event!(start = self.next_pos, len, "commit");
+        self.next_pos += len;
+    }
+
+    /// Allocate a 2 MiB-aligned range for huge-page or DMA buffers
+    ///
+    /// 为大页或 DMA 缓冲区分配 2 MiB 对齐的范围
+    ///
+    /// Rounds the current position up to [`HUGE_PAGE`], skipping the gap, and reserves `size`
+    /// rounded up to [`HUGE_PAGE`]. The skipped gap is never handed out. Like
+    /// [`allocate`](Self::allocate), a tail shorter than the rounded size is still allocated,
+    /// ending at `total_size`.
+    ///
+    /// 将当前位置向上对齐到 [`HUGE_PAGE`]（跳过间隙），并预留向上对齐到 [`HUGE_PAGE`] 的 `size`。
+    /// 被跳过的间隙不会再被分配。与 [`allocate`](Self::allocate) 相同，
+    /// 短于对齐大小的尾部仍会被分配，结束于 `total_size`。
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate (will be aligned to 2 MiB)
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数（会向上对齐到 2 MiB）
+    ///
+    /// # Returns
+    /// Returns `None` if no space remains after the aligned start
+    ///
+    /// # 返回值
+    /// 如果对齐后的起始位置之后没有剩余空间，返回 `None`
+    #[inline]
+    pub fn allocate_huge(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        let mask = HUGE_PAGE - 1;
+        let total = self.total_size.get();
+        let start = self.next_pos.checked_add(mask)? & !mask;
+        if start >= total {
+            return None;
+        }
+
+        let size = size.get().checked_add(mask)? & !mask;
+        let end = start.saturating_add(size).min(total);
+        self.next_pos = end;
+        trace_event!(start, len = end - start, "allocate");
+        Some(AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    /// Compute the range the next `allocate` would return, without allocating
+    ///
+    /// 计算下一次 `allocate` 将返回的范围，但不进行分配
+    ///
+    /// Returns exactly what [`allocate`](Self::allocate) would return for the same `size`,
+    /// but leaves the allocation position unchanged. Useful for validating that a planned
+    /// layout fits before committing to it.
+    ///
+    /// 返回与相同 `size` 下 [`allocate`](Self::allocate) 完全相同的结果，但不改变分配位置。
+    /// 适用于在提交之前验证计划的布局是否放得下。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 2).unwrap());
+    /// let size = NonZeroU64::new(ALIGNMENT).unwrap();
+    ///
+    /// let planned = allocator.peek(size).unwrap();
+    /// assert_eq!(allocator.next_pos(), 0);
+    /// assert_eq!(allocator.allocate(size), Some(planned));
+    /// ```
+    #[inline]
+    pub fn peek(&self, size: NonZeroU64) -> Option<AllocatedRange> {
         let remaining = self.total_size.get().saturating_sub(self.next_pos);
         if remaining == 0 {
             return None;
@@ -88,15 +301,97 @@ impl Allocator {
         let start = self.next_pos;
         // Align the requested size up to 4K boundary
         // 将请求大小向上对齐到4K边界
-        let aligned_size = align_up(size.get());
+        let aligned_size = checked_align_up(size.get())?;
         // Allocate min(aligned_requested, remaining)
         let actual_size = aligned_size.min(remaining);
         let end = start + actual_size;
-        self.next_pos = end;
 
         Some(AllocatedRange::from_range_unchecked(start, end))
     }
 
+    /// Allocate a range of the specified size, reporting why allocation failed
+    ///
+    /// 分配指定大小的范围，并报告分配失败的原因
+    ///
+    /// Same as [`allocate`](Self::allocate), but returns an [`AllocError`] instead of `None`,
+    /// distinguishing a zero-sized request from an exhausted file.
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但返回 [`AllocError`] 而不是 `None`，
+    /// 以区分零大小请求和文件空间耗尽。
+    ///
+    /// # Errors
+    /// - `ZeroSize` if `size` is 0
+    /// - `Exhausted { remaining }` if no space is left
+    ///
+    /// # Errors
+    /// - 如果 `size` 为 0，返回 `ZeroSize`
+    /// - 如果没有剩余空间，返回 `Exhausted { remaining }`
+    #[inline]
+    pub fn try_allocate(&mut self, size: u64) -> Result<AllocatedRange, AllocError> {
+        let size = NonZeroU64::new(size).ok_or(AllocError::ZeroSize)?;
+        self.allocate(size).ok_or_else(|| AllocError::Exhausted {
+            remaining: self.remaining(),
+        })
+    }
+
+    /// Length of the encoding produced by [`to_bytes`](Self::to_bytes)
+    ///
+    /// [`to_bytes`](Self::to_bytes) 生成的编码长度
+    pub const ENCODED_LEN: usize = 16;
+
+    /// Encode the allocator state for storage in a file header
+    ///
+    /// 编码分配器状态，以便保存在文件头部中
+    ///
+    /// The format is stable: `next_pos` followed by `total_size`, both little-endian `u64`.
+    /// Restore it with [`from_bytes`](Self::from_bytes).
+    ///
+    /// 格式是稳定的：`next_pos` 之后是 `total_size`，均为小端序 `u64`。
+    /// 使用 [`from_bytes`](Self::from_bytes) 恢复。
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap());
+    /// allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    ///
+    /// let restored = Allocator::from_bytes(&allocator.to_bytes()).unwrap();
+    /// assert_eq!(restored.next_pos(), ALIGNMENT);
+    /// ```
+    #[inline]
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        bytes[..8].copy_from_slice(&self.next_pos.to_le_bytes());
+        bytes[8..].copy_from_slice(&self.total_size.get().to_le_bytes());
+        bytes
+    }
+
+    /// Decode allocator state written by [`to_bytes`](Self::to_bytes)
+    ///
+    /// 解码由 [`to_bytes`](Self::to_bytes) 写入的分配器状态
+    ///
+    /// # Errors
+    /// Returns `InvalidState` if the total size is 0 or `next_pos` exceeds it
+    ///
+    /// # Errors
+    /// 如果总大小为 0 或 `next_pos` 超出总大小，返回 `InvalidState`
+    #[inline]
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Result<Self, AllocError> {
+        let (next_pos, total_size) = bytes.split_at(8);
+        let next_pos = u64::from_le_bytes(next_pos.try_into().unwrap());
+        let total = u64::from_le_bytes(total_size.try_into().unwrap());
+
+        match NonZeroU64::new(total) {
+            Some(total_size) if next_pos <= total => Ok(Self::starting_at(total_size, next_pos)),
+            _ => Err(AllocError::InvalidState {
+                next_pos,
+                total_size: total,
+            }),
+        }
+    }
+
     /// Get the number of remaining allocatable bytes
     ///
     /// 获取剩余可分配字节数
@@ -127,6 +422,136 @@ impl RangeAllocator for Allocator {
     fn total_size(&self) -> NonZeroU64 {
         self.total_size
     }
+
+    #[inline]
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        Allocator::allocate(self, size)
+    }
+
+    #[inline]
+    fn remaining(&self) -> u64 {
+        Allocator::remaining(self)
+    }
+}
+
+/// Split a file into `n` page-aligned slices, each with its own allocator
+///
+/// 将文件划分为 `n` 个页对齐的分片，每个分片拥有自己的分配器
+///
+/// Every slice except possibly the last has the same 4K aligned length; the last one ends
+/// at `total_size`. Slices that would start past the end of the file are empty. Each
+/// allocator only hands out ranges within its slice, so workers can allocate from their own
+/// slice without any atomics or coordination. An allocator's
+/// [`total_size`](RangeAllocator::total_size) is the end of its slice.
+///
+/// 除最后一个分片外，每个分片都具有相同的 4K 对齐长度；最后一个分片结束于 `total_size`。
+/// 起始位置超出文件末尾的分片为空。每个分配器只在自己的分片内分配范围，
+/// 因此各工作线程可以在各自的分片中分配，无需任何原子操作或协调。
+/// 分配器的 [`total_size`](RangeAllocator::total_size) 为其分片的结束位置。
+///
+/// # Parameters
+/// - `total_size`: Total file size
+/// - `n`: Number of slices
+///
+/// # 参数
+/// - `total_size`: 文件总大小
+/// - `n`: 分片数量
+///
+/// # Panics
+/// Panics if `n` is 0
+///
+/// # Panics
+/// 如果 `n` 为 0，则 panic
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::allocator::{sequential, ALIGNMENT};
+/// # use std::num::NonZeroU64;
+/// let slices = sequential::partition(NonZeroU64::new(ALIGNMENT * 4).unwrap(), 2);
+///
+/// let (slice, mut allocator) = slices.into_iter().nth(1).unwrap();
+/// assert_eq!(slice.start(), ALIGNMENT * 2);
+/// assert_eq!(allocator.allocate(NonZeroU64::new(1).unwrap()).unwrap().start(), ALIGNMENT * 2);
+/// ```
+pub fn partition(total_size: NonZeroU64, n: usize) -> Vec<(AllocatedRange, Allocator)> {
+    assert!(n > 0, "Cannot partition into 0 slices");
+
+    let total = total_size.get();
+    let slice_len = checked_align_up(total.div_ceil(n as u64)).unwrap_or(total);
+
+    (0..n as u64)
+        .map(|i| {
+            let start = (i * slice_len).min(total);
+            let end = start.saturating_add(slice_len).min(total);
+            // end > 0 because both slice_len and total are non-zero
+            // 由于 slice_len 和 total 都不为 0，end > 0
+            let end_nz = NonZeroU64::new(end).unwrap_or(total_size);
+            (
+                AllocatedRange::from_range_unchecked(start, end),
+                Allocator::starting_at(end_nz, start),
+            )
+        })
+        .collect()
+}
+
+/// Split a file into page-aligned ranges sized proportionally to `weights`
+///
+/// 将文件划分为按 `weights` 比例确定大小的页对齐范围
+///
+/// Returns one range per weight, in order, laid out back to back from offset 0. Every
+/// boundary between ranges is rounded down to a 4K boundary, and the last range absorbs the
+/// rounding remainder so the ranges always cover the whole file. A weight of 0 yields an
+/// empty range, as does a weight too small to earn a whole page when there are more weight
+/// units than pages. If all weights are 0, every range is empty.
+///
+/// 按顺序为每个权重返回一个范围，从偏移 0 开始首尾相接。范围之间的每个边界都会向下对齐到
+/// 4K 边界，最后一个范围吸收舍入剩余部分，因此这些范围总是覆盖整个文件。权重为 0 时得到空范围；
+/// 当权重单位多于页数时，过小而不足一整页的权重同样得到空范围。如果所有权重都为 0，则所有范围都为空。
+///
+/// # Parameters
+/// - `total_size`: Total file size
+/// - `weights`: Relative size of each range
+///
+/// # 参数
+/// - `total_size`: 文件总大小
+/// - `weights`: 每个范围的相对大小
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::allocator::{sequential, ALIGNMENT};
+/// # use std::num::NonZeroU64;
+/// let ranges = sequential::partition_weighted(NonZeroU64::new(ALIGNMENT * 4).unwrap(), &[1, 3]);
+/// assert_eq!(ranges[0].len(), ALIGNMENT);
+/// assert_eq!(ranges[1].len(), ALIGNMENT * 3);
+/// ```
+pub fn partition_weighted(total_size: NonZeroU64, weights: &[u32]) -> Vec<AllocatedRange> {
+    let total = total_size.get();
+    let sum: u64 = weights.iter().map(|&w| w as u64).sum();
+    if sum == 0 {
+        return vec![AllocatedRange::from_range_unchecked(0, 0); weights.len()];
+    }
+
+    let mut cumulative = 0u64;
+    let mut start = 0u64;
+    weights
+        .iter()
+        .enumerate()
+        .map(|(i, &weight)| {
+            cumulative += weight as u64;
+            let end = if i + 1 == weights.len() {
+                total
+            } else {
+                // total * cumulative / sum <= total, so the result fits in u64
+                // total * cumulative / sum <= total，因此结果能放入 u64
+                align_down((total as u128 * cumulative as u128 / sum as u128) as u64)
+            };
+            let range = AllocatedRange::from_range_unchecked(start, end);
+            start = end;
+            range
+        })
+        .collect()
 }
 
 #[cfg(test)]
@@ -237,9 +662,252 @@ mod tests {
         assert_eq!(allocator.next_pos(), ALIGNMENT * 2);
     }
 
+    #[test]
+    fn test_sequential_try_allocate() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+
+        assert_eq!(allocator.try_allocate(0), Err(AllocError::ZeroSize));
+
+        let range = allocator.try_allocate(100).unwrap();
+        assert_eq!(range.start(), 0);
+        assert_eq!(range.end(), ALIGNMENT);
+
+        // Partial allocation near the end: request 16384, only 8192 left
+        let range = allocator.try_allocate(ALIGNMENT * 4).unwrap();
+        assert_eq!(range.start(), ALIGNMENT);
+        assert_eq!(range.end(), ALIGNMENT * 3);
+
+        assert_eq!(
+            allocator.try_allocate(1),
+            Err(AllocError::Exhausted { remaining: 0 })
+        );
+    }
+
+    #[test]
+    fn test_sequential_peek_matches_allocate() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 3));
+
+        for size in [100, ALIGNMENT + 1, ALIGNMENT * 4] {
+            let peeked = allocator.peek(non_zero(size));
+            let pos = allocator.next_pos();
+
+            // Peeking twice doesn't advance the position
+            assert_eq!(allocator.peek(non_zero(size)), peeked);
+            assert_eq!(allocator.next_pos(), pos);
+
+            assert_eq!(allocator.allocate(non_zero(size)), peeked);
+        }
+
+        // Exhausted: both return None
+        assert!(allocator.peek(non_zero(1)).is_none());
+        assert!(allocator.allocate(non_zero(1)).is_none());
+    }
+
     #[test]
     fn test_sequential_total_size() {
         let allocator = Allocator::new(non_zero(12345));
         assert_eq!(allocator.total_size().get(), 12345);
     }
+
+    #[test]
+    fn test_sequential_partition() {
+        let slices = partition(non_zero(ALIGNMENT * 10), 4);
+        assert_eq!(slices.len(), 4);
+
+        // Slices are disjoint, page aligned and cover the whole file
+        let mut expected_start = 0;
+        for (slice, _) in &slices {
+            assert_eq!(slice.start(), expected_start);
+            assert_eq!(slice.start() % ALIGNMENT, 0);
+            expected_start = slice.end();
+        }
+        assert_eq!(expected_start, ALIGNMENT * 10);
+        assert_eq!(slices[0].0.len(), ALIGNMENT * 3);
+        assert_eq!(slices[3].0.len(), ALIGNMENT);
+
+        // Each allocator stays within its slice
+        for (slice, mut allocator) in slices {
+            let mut covered = slice.start();
+            while let Some(range) = allocator.allocate(non_zero(ALIGNMENT)) {
+                assert_eq!(range.start(), covered);
+                covered = range.end();
+            }
+            assert_eq!(covered, slice.end());
+        }
+    }
+
+    #[test]
+    fn test_sequential_partition_more_slices_than_pages() {
+        let slices = partition(non_zero(ALIGNMENT * 2), 4);
+
+        // Trailing slices are empty
+        let lens: Vec<u64> = slices.iter().map(|(slice, _)| slice.len()).collect();
+        assert_eq!(lens, vec![ALIGNMENT, ALIGNMENT, 0, 0]);
+        for (_, mut allocator) in slices.into_iter().skip(2) {
+            assert!(allocator.allocate(non_zero(1)).is_none());
+        }
+    }
+
+    #[test]
+    fn test_sequential_partition_weighted() {
+        let ranges = partition_weighted(non_zero(ALIGNMENT * 4), &[1, 2, 1]);
+        let spans: Vec<(u64, u64)> = ranges.iter().map(|r| r.as_range_tuple()).collect();
+        assert_eq!(
+            spans,
+            vec![(0, ALIGNMENT), (ALIGNMENT, ALIGNMENT * 3), (ALIGNMENT * 3, ALIGNMENT * 4)]
+        );
+    }
+
+    #[test]
+    fn test_sequential_partition_weighted_remainder() {
+        // Boundaries round down; the last range takes the unaligned tail
+        let ranges = partition_weighted(non_zero(ALIGNMENT * 3 + 100), &[1, 1]);
+        assert_eq!(ranges[0].as_range_tuple(), (0, ALIGNMENT));
+        assert_eq!(ranges[1].as_range_tuple(), (ALIGNMENT, ALIGNMENT * 3 + 100));
+    }
+
+    #[test]
+    fn test_sequential_partition_weighted_edge_cases() {
+        // Zero weights yield empty ranges
+        let ranges = partition_weighted(non_zero(ALIGNMENT * 4), &[0, 1, 0, 1]);
+        let lens: Vec<u64> = ranges.iter().map(|r| r.len()).collect();
+        assert_eq!(lens, vec![0, ALIGNMENT * 2, 0, ALIGNMENT * 2]);
+
+        // All weights zero
+        assert!(partition_weighted(non_zero(ALIGNMENT), &[0, 0]).iter().all(|r| r.is_empty()));
+        assert!(partition_weighted(non_zero(ALIGNMENT), &[]).is_empty());
+
+        // More weight units than pages: small weights get nothing, ranges stay contiguous
+        let ranges = partition_weighted(non_zero(ALIGNMENT * 2), &[1, 1, 1, 1, 1]);
+        let mut end = 0;
+        for range in &ranges {
+            assert_eq!(range.start(), end);
+            assert_eq!(range.start() % ALIGNMENT, 0);
+            end = range.end();
+        }
+        assert_eq!(end, ALIGNMENT * 2);
+    }
+
+    #[test]
+    fn test_sequential_allocate_bytes() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 2));
+
+        // Zero returns None without consuming space
+        assert!(allocator.allocate_bytes(0).is_none());
+        assert_eq!(allocator.next_pos(), 0);
+
+        let range = allocator.allocate_bytes(100).unwrap();
+        assert_eq!(range.as_range_tuple(), (0, ALIGNMENT));
+    }
+
+    #[test]
+    fn test_sequential_allocate_rest() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 10));
+
+        let header = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(header.as_range_tuple(), (0, ALIGNMENT));
+
+        let rest = allocator.allocate_rest().unwrap();
+        assert_eq!(rest.as_range_tuple(), (4096, 40960));
+        assert_eq!(allocator.remaining(), 0);
+
+        // Nothing left
+        assert!(allocator.allocate_rest().is_none());
+        assert!(allocator.allocate(non_zero(1)).is_none());
+    }
+
+    #[test]
+    fn test_sequential_allocate_huge() {
+        let mut allocator = Allocator::new(non_zero(HUGE_PAGE * 4));
+
+        let small = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(small.start(), 0);
+
+        // Start is rounded up to 2 MiB and the gap is skipped
+        let huge = allocator.allocate_huge(non_zero(HUGE_PAGE - 1)).unwrap();
+        assert_eq!(huge.start() % HUGE_PAGE, 0);
+        assert_eq!(huge.start(), HUGE_PAGE);
+        assert_eq!(huge.len(), HUGE_PAGE);
+        assert_eq!(allocator.next_pos(), HUGE_PAGE * 2);
+
+        // Regular allocations continue after the huge range
+        let next = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(next.start(), HUGE_PAGE * 2);
+    }
+
+    #[test]
+    fn test_sequential_allocate_huge_exhausted() {
+        let mut allocator = Allocator::new(non_zero(HUGE_PAGE + ALIGNMENT * 2));
+        allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+
+        // The tail after the aligned start is clamped to the total size
+        let tail = allocator.allocate_huge(non_zero(HUGE_PAGE)).unwrap();
+        assert_eq!(tail.start(), HUGE_PAGE);
+        assert_eq!(tail.end(), HUGE_PAGE + ALIGNMENT * 2);
+        assert!(allocator.allocate_huge(non_zero(1)).is_none());
+    }
+
+
+    #[test]
+    fn test_sequential_tail_commit() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 4));
+        allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+
+        // The tail covers everything unallocated and doesn't advance
+        let tail = allocator.tail().unwrap();
+        assert_eq!((tail.start(), tail.end()), (ALIGNMENT, ALIGNMENT * 4));
+        assert_eq!(allocator.tail(), Some(tail));
+
+        // Write 3000 bytes into the tail, then commit them
+        let start = allocator.next_pos();
+        allocator.commit(3000);
+        let next = allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert_eq!(next.start(), crate::allocator::align_up(start + 3000));
+
+        allocator.commit(allocator.remaining());
+        assert!(allocator.tail().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "only")]
+    fn test_sequential_commit_past_end() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT));
+        allocator.commit(ALIGNMENT + 1);
+    }
+
+
+    #[test]
+    fn test_sequential_bytes_round_trip() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 4 + 100));
+        allocator.allocate(non_zero(ALIGNMENT * 2)).unwrap();
+
+        let bytes = allocator.to_bytes();
+        assert_eq!(&bytes[..8], &(ALIGNMENT * 2).to_le_bytes());
+        assert_eq!(&bytes[8..], &(ALIGNMENT * 4 + 100).to_le_bytes());
+
+        let mut restored = Allocator::from_bytes(&bytes).unwrap();
+        assert_eq!(restored.next_pos(), allocator.next_pos());
+        assert_eq!(restored.total_size(), allocator.total_size());
+        assert_eq!(restored.allocate(non_zero(ALIGNMENT)), allocator.allocate(non_zero(ALIGNMENT)));
+
+        // An exhausted allocator round-trips too
+        allocator.allocate_rest().unwrap();
+        let exhausted = Allocator::from_bytes(&allocator.to_bytes()).unwrap();
+        assert_eq!(exhausted.remaining(), 0);
+    }
+
+    #[test]
+    fn test_sequential_from_bytes_rejects_corrupt_state() {
+        let mut bytes = [0u8; Allocator::ENCODED_LEN];
+        bytes[..8].copy_from_slice(&(ALIGNMENT * 2).to_le_bytes());
+        bytes[8..].copy_from_slice(&ALIGNMENT.to_le_bytes());
+        assert_eq!(
+            Allocator::from_bytes(&bytes).err(),
+            Some(AllocError::InvalidState { next_pos: ALIGNMENT * 2, total_size: ALIGNMENT })
+        );
+
+        // A zero total size is invalid as well
+        assert!(Allocator::from_bytes(&[0u8; Allocator::ENCODED_LEN]).is_err());
+    }
+
 }
@@ -0,0 +1,206 @@
+//! Mutex-guarded sequential range allocator implementation
+//!
+//! 基于互斥锁的顺序范围分配器实现
+
+use super::{sequential, ConcurrentRangeAllocator, RangeAllocator};
+use crate::file::range::AllocatedRange;
+use std::num::NonZeroU64;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// Sequential range allocator shared between threads through a mutex
+///
+/// 通过互斥锁在线程之间共享的顺序范围分配器
+///
+/// Wraps a [`sequential::Allocator`] in a [`Mutex`] so it can allocate through `&self` and
+/// be put in an `Arc`. Allocations are serialized, but keep exactly the sequential semantics,
+/// including handing out a short last range. For moderate concurrency this is simpler than
+/// [`concurrent::Allocator`](super::concurrent::Allocator); under heavy contention prefer the
+/// lock-free one.
+///
+/// 将 [`sequential::Allocator`] 包装在 [`Mutex`] 中，使其可以通过 `&self` 分配并放入 `Arc`。
+/// 分配是串行的，但完全保持顺序分配器的语义，包括分配较短的最后一个范围。
+/// 在中等并发下，这比 [`concurrent::Allocator`](super::concurrent::Allocator) 更简单；
+/// 在高度竞争时应优先使用无锁版本。
+///
+/// # Example
+///
+/// ```
+/// # use ranged_mmap::allocator::{shared::Allocator, RangeAllocator, ALIGNMENT};
+/// # use std::num::NonZeroU64;
+/// # use std::sync::Arc;
+/// let allocator = Arc::new(Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap()));
+///
+/// std::thread::scope(|s| {
+///     for _ in 0..2 {
+///         let allocator = Arc::clone(&allocator);
+///         s.spawn(move || allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap());
+///     }
+/// });
+/// assert_eq!(allocator.next_pos(), ALIGNMENT * 2);
+/// ```
+pub struct Allocator {
+    /// Sequential allocator doing the actual work
+    ///
+    /// 实际执行分配的顺序分配器
+    inner: Mutex<sequential::Allocator>,
+
+    /// Total file size
+    ///
+    /// 文件总大小
+    total_size: NonZeroU64,
+}
+
+impl Allocator {
+    /// Allocate a range of the specified size (4K aligned)
+    ///
+    /// 分配指定大小的范围（4K对齐）
+    ///
+    /// Same as [`sequential::Allocator::allocate`], serialized by the mutex.
+    ///
+    /// 与 [`sequential::Allocator::allocate`] 相同，由互斥锁串行化。
+    ///
+    /// # Parameters
+    /// - `size`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `size`: 要分配的字节数（会向上对齐到4K）
+    ///
+    /// # Returns
+    /// Returns `None` when no space is left, or when aligning `size` would overflow `u64`
+    ///
+    /// # 返回值
+    /// 当没有剩余空间，或对齐 `size` 会溢出 `u64` 时返回 `None`
+    #[inline]
+    pub fn allocate(&self, size: NonZeroU64) -> Option<AllocatedRange> {
+        self.lock().allocate(size)
+    }
+
+    /// Get the number of remaining allocatable bytes
+    ///
+    /// 获取剩余可分配字节数
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.lock().remaining()
+    }
+
+    /// Get the next allocation position
+    ///
+    /// 获取下一个分配位置
+    #[inline]
+    pub fn next_pos(&self) -> u64 {
+        self.lock().next_pos()
+    }
+
+    /// Unwrap into the inner sequential allocator
+    ///
+    /// 解包为内部的顺序分配器
+    #[inline]
+    pub fn into_inner(self) -> sequential::Allocator {
+        self.inner.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Lock the inner allocator
+    ///
+    /// 锁定内部分配器
+    ///
+    /// A panic while holding the lock can't leave the allocator inconsistent, so
+    /// poisoning is ignored.
+    ///
+    /// 持有锁时发生 panic 不会使分配器处于不一致状态，因此忽略锁中毒。
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, sequential::Allocator> {
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl RangeAllocator for Allocator {
+    #[inline]
+    fn new(total_size: NonZeroU64) -> Self {
+        Self {
+            inner: Mutex::new(sequential::Allocator::new(total_size)),
+            total_size,
+        }
+    }
+
+    #[inline]
+    fn total_size(&self) -> NonZeroU64 {
+        self.total_size
+    }
+
+    #[inline]
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        self.inner
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner)
+            .allocate(size)
+    }
+
+    #[inline]
+    fn remaining(&self) -> u64 {
+        Allocator::remaining(self)
+    }
+}
+
+impl ConcurrentRangeAllocator for Allocator {
+    #[inline]
+    fn allocate(&self, size: NonZeroU64) -> Option<AllocatedRange> {
+        Allocator::allocate(self, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::allocator::ALIGNMENT;
+    use super::*;
+    use std::sync::Arc;
+
+    fn non_zero(val: u64) -> NonZeroU64 {
+        NonZeroU64::new(val).unwrap()
+    }
+
+    #[test]
+    fn test_shared_allocate_matches_sequential() {
+        let shared = Allocator::new(non_zero(ALIGNMENT * 2 + 100));
+        let mut sequential = sequential::Allocator::new(non_zero(ALIGNMENT * 2 + 100));
+
+        for _ in 0..4 {
+            assert_eq!(shared.allocate(non_zero(ALIGNMENT)), sequential.allocate(non_zero(ALIGNMENT)));
+        }
+        assert_eq!(shared.remaining(), 0);
+        assert_eq!(shared.into_inner().next_pos(), sequential.next_pos());
+    }
+
+    #[test]
+    fn test_shared_allocate_multithreaded() {
+        const THREADS: u64 = 8;
+        const PER_THREAD: u64 = 32;
+        let allocator = Arc::new(Allocator::new(non_zero(ALIGNMENT * THREADS * PER_THREAD)));
+
+        let mut ranges: Vec<AllocatedRange> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|_| {
+                    let allocator = Arc::clone(&allocator);
+                    s.spawn(move || {
+                        let mut ranges = Vec::new();
+                        while let Some(range) = allocator.allocate(non_zero(ALIGNMENT)) {
+                            ranges.push(range);
+                        }
+                        ranges
+                    })
+                })
+                .collect();
+            handles.into_iter().flat_map(|h| h.join().unwrap()).collect()
+        });
+
+        // No overlaps and full coverage: sorted ranges are contiguous from 0 to the end
+        ranges.sort_by_key(|range| range.start());
+        assert_eq!(ranges.len() as u64, THREADS * PER_THREAD);
+        let mut expected = 0;
+        for range in &ranges {
+            assert_eq!(range.start(), expected);
+            expected = range.end();
+        }
+        assert_eq!(expected, allocator.total_size().get());
+        assert_eq!(allocator.remaining(), 0);
+    }
+}
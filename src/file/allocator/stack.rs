@@ -0,0 +1,286 @@
+//! Stack (LIFO) range allocator implementation
+//!
+//! 栈式（LIFO）范围分配器实现
+
+use super::{checked_align_up, RangeAllocator};
+use crate::file::range::AllocatedRange;
+use std::num::NonZeroU64;
+
+/// Position in a [`stack::Allocator`](Allocator) that can be rewound to
+///
+/// [`stack::Allocator`](Allocator) 中可以回退到的位置
+///
+/// Obtained from [`Allocator::marker`] and consumed by [`Allocator::rewind`].
+///
+/// 由 [`Allocator::marker`] 获取，供 [`Allocator::rewind`] 使用。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker {
+    /// Allocation position when the marker was taken
+    ///
+    /// 获取标记时的分配位置
+    pos: u64,
+}
+
+/// Stack (LIFO) range allocator with marker/rewind
+///
+/// 支持标记/回退的栈式（LIFO）范围分配器
+///
+/// Allocates sequentially like [`sequential::Allocator`](super::sequential::Allocator), and
+/// additionally lets callers take a [`Marker`] and later [`rewind`](Self::rewind) to it,
+/// freeing everything allocated since in one step. This is the classic frame allocator
+/// pattern for scoped scratch space in a file-backed arena.
+///
+/// 与 [`sequential::Allocator`](super::sequential::Allocator) 一样顺序分配，
+/// 并允许调用者获取 [`Marker`]，之后通过 [`rewind`](Self::rewind) 回退到该标记，
+/// 一次性释放之后分配的所有范围。这是文件支持的内存区中作用域临时空间的经典帧分配器模式。
+///
+/// # ⚠️ Overlap
+///
+/// Ranges allocated after a rewind reuse the offsets of the freed ranges. The caller must
+/// stop using every range allocated after the marker before rewinding, otherwise old and new
+/// ranges overlap. This is why [`rewind`](Self::rewind) is `unsafe`: the
+/// [`RangeAllocator`] contract, which the safe [`MmapFile`](crate::MmapFile) APIs rely on,
+/// promises non-overlapping ranges.
+///
+/// # ⚠️ 重叠
+///
+/// 回退之后分配的范围会复用已释放范围的偏移。调用者必须在回退之前停止使用标记之后分配的
+/// 所有范围，否则新旧范围会重叠。这正是 [`rewind`](Self::rewind) 为 `unsafe` 的原因：
+/// 安全的 [`MmapFile`](crate::MmapFile) API 所依赖的 [`RangeAllocator`] 约定保证范围不重叠。
+///
+/// # Example
+///
+/// ```
+/// # use ranged_mmap::allocator::{stack::Allocator, RangeAllocator, ALIGNMENT};
+/// # use std::num::NonZeroU64;
+/// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap());
+/// let size = NonZeroU64::new(ALIGNMENT).unwrap();
+///
+/// let marker = allocator.marker();
+/// let scratch = allocator.allocate(size).unwrap();
+///
+/// // Free the scratch range
+/// // 释放临时范围
+/// // Safety: the bytes of `scratch` are not read or written after the rewind
+/// // Safety: 回退之后不再读写 `scratch` 的字节
+/// unsafe { allocator.rewind(marker) };
+/// assert_eq!(allocator.allocate(size).unwrap().start(), scratch.start());
+/// ```
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Allocator {
+    /// Next allocation position
+    ///
+    /// 下一个分配位置
+    next_pos: u64,
+
+    /// Total file size
+    ///
+    /// 文件总大小
+    total_size: NonZeroU64,
+}
+
+impl Allocator {
+    /// Allocate a range of the specified size (4K aligned)
+    ///
+    /// 分配指定大小的范围（4K对齐）
+    ///
+    /// Same behavior as [`sequential::Allocator::allocate`](super::sequential::Allocator::allocate):
+    /// when remaining space is less than the aligned requested size, allocates all remaining
+    /// space. Returns `None` when no space is left or the aligned size overflows `u64`.
+    ///
+    /// 行为与 [`sequential::Allocator::allocate`](super::sequential::Allocator::allocate) 相同：
+    /// 当剩余空间小于对齐后的请求大小时，分配所有剩余空间。当没有剩余空间或对齐后的大小溢出 `u64` 时返回 `None`。
+    #[inline]
+    pub fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        let remaining = self.total_size.get().saturating_sub(self.next_pos);
+        if remaining == 0 {
+            return None;
+        }
+
+        let start = self.next_pos;
+        let end = start + checked_align_up(size.get())?.min(remaining);
+        self.next_pos = end;
+        trace_event!(start, len = end - start, "allocate");
+
+        Some(AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    /// Allocate a range from a plain byte count
+    ///
+    /// 按普通字节数分配范围
+    ///
+    /// Same as [`allocate`](Self::allocate), but takes a `u64` and returns `None` for 0 instead of
+    /// requiring the caller to build a [`NonZeroU64`].
+    ///
+    /// 与 [`allocate`](Self::allocate) 相同，但接受 `u64`，并在为 0 时返回 `None`，
+    /// 而不需要调用者构造 [`NonZeroU64`]。
+    ///
+    /// # Parameters
+    /// - `bytes`: Number of bytes to allocate (will be aligned to 4K)
+    ///
+    /// # 参数
+    /// - `bytes`: 要分配的字节数（会向上对齐到4K）
+    #[inline]
+    pub fn allocate_bytes(&mut self, bytes: u64) -> Option<AllocatedRange> {
+        self.allocate(NonZeroU64::new(bytes)?)
+    }
+
+    /// Take a marker at the current allocation position
+    ///
+    /// 在当前分配位置获取标记
+    #[inline]
+    pub fn marker(&self) -> Marker {
+        Marker { pos: self.next_pos }
+    }
+
+    /// Rewind to a marker, freeing everything allocated since
+    ///
+    /// 回退到标记，释放之后分配的所有范围
+    ///
+    /// Markers must be rewound in LIFO order. A marker taken after the current position
+    /// (i.e. from a frame that was already rewound) is ignored.
+    ///
+    /// 标记必须按 LIFO 顺序回退。晚于当前位置的标记（即来自已经回退的帧）会被忽略。
+    ///
+    /// # Safety
+    ///
+    /// No range allocated after `marker` may be read or written (by any thread or clone of
+    /// the file) once it has been rewound, since later allocations hand the same offsets
+    /// out again and the safe [`MmapFile`](crate::MmapFile) APIs assume ranges never overlap.
+    ///
+    /// # Safety
+    ///
+    /// 回退之后，不得再（由任何线程或文件的任何克隆）读写 `marker` 之后分配的任何范围，
+    /// 因为之后的分配会再次分配相同的偏移，而安全的 [`MmapFile`](crate::MmapFile) API 假定范围永不重叠。
+    ///
+    /// # Parameters
+    /// - `marker`: Marker obtained from [`marker`](Self::marker)
+    ///
+    /// # 参数
+    /// - `marker`: 由 [`marker`](Self::marker) 获取的标记
+    #[inline]
+    pub unsafe fn rewind(&mut self, marker: Marker) {
+        self.next_pos = self.next_pos.min(marker.pos);
+    }
+
+    /// Get the number of remaining allocatable bytes
+    ///
+    /// 获取剩余可分配字节数
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.total_size.get().saturating_sub(self.next_pos)
+    }
+
+    /// Get the next allocation position
+    ///
+    /// 获取下一个分配位置
+    #[inline]
+    pub fn next_pos(&self) -> u64 {
+        self.next_pos
+    }
+}
+
+impl RangeAllocator for Allocator {
+    #[inline]
+    fn new(total_size: NonZeroU64) -> Self {
+        Self {
+            next_pos: 0,
+            total_size,
+        }
+    }
+
+    #[inline]
+    fn total_size(&self) -> NonZeroU64 {
+        self.total_size
+    }
+
+    #[inline]
+    fn allocate(&mut self, size: NonZeroU64) -> Option<AllocatedRange> {
+        Allocator::allocate(self, size)
+    }
+
+    #[inline]
+    fn remaining(&self) -> u64 {
+        Allocator::remaining(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::allocator::ALIGNMENT;
+    use super::*;
+
+    fn non_zero(val: u64) -> NonZeroU64 {
+        NonZeroU64::new(val).unwrap()
+    }
+
+    #[test]
+    fn test_stack_rewind_reuses_offsets() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 10));
+
+        let _first = allocator.allocate(non_zero(100)).unwrap();
+        let marker = allocator.marker();
+        let second = allocator.allocate(non_zero(ALIGNMENT * 2)).unwrap();
+        let _third = allocator.allocate(non_zero(100)).unwrap();
+        assert_eq!(allocator.next_pos(), ALIGNMENT * 4);
+
+        // Rewind to after the first allocation
+        unsafe { allocator.rewind(marker) };
+        assert_eq!(allocator.next_pos(), ALIGNMENT);
+
+        // The next allocation reuses the second range's offset
+        let reused = allocator.allocate(non_zero(100)).unwrap();
+        assert_eq!(reused.start(), second.start());
+    }
+
+    #[test]
+    fn test_stack_nested_markers() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 10));
+
+        let outer = allocator.marker();
+        allocator.allocate(non_zero(100)).unwrap();
+        let inner = allocator.marker();
+        allocator.allocate(non_zero(100)).unwrap();
+
+        unsafe { allocator.rewind(inner) };
+        assert_eq!(allocator.next_pos(), ALIGNMENT);
+        unsafe { allocator.rewind(outer) };
+        assert_eq!(allocator.next_pos(), 0);
+        assert_eq!(allocator.remaining(), ALIGNMENT * 10);
+    }
+
+    #[test]
+    fn test_stack_exhausted() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT));
+        let marker = allocator.marker();
+
+        allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        assert!(allocator.allocate(non_zero(1)).is_none());
+
+        // Rewinding frees space again
+        unsafe { allocator.rewind(marker) };
+        assert!(allocator.allocate(non_zero(1)).is_some());
+    }
+
+    #[test]
+    fn test_stack_allocate_bytes() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT));
+        assert!(allocator.allocate_bytes(0).is_none());
+        assert_eq!(allocator.allocate_bytes(1).unwrap().len(), ALIGNMENT);
+    }
+
+    #[test]
+    fn test_stack_rewind_ignores_later_marker() {
+        let mut allocator = Allocator::new(non_zero(ALIGNMENT * 4));
+
+        let outer = allocator.marker();
+        allocator.allocate(non_zero(ALIGNMENT)).unwrap();
+        let inner = allocator.marker();
+
+        // Rewinding the outer frame first makes the inner marker stale
+        unsafe { allocator.rewind(outer) };
+        unsafe { allocator.rewind(inner) };
+        assert_eq!(allocator.next_pos(), 0);
+    }
+}
@@ -0,0 +1,165 @@
+//! `tokio::io::AsyncWrite` adapter over an allocated range
+//!
+//! 基于已分配范围的 `tokio::io::AsyncWrite` 适配器
+
+use super::error::Result;
+use super::mmap_file_inner::MmapFileInner;
+use super::range::{AllocatedRange, WriteReceipt};
+use std::io;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::io::AsyncWrite;
+use tokio::task::JoinHandle;
+
+/// Async writer that fills an [`AllocatedRange`] from the start
+///
+/// 从起始位置开始填充 [`AllocatedRange`] 的异步写入器
+///
+/// Created by [`MmapFile::async_range_writer`](super::MmapFile::async_range_writer), available
+/// with the `tokio` feature. Writes never block: `poll_write` copies straight into the
+/// mapping and always completes immediately, returning `Ok(0)` once the range is full.
+/// `poll_flush` and `poll_shutdown` synchronously flush the bytes written so far on tokio's
+/// blocking thread pool, so they must be called from within a tokio runtime.
+///
+/// 由 [`MmapFile::async_range_writer`](super::MmapFile::async_range_writer) 创建，需要启用 `tokio` 特性。
+/// 写入永远不会阻塞：`poll_write` 直接拷贝到映射中并总是立即完成，范围写满后返回 `Ok(0)`。
+/// `poll_flush` 和 `poll_shutdown` 在 tokio 的阻塞线程池中同步刷新目前已写入的字节，
+/// 因此必须在 tokio 运行时中调用。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{MmapFile, allocator::ALIGNMENT};
+/// # use tempfile::tempdir;
+/// # use tokio::io::AsyncWriteExt;
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("async.bin");
+/// # use std::num::NonZeroU64;
+/// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
+/// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+///
+/// let mut writer = file.async_range_writer(range);
+/// writer.write_all(&[1u8; ALIGNMENT as usize]).await?;
+/// writer.flush().await?;
+///
+/// let receipt = writer.into_receipt().unwrap();
+/// assert_eq!(receipt.len(), ALIGNMENT);
+/// # Ok(())
+/// # }
+/// ```
+pub struct AsyncRangeWriter {
+    /// Handle to the mapping, kept alive by the writer
+    ///
+    /// 映射的句柄，由写入器保持存活
+    file: MmapFileInner,
+
+    /// Range being filled
+    ///
+    /// 正在填充的范围
+    range: AllocatedRange,
+
+    /// Bytes written so far, from the start of the range
+    ///
+    /// 从范围起始位置开始迄今已写入的字节数
+    written: u64,
+
+    /// Flush running on the blocking pool, if any
+    ///
+    /// 正在阻塞线程池中运行的刷新（如有）
+    flushing: Option<JoinHandle<Result<()>>>,
+}
+
+impl AsyncRangeWriter {
+    /// Create a writer for `range`
+    ///
+    /// 为 `range` 创建写入器
+    #[inline]
+    pub(crate) fn new(file: MmapFileInner, range: AllocatedRange) -> Self {
+        Self {
+            file,
+            range,
+            written: 0,
+            flushing: None,
+        }
+    }
+
+    /// Get the number of bytes written so far
+    ///
+    /// 获取迄今已写入的字节数
+    #[inline]
+    pub fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// Consume the writer and get a receipt if the whole range was written
+    ///
+    /// 消耗写入器，如果整个范围都已写入则返回凭据
+    ///
+    /// Returns `None` if fewer than `range.len()` bytes were written.
+    ///
+    /// 如果写入的字节数少于 `range.len()`，返回 `None`。
+    #[inline]
+    pub fn into_receipt(self) -> Option<WriteReceipt> {
+        (self.written == self.range.len()).then(|| WriteReceipt::new(self.range))
+    }
+
+    /// Start a flush of the written prefix if none is running, then poll it
+    ///
+    /// 如果没有正在运行的刷新，则开始刷新已写入的前缀，然后轮询它
+    fn poll_sync(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        if self.written == 0 {
+            return Poll::Ready(Ok(()));
+        }
+
+        let handle = self.flushing.get_or_insert_with(|| {
+            let file = self.file.clone();
+            let (start, len) = (self.range.start(), self.written as usize);
+            // Safety: the range belongs to this writer; msync never reads it through Rust
+            // references, so a write racing with the flush is at worst not included in it
+            // Safety: 该范围属于此写入器；msync 不会通过 Rust 引用读取它，
+            // 因此与刷新同时发生的写入最多只是不会被包含在本次刷新中
+            tokio::task::spawn_blocking(move || unsafe { file.sync_range(start, len) })
+        });
+
+        let result = ready!(Pin::new(handle).poll(cx));
+        self.flushing = None;
+
+        Poll::Ready(match result {
+            Ok(result) => result.map_err(io::Error::from),
+            Err(err) => Err(io::Error::other(err)),
+        })
+    }
+}
+
+impl AsyncWrite for AsyncRangeWriter {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let remaining = this.range.len() - this.written;
+        let len = (buf.len() as u64).min(remaining) as usize;
+
+        if len > 0 {
+            // Safety: [start + written, start + written + len) lies inside the allocated
+            // range, which no other writer can touch
+            // Safety: [start + written, start + written + len) 位于已分配范围内，其他写入者无法访问
+            unsafe { this.file.write_at(this.range.start() + this.written, &buf[..len]) };
+            this.file.mark_dirty(this.range.start() + this.written, len as u64);
+            this.written += len as u64;
+        }
+
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_sync(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_sync(cx)
+    }
+}
@@ -0,0 +1,200 @@
+//! Write coalescing buffer for many small writes
+//!
+//! 用于大量小写入的写合并缓冲区
+
+use super::error::{Error, Result};
+use super::mmap_file_inner::MmapFileInner;
+use super::range::{AllocatedRange, WriteReceipt};
+use std::ops::Range;
+
+/// Buffer that coalesces many small writes into few copies
+///
+/// 将大量小写入合并为少量拷贝的缓冲区
+///
+/// Accumulates `(offset, data)` writes in one buffer and applies them on
+/// [`commit`](Self::commit): writes are sorted by offset and contiguous or overlapping ones
+/// are grouped into a single span. Each span is bounds-checked and reported once, and its
+/// writes are copied straight into the mapping. For workloads issuing thousands of tiny
+/// updates this avoids a bounds check, an allocation and a watermark update per write.
+///
+/// 在一个缓冲区中累积 `(offset, data)` 写入，并在 [`commit`](Self::commit) 时应用：
+/// 写入按偏移排序，连续或重叠的写入会归入同一个区间。每个区间只检查一次边界并只报告一次，
+/// 其中的写入直接拷贝到映射中。对于发出成千上万次细粒度更新的场景，
+/// 这避免了每次写入都进行边界检查、内存分配和水位线更新。
+///
+/// Overlapping writes behave as if applied in the order they were added: the last one wins.
+///
+/// 重叠的写入按添加顺序生效：后添加的写入覆盖先添加的。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{BatchedWriter, MmapFileInner, Result};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("batched.bin");
+/// # use std::num::NonZeroU64;
+/// let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap())?;
+///
+/// let mut batch = BatchedWriter::new(&file);
+/// for i in 0..16u64 {
+///     batch.write(i * 8, i.to_le_bytes());
+/// }
+///
+/// // Safety: no other thread accesses [0, 128)
+/// // Safety: 没有其他线程访问 [0, 128)
+/// let receipts = unsafe { batch.commit()? };
+/// assert_eq!(receipts.len(), 1);
+/// assert_eq!(receipts[0].end(), 128);
+/// # Ok(())
+/// # }
+/// ```
+pub struct BatchedWriter<'a> {
+    /// File the writes are applied to
+    ///
+    /// 写入应用的目标文件
+    file: &'a MmapFileInner,
+
+    /// Pending writes in insertion order, as file offset and range in `data`
+    ///
+    /// 按插入顺序排列的待处理写入，包含文件偏移以及在 `data` 中的范围
+    writes: Vec<(u64, Range<usize>)>,
+
+    /// Bytes of all pending writes, back to back
+    ///
+    /// 所有待处理写入的字节，首尾相接
+    data: Vec<u8>,
+}
+
+impl<'a> BatchedWriter<'a> {
+    /// Create an empty batch for `file`
+    ///
+    /// 为 `file` 创建空的批次
+    #[inline]
+    pub fn new(file: &'a MmapFileInner) -> Self {
+        Self {
+            file,
+            writes: Vec::new(),
+            data: Vec::new(),
+        }
+    }
+
+    /// Queue a write of `data` at `offset`
+    ///
+    /// 将在 `offset` 处写入 `data` 加入队列
+    ///
+    /// # Parameters
+    /// - `offset`: Write position (byte offset from file start)
+    /// - `data`: Data to write
+    ///
+    /// # 参数
+    /// - `offset`: 写入位置（从文件开头的字节偏移）
+    /// - `data`: 要写入的数据
+    #[inline]
+    pub fn write(&mut self, offset: u64, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        if !data.is_empty() {
+            let at = self.data.len();
+            self.data.extend_from_slice(data);
+            self.writes.push((offset, at..self.data.len()));
+        }
+    }
+
+    /// Get the number of queued writes
+    ///
+    /// 获取队列中的写入数
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.writes.len()
+    }
+
+    /// Check if no writes are queued
+    ///
+    /// 检查队列是否为空
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /// Apply all queued writes, merging contiguous ones
+    ///
+    /// 应用所有排队的写入，并合并连续的写入
+    ///
+    /// All spans are bounds-checked before anything is copied. On success the batch is
+    /// emptied and can be reused.
+    ///
+    /// 在拷贝任何数据之前会先检查所有区间的边界。成功后批次被清空，可以复用。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`MmapFileInner::write_at`] for every merged span.
+    ///
+    /// # Safety
+    ///
+    /// 每个合并区间都需满足与 [`MmapFileInner::write_at`] 相同的要求。
+    ///
+    /// # Returns
+    /// One [`WriteReceipt`] per merged span, sorted by offset
+    ///
+    /// # 返回值
+    /// 每个合并区间一个 [`WriteReceipt`]，按偏移排序
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if any write exceeds the file size; nothing is written
+    ///
+    /// # Errors
+    /// 如果任何写入超出文件大小，返回 `OutOfBounds` 错误，且不写入任何数据
+    pub unsafe fn commit(&mut self) -> Result<Vec<WriteReceipt>> {
+        let size = self.file.size().get();
+
+        // Sort indices by offset, then group overlapping or touching writes into spans
+        // 按偏移排序索引，然后将重叠或相邻的写入分组为区间
+        let mut order: Vec<usize> = (0..self.writes.len()).collect();
+        order.sort_by_key(|&i| self.writes[i].0);
+
+        let mut spans: Vec<(u64, u64, Range<usize>)> = Vec::new();
+        for (at, &i) in order.iter().enumerate() {
+            let (offset, ref data) = self.writes[i];
+            let len = data.len() as u64;
+            let Some(end) = offset.checked_add(len) else {
+                return Err(Error::OutOfBounds { offset, len, size });
+            };
+            match spans.last_mut() {
+                Some((_, span_end, members)) if offset <= *span_end => {
+                    *span_end = (*span_end).max(end);
+                    members.end = at + 1;
+                }
+                _ => spans.push((offset, end, at..at + 1)),
+            }
+        }
+
+        for &(start, end, _) in &spans {
+            if end > size {
+                return Err(Error::OutOfBounds { offset: start, len: end - start, size });
+            }
+        }
+
+        let base = unsafe { self.file.as_mut_ptr() };
+        let mut receipts = Vec::with_capacity(spans.len());
+        for (start, end, members) in spans {
+            // Apply in insertion order so the last write wins where writes overlap
+            // 按插入顺序应用，使重叠处最后的写入生效
+            let members = &mut order[members];
+            members.sort_unstable();
+            for &i in members.iter() {
+                let (offset, ref data) = self.writes[i];
+                let data = &self.data[data.clone()];
+                // Safety: the span, and so this write, was checked to lie within the file
+                // Safety: 已检查区间（因而此写入）位于文件之内
+                unsafe { std::ptr::copy_nonoverlapping(data.as_ptr(), base.add(offset as usize), data.len()) };
+            }
+            self.file.mark_written(end);
+            receipts.push(WriteReceipt::new(AllocatedRange::from_range_unchecked(start, end)));
+        }
+
+        self.writes.clear();
+        self.data.clear();
+        Ok(receipts)
+    }
+}
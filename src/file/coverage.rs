@@ -0,0 +1,112 @@
+//! Write coverage helpers
+//!
+//! 写入覆盖率辅助工具
+
+use super::range::WriteReceipt;
+use std::ops::Range;
+
+/// Find the regions of `[0, total_size)` not covered by any receipt
+///
+/// 查找 `[0, total_size)` 中未被任何凭据覆盖的区域
+///
+/// Returns the complement of the union of the receipt ranges, sorted and non-overlapping.
+/// Useful to check that every worker of a parallel write finished before declaring the
+/// file complete. Receipts may be given in any order and may overlap.
+///
+/// 返回凭据范围并集的补集，按顺序排列且互不重叠。
+/// 可用于在宣布文件完成之前检查并行写入的每个 worker 是否都已完成。凭据可以是任意顺序，也可以重叠。
+///
+/// # Parameters
+/// - `total_size`: Size of the file in bytes
+/// - `receipts`: Receipts of the ranges written so far
+///
+/// # 参数
+/// - `total_size`: 文件大小（字节）
+/// - `receipts`: 迄今为止已写入范围的凭据
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{MmapFile, Result, allocator::ALIGNMENT, coverage::unwritten_gaps};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("output.bin");
+/// # use std::num::NonZeroU64;
+/// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 3).unwrap())?;
+///
+/// let first = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+/// let _missed = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+/// let last = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+///
+/// let receipts = [
+///     file.write_range(first, [0u8; ALIGNMENT as usize]),
+///     file.write_range(last, [0u8; ALIGNMENT as usize]),
+/// ];
+///
+/// assert_eq!(unwritten_gaps(file.size().get(), &receipts), vec![ALIGNMENT..ALIGNMENT * 2]);
+/// # Ok(())
+/// # }
+/// ```
+pub fn unwritten_gaps(total_size: u64, receipts: &[WriteReceipt]) -> Vec<Range<u64>> {
+    let mut sorted = receipts.to_vec();
+    sorted.sort_unstable_by_key(|receipt| receipt.start());
+
+    let mut gaps = Vec::new();
+    let mut covered = 0;
+
+    for receipt in sorted {
+        let start = receipt.start().min(total_size);
+        if start > covered {
+            gaps.push(covered..start);
+        }
+        covered = covered.max(receipt.end().min(total_size));
+    }
+
+    if covered < total_size {
+        gaps.push(covered..total_size);
+    }
+
+    gaps
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::allocator::ALIGNMENT;
+    use super::super::range::AllocatedRange;
+
+    fn receipt(start: u64, end: u64) -> WriteReceipt {
+        WriteReceipt::new(AllocatedRange::from_range_unchecked(start, end))
+    }
+
+    #[test]
+    fn test_unwritten_gaps_full_coverage() {
+        // Out of order and overlapping receipts that cover everything
+        let receipts = [
+            receipt(ALIGNMENT * 2, ALIGNMENT * 4),
+            receipt(0, ALIGNMENT),
+            receipt(ALIGNMENT, ALIGNMENT * 3),
+        ];
+        assert!(unwritten_gaps(ALIGNMENT * 4, &receipts).is_empty());
+    }
+
+    #[test]
+    fn test_unwritten_gaps_two_missing() {
+        let receipts = [
+            receipt(ALIGNMENT * 3, ALIGNMENT * 4),
+            receipt(0, ALIGNMENT),
+            receipt(ALIGNMENT * 2, ALIGNMENT * 3),
+        ];
+        assert_eq!(
+            unwritten_gaps(ALIGNMENT * 6, &receipts),
+            vec![ALIGNMENT..ALIGNMENT * 2, ALIGNMENT * 4..ALIGNMENT * 6]
+        );
+    }
+
+    #[test]
+    fn test_unwritten_gaps_no_receipts() {
+        assert_eq!(unwritten_gaps(ALIGNMENT, &[]), vec![0..ALIGNMENT]);
+        assert!(unwritten_gaps(0, &[]).is_empty());
+    }
+}
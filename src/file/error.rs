@@ -4,6 +4,7 @@
 
 use std::fmt;
 use std::io;
+use std::ops::Range;
 
 /// Error type for ranged-mmap operations
 /// 
@@ -28,6 +29,109 @@ pub enum Error {
         range_len: u64,
     },
 
+    /// Mapping is not writable
+    /// 
+    /// 映射不可写
+    ReadOnlyMapping,
+
+    /// Data too large for range
+    /// 
+    /// 数据超出范围长度
+    DataTooLarge {
+        data_len: usize,
+        range_len: u64,
+    },
+
+    /// Region exceeds file size
+    /// 
+    /// 区域超出文件大小
+    OutOfBounds {
+        offset: u64,
+        len: u64,
+        size: u64,
+    },
+
+    /// Scattered piece does not fit within its range
+    /// 
+    /// 分散写入的片段超出其范围
+    PieceOutOfRange {
+        offset: u64,
+        len: usize,
+        range_len: u64,
+    },
+
+    /// Scattered pieces overlap each other
+    /// 
+    /// 分散写入的片段相互重叠
+    OverlappingPieces {
+        offset: u64,
+    },
+
+    /// Backing storage ran out of space during a mapped write
+    /// 
+    /// 映射写入期间后备存储空间不足
+    StorageFull {
+        offset: u64,
+        len: usize,
+    },
+
+    /// Two mappings being compared have different sizes
+    /// 
+    /// 比较的两个映射大小不同
+    SizeMismatch {
+        size: u64,
+        other: u64,
+    },
+
+    /// Requested read length exceeds the file size
+    /// 
+    /// 请求的读取长度超出文件大小
+    ReadLengthTooLarge {
+        len: usize,
+        size: u64,
+    },
+
+    /// Operation requires the last handle, but other clones are alive
+    /// 
+    /// 操作需要最后一个句柄，但仍有其他克隆存活
+    MappingInUse {
+        clones: usize,
+    },
+
+    /// Mapping is not backed by a named file
+    /// 
+    /// 映射不由具名文件支持
+    NoBackingPath,
+
+    /// File size exceeds the address space of this platform
+    /// 
+    /// 文件大小超出当前平台的地址空间
+    FileTooLargeForPlatform {
+        size: u64,
+        max: usize,
+    },
+
+    /// Receipts do not cover the whole file
+    /// 
+    /// 凭据未覆盖整个文件
+    IncompleteWrite {
+        gaps: Vec<Range<u64>>,
+    },
+
+    /// Mapping was not created or opened with a completion marker header
+    /// 
+    /// 映射创建或打开时没有完成标记头部
+    NoCompletionMarker,
+
+    /// Operation is not supported on a copy-on-write mapping, whose private changes it would lose
+    /// 
+    /// 写时复制映射不支持该操作，因为它会丢失映射的私有修改
+    CopyOnWriteMapping,
+
+    /// Computing the requested size overflows `u64`
+    /// 
+    /// 计算请求的大小时溢出 `u64`
+    SizeOverflow,
 }
 
 impl fmt::Display for Error {
@@ -42,6 +146,86 @@ impl fmt::Display for Error {
                     buffer_len, range_len, buffer_len, range_len
                 )
             }
+            Error::ReadOnlyMapping => write!(f, "Mapping is not writable / 映射不可写"),
+            Error::DataTooLarge { data_len, range_len } => {
+                write!(
+                    f,
+                    "Data length {} exceeds range length {} / 数据长度 {} 超出范围长度 {}",
+                    data_len, range_len, data_len, range_len
+                )
+            }
+            Error::OutOfBounds { offset, len, size } => {
+                write!(
+                    f,
+                    "Region [{}, +{}) exceeds file size {} / 区域 [{}, +{}) 超出文件大小 {}",
+                    offset, len, size, offset, len, size
+                )
+            }
+            Error::PieceOutOfRange { offset, len, range_len } => {
+                write!(
+                    f,
+                    "Piece [{}, +{}) exceeds range length {} / 片段 [{}, +{}) 超出范围长度 {}",
+                    offset, len, range_len, offset, len, range_len
+                )
+            }
+            Error::OverlappingPieces { offset } => {
+                write!(
+                    f,
+                    "Pieces overlap at relative offset {} / 片段在相对偏移 {} 处重叠",
+                    offset, offset
+                )
+            }
+            Error::StorageFull { offset, len } => {
+                write!(
+                    f,
+                    "Storage full while writing [{}, +{}) / 写入 [{}, +{}) 时存储空间不足",
+                    offset, len, offset, len
+                )
+            }
+            Error::SizeMismatch { size, other } => {
+                write!(
+                    f,
+                    "Size {} does not match size {} / 大小 {} 与大小 {} 不一致",
+                    size, other, size, other
+                )
+            }
+            Error::ReadLengthTooLarge { len, size } => {
+                write!(
+                    f,
+                    "Read length {} exceeds file size {} / 读取长度 {} 超出文件大小 {}",
+                    len, size, len, size
+                )
+            }
+            Error::MappingInUse { clones } => {
+                write!(
+                    f,
+                    "Mapping is still used by {} other clone(s) / 映射仍被 {} 个其他克隆使用",
+                    clones, clones
+                )
+            }
+            Error::NoBackingPath => write!(f, "Mapping has no backing path / 映射没有底层文件路径"),
+            Error::FileTooLargeForPlatform { size, max } => {
+                write!(
+                    f,
+                    "File size {} exceeds the platform limit {} / 文件大小 {} 超出平台上限 {}",
+                    size, max, size, max
+                )
+            }
+            Error::IncompleteWrite { gaps } => match gaps.first() {
+                Some(first) => write!(
+                    f,
+                    "Incomplete write: {} unwritten gap(s), first [{}, {}) / 写入不完整：{} 个未写入的空洞，首个为 [{}, {})",
+                    gaps.len(), first.start, first.end, gaps.len(), first.start, first.end
+                ),
+                None => write!(f, "Incomplete write / 写入不完整"),
+            },
+            Error::NoCompletionMarker => {
+                write!(f, "Mapping has no completion marker header / 映射没有完成标记头部")
+            }
+            Error::CopyOnWriteMapping => {
+                write!(f, "Operation not supported on a copy-on-write mapping / 写时复制映射不支持该操作")
+            }
+            Error::SizeOverflow => write!(f, "Requested size overflows u64 / 请求的大小溢出 u64"),
         }
     }
 }
@@ -72,7 +256,22 @@ impl From<Error> for io::Error {
         match err {
             Error::Io(io_err) => io_err,
             Error::EmptyFile => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
-            Error::BufferTooSmall { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+            Error::BufferTooSmall { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::ReadOnlyMapping => io::Error::new(io::ErrorKind::PermissionDenied, err.to_string()),
+            Error::DataTooLarge { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::OutOfBounds { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::PieceOutOfRange { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::OverlappingPieces { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::StorageFull { .. } => io::Error::new(io::ErrorKind::StorageFull, err.to_string()),
+            Error::SizeMismatch { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::ReadLengthTooLarge { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::MappingInUse { .. } => io::Error::new(io::ErrorKind::ResourceBusy, err.to_string()),
+            Error::NoBackingPath => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::FileTooLargeForPlatform { .. } => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::IncompleteWrite { .. } => io::Error::new(io::ErrorKind::InvalidData, err.to_string()),
+            Error::NoCompletionMarker => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
+            Error::CopyOnWriteMapping => io::Error::new(io::ErrorKind::Unsupported, err.to_string()),
+            Error::SizeOverflow => io::Error::new(io::ErrorKind::InvalidInput, err.to_string()),
         }
     }
 }
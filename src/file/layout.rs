@@ -0,0 +1,111 @@
+//! Header + data layout helper
+//!
+//! 头部 + 数据布局辅助工具
+
+use super::allocator::{checked_align_up, sequential};
+use super::error::{Error, Result};
+use super::range::AllocatedRange;
+use std::num::NonZeroU64;
+
+/// Builder for the common "fixed header, then data" file layout
+///
+/// 常见的"固定头部 + 数据"文件布局的构建器
+///
+/// Reserves a page-aligned header at the start of the file and returns an allocator for
+/// the remaining data space, so the header never overlaps data ranges.
+///
+/// 在文件开头保留一个页对齐的头部，并返回用于剩余数据空间的分配器，
+/// 从而保证头部永远不会与数据范围重叠。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{LayoutBuilder, MmapFile, Result, allocator::ALIGNMENT};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("format.bin");
+/// # use std::num::NonZeroU64;
+/// let size = NonZeroU64::new(ALIGNMENT * 4).unwrap();
+/// let (file, _) = MmapFile::create_default(&path, size)?;
+///
+/// // 100-byte header, rounded up to one page
+/// // 100 字节的头部，向上取整为一页
+/// let (header, mut data) = LayoutBuilder::new(size).header(100).build()?;
+/// assert_eq!(header.len(), ALIGNMENT);
+///
+/// let range = data.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+/// assert_eq!(range.start(), ALIGNMENT);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct LayoutBuilder {
+    /// Total file size
+    ///
+    /// 文件总大小
+    total_size: NonZeroU64,
+
+    /// Requested header length before alignment
+    ///
+    /// 对齐前请求的头部长度
+    header_len: u64,
+}
+
+impl LayoutBuilder {
+    /// Create a builder for a file of `total_size` bytes, with no header
+    ///
+    /// 为 `total_size` 字节的文件创建构建器，默认没有头部
+    #[inline]
+    pub fn new(total_size: NonZeroU64) -> Self {
+        Self {
+            total_size,
+            header_len: 0,
+        }
+    }
+
+    /// Reserve a header of `len` bytes (rounded up to 4K)
+    ///
+    /// 保留 `len` 字节的头部（向上对齐到4K）
+    #[inline]
+    pub fn header(mut self, len: u64) -> Self {
+        self.header_len = len;
+        self
+    }
+
+    /// Build the layout
+    ///
+    /// 构建布局
+    ///
+    /// # Returns
+    /// `(header_range, data_allocator)`, where the data allocator starts right after the
+    /// aligned header
+    ///
+    /// # 返回值
+    /// `(header_range, data_allocator)`，其中数据分配器从对齐后的头部之后开始
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if the aligned header doesn't fit in the file or aligning
+    /// the header length overflows `u64`
+    ///
+    /// # Errors
+    /// 如果对齐后的头部超出文件大小或对齐头部长度时溢出 `u64`，返回 `OutOfBounds` 错误
+    pub fn build(self) -> Result<(AllocatedRange, sequential::Allocator)> {
+        let total = self.total_size.get();
+        let header_end = match checked_align_up(self.header_len) {
+            Some(end) if end <= total => end,
+            aligned => {
+                return Err(Error::OutOfBounds {
+                    offset: 0,
+                    len: aligned.unwrap_or(self.header_len),
+                    size: total,
+                });
+            }
+        };
+
+        Ok((
+            AllocatedRange::from_range_unchecked(0, header_end),
+            sequential::Allocator::starting_at(self.total_size, header_end),
+        ))
+    }
+}
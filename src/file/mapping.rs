@@ -0,0 +1,172 @@
+//! Memory mapping held by `MmapFileInner`
+//!
+//! `MmapFileInner` 持有的内存映射
+
+use memmap2::MmapMut;
+use std::io;
+use std::ops::{Deref, DerefMut};
+
+/// Writable mapping, created by memmap2 or placed at a fixed address
+///
+/// 可写映射，由 memmap2 创建或放置在固定地址
+pub(crate) enum Mapping {
+    /// Mapping created and owned by memmap2
+    ///
+    /// 由 memmap2 创建并拥有的映射
+    Memmap(MmapMut),
+
+    /// Mapping created with `MAP_FIXED`, unmapped on drop
+    ///
+    /// 使用 `MAP_FIXED` 创建的映射，丢弃时解除映射
+    #[cfg(unix)]
+    Fixed(FixedMapping),
+}
+
+impl Mapping {
+    /// Flush the whole mapping synchronously
+    ///
+    /// 同步刷新整个映射
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        self.flush_range(0, self.len())
+    }
+
+    /// Flush the whole mapping asynchronously
+    ///
+    /// 异步刷新整个映射
+    pub(crate) fn flush_async(&self) -> io::Result<()> {
+        self.flush_async_range(0, self.len())
+    }
+
+    /// Flush `[offset, offset + len)` synchronously
+    ///
+    /// 同步刷新 `[offset, offset + len)`
+    pub(crate) fn flush_range(&self, offset: usize, len: usize) -> io::Result<()> {
+        match self {
+            Self::Memmap(mmap) => mmap.flush_range(offset, len),
+            #[cfg(unix)]
+            Self::Fixed(fixed) => fixed.msync(offset, len, libc::MS_SYNC),
+        }
+    }
+
+    /// Flush `[offset, offset + len)` asynchronously
+    ///
+    /// 异步刷新 `[offset, offset + len)`
+    pub(crate) fn flush_async_range(&self, offset: usize, len: usize) -> io::Result<()> {
+        match self {
+            Self::Memmap(mmap) => mmap.flush_async_range(offset, len),
+            #[cfg(unix)]
+            Self::Fixed(fixed) => fixed.msync(offset, len, libc::MS_ASYNC),
+        }
+    }
+}
+
+impl From<MmapMut> for Mapping {
+    #[inline]
+    fn from(mmap: MmapMut) -> Self {
+        Self::Memmap(mmap)
+    }
+}
+
+impl Deref for Mapping {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        match self {
+            Self::Memmap(mmap) => mmap,
+            // Safety: the fixed mapping is live and `len` bytes long
+            // Safety: 固定映射有效且长度为 `len` 字节
+            #[cfg(unix)]
+            Self::Fixed(fixed) => unsafe { std::slice::from_raw_parts(fixed.ptr, fixed.len) },
+        }
+    }
+}
+
+impl DerefMut for Mapping {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut [u8] {
+        match self {
+            Self::Memmap(mmap) => mmap,
+            // Safety: the fixed mapping is live, writable and `len` bytes long
+            // Safety: 固定映射有效、可写且长度为 `len` 字节
+            #[cfg(unix)]
+            Self::Fixed(fixed) => unsafe { std::slice::from_raw_parts_mut(fixed.ptr, fixed.len) },
+        }
+    }
+}
+
+/// Shared file mapping at a caller-chosen address
+///
+/// 位于调用者指定地址的共享文件映射
+#[cfg(unix)]
+pub(crate) struct FixedMapping {
+    /// Start of the mapping, page-aligned
+    ///
+    /// 映射起始地址，页对齐
+    ptr: *mut u8,
+
+    /// Length of the mapping in bytes
+    ///
+    /// 映射长度（字节）
+    len: usize,
+}
+
+#[cfg(unix)]
+impl FixedMapping {
+    /// Map `len` bytes of `file` read-write at exactly `addr`
+    ///
+    /// 将 `file` 的 `len` 字节以读写方式映射到恰好 `addr` 处
+    ///
+    /// # Safety
+    ///
+    /// `MAP_FIXED` silently replaces whatever is mapped in `[addr, addr + len)`; the caller
+    /// must own that address range and nothing may still reference memory in it.
+    ///
+    /// # Safety
+    ///
+    /// `MAP_FIXED` 会悄无声息地替换 `[addr, addr + len)` 中已有的映射；
+    /// 调用者必须拥有该地址区间，且不能再有任何东西引用其中的内存。
+    pub(crate) unsafe fn map(file: &std::fs::File, len: usize, addr: *mut u8) -> io::Result<Self> {
+        use std::os::unix::io::AsRawFd;
+
+        let ptr = unsafe {
+            libc::mmap(
+                addr.cast(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self { ptr: ptr.cast(), len })
+    }
+
+    /// Call `msync` on `[offset, offset + len)`, widened to page boundaries
+    ///
+    /// 对扩展到页边界的 `[offset, offset + len)` 调用 `msync`
+    fn msync(&self, offset: usize, len: usize, flags: libc::c_int) -> io::Result<()> {
+        let misalignment = offset % super::sys::page_size();
+        let start = offset - misalignment;
+
+        // Safety: [start, offset + len) lies within the live mapping
+        // Safety: [start, offset + len) 位于有效映射之内
+        match unsafe { libc::msync(self.ptr.add(start).cast(), len + misalignment, flags) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for FixedMapping {
+    fn drop(&mut self) {
+        // Safety: the mapping was created by `map` and is unmapped exactly once
+        // Safety: 映射由 `map` 创建，且只解除一次
+        unsafe { libc::munmap(self.ptr.cast(), self.len) };
+    }
+}
@@ -8,6 +8,7 @@ use super::allocator::RangeAllocator;
 use super::mmap_file_inner::MmapFileInner;
 use super::range::{AllocatedRange, WriteReceipt};
 use super::error::{Error, Result};
+use super::layout::LayoutBuilder;
 use std::path::Path;
 use std::num::NonZeroU64;
 
@@ -88,9 +89,125 @@ pub struct MmapFile {
     /// # Safety
     /// 通过 AllocatedRange 保证不同线程写入不重叠的区域
     inner: MmapFileInner,
+
+    /// Largest gap between receipt ranges that flushes still merge across
+    /// 
+    /// 刷新时仍会跨越合并的凭据范围之间的最大间隙
+    flush_coalesce_gap: u64,
+
+    /// Ranges written so far, keyed by start, for detecting overlapping allocations
+    /// 
+    /// 迄今为止写入的范围（以起始位置为键），用于检测重叠的分配
+    #[cfg(feature = "debug-alloc-checks")]
+    written: std::sync::Arc<std::sync::Mutex<std::collections::BTreeMap<u64, u64>>>,
+
+    /// Starts of ranges already prefetched by `read_range`, `None` when prefetch on first read is off
+    /// 
+    /// `read_range` 已预取的范围起始位置，未启用首次读取预取时为 `None`
+    prefetched: Option<std::sync::Arc<std::sync::Mutex<std::collections::HashSet<u64>>>>,
+
+    /// Number of prefetch advices issued by `read_range`, for testing
+    /// 
+    /// `read_range` 发出的预取建议次数，用于测试
+    #[cfg(test)]
+    prefetch_advised: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+
+    /// Whether the first page is a header holding the completion marker byte
+    /// 
+    /// 第一页是否为保存完成标记字节的头部
+    completion_marker: bool,
 }
 
 impl MmapFile {
+    /// Length of the sentinel written by [`write_range_sealed`](Self::write_range_sealed)
+    /// 
+    /// [`write_range_sealed`](Self::write_range_sealed) 写入的哨兵长度
+    pub const SEAL_LEN: u64 = 8;
+
+    /// Sentinel value written by [`write_range_sealed`](Self::write_range_sealed), little-endian
+    /// 
+    /// [`write_range_sealed`](Self::write_range_sealed) 写入的哨兵值（小端序）
+    pub const SEAL_SENTINEL: u64 = 0x5EA1_ED5E_A1ED_0A55;
+
+    /// Length of the header reserved by [`create_with_completion_marker`](Self::create_with_completion_marker)
+    /// 
+    /// [`create_with_completion_marker`](Self::create_with_completion_marker) 保留的头部长度
+    pub const COMPLETION_HEADER_LEN: u64 = allocator::ALIGNMENT;
+
+    /// Wrap a mapping
+    /// 
+    /// 包装映射
+    #[inline]
+    fn from_inner(inner: MmapFileInner) -> Self {
+        Self {
+            inner,
+            flush_coalesce_gap: 0,
+            #[cfg(feature = "debug-alloc-checks")]
+            written: Default::default(),
+            prefetched: None,
+            #[cfg(test)]
+            prefetch_advised: Default::default(),
+            completion_marker: false,
+        }
+    }
+
+    /// Mark the 4K pages of a range dirty once its bytes have been written
+    /// 
+    /// 在范围的字节写入完成后，将其 4K 页面标记为脏页
+    /// 
+    /// Marking only after the write means a concurrent [`sync_dirty`](Self::sync_dirty) either
+    /// sees the bit together with the data or leaves it for the next call.
+    /// 
+    /// 写入完成后才标记，使并发的 [`sync_dirty`](Self::sync_dirty) 要么同时看到脏位和数据，
+    /// 要么将其留给下一次调用。
+    #[inline]
+    fn mark_dirty(&self, range: AllocatedRange) {
+        self.inner.mark_dirty(range.start(), range.len());
+    }
+
+    /// Record a range being written, panicking if it overlaps a different range written before
+    /// 
+    /// 记录正在写入的范围，如果与之前写入的另一个范围重叠则 panic
+    /// 
+    /// Rewriting exactly the same range is allowed; any other overlap means two allocators
+    /// handed out ranges for the same file.
+    /// 
+    /// 允许重写完全相同的范围；其他任何重叠都意味着两个分配器为同一文件分配了范围。
+    #[cfg(feature = "debug-alloc-checks")]
+    fn check_overlap(&self, range: AllocatedRange) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut written = self.written.lock().unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        // Written ranges are disjoint, so only the last one starting before our end can overlap
+        // 已写入的范围互不重叠，因此只有在我们结束之前开始的最后一个范围可能重叠
+        if let Some((&start, &end)) = written.range(..range.end()).next_back()
+            && end > range.start()
+        {
+            if (start, end) == (range.start(), range.end()) {
+                return;
+            }
+            panic!(
+                "Range [{}, {}) overlaps previously written range [{}, {}); \
+                 are two allocators used with the same file? / \
+                 范围 [{}, {}) 与之前写入的范围 [{}, {}) 重叠；是否对同一文件使用了两个分配器？",
+                range.start(), range.end(), start, end,
+                range.start(), range.end(), start, end,
+            );
+        }
+
+        written.insert(range.start(), range.end());
+    }
+
+    /// No-op without the `debug-alloc-checks` feature
+    /// 
+    /// 未启用 `debug-alloc-checks` 特性时不执行任何操作
+    #[cfg(not(feature = "debug-alloc-checks"))]
+    #[inline(always)]
+    fn check_overlap(&self, _range: AllocatedRange) {}
+
     /// Create a new file and return (MmapFile, A) where A implements RangeAllocator
     /// 
     /// 创建新文件并返回 (MmapFile, A)，其中 A 实现 RangeAllocator
@@ -156,7 +273,7 @@ impl MmapFile {
     pub fn create<A: RangeAllocator>(path: impl AsRef<Path>, size: NonZeroU64) -> Result<(Self, A)> {
         let inner = MmapFileInner::create(path, size)?;
         let allocator = A::new(size);
-        Ok((Self { inner }, allocator))
+        Ok((Self::from_inner(inner), allocator))
     }
 
     /// Create a new file with default allocator::sequential::Allocator
@@ -171,6 +288,266 @@ impl MmapFile {
         Self::create::<allocator::sequential::Allocator>(path, size)
     }
 
+    /// Create a new file with a shared allocator::concurrent::Allocator
+    ///
+    /// 使用共享的 allocator::concurrent::Allocator 创建新文件
+    ///
+    /// The allocator is returned behind an `Arc`, so every worker thread can hold it and
+    /// allocate its own ranges through `&self` ([`ConcurrentRangeAllocator`](crate::allocator::ConcurrentRangeAllocator)),
+    /// then write them with a clone of the file. There is no need to pre-allocate
+    /// ranges on the main thread.
+    ///
+    /// 分配器以 `Arc` 返回，每个工作线程都可以持有它并通过 `&self`
+    /// （[`ConcurrentRangeAllocator`](crate::allocator::ConcurrentRangeAllocator)）分配自己的范围，
+    /// 再用文件的克隆写入。无需在主线程预先分配范围。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, nz, allocator::ALIGNMENT};
+    /// # use tempfile::tempdir;
+    /// # use std::sync::Arc;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("output.bin");
+    /// let (file, allocator) = MmapFile::create_concurrent(&path, nz(ALIGNMENT * 4))?;
+    ///
+    /// std::thread::scope(|s| {
+    ///     for _ in 0..2 {
+    ///         let file = file.clone();
+    ///         let allocator = Arc::clone(&allocator);
+    ///         s.spawn(move || {
+    ///             // Each thread allocates and writes its own ranges
+    ///             // 每个线程分配并写入自己的范围
+    ///             while let Some(range) = allocator.allocate(nz(ALIGNMENT)) {
+    ///                 file.write_range(range, &vec![1u8; ALIGNMENT as usize]);
+    ///             }
+    ///         });
+    ///     }
+    /// });
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn create_concurrent(
+        path: impl AsRef<Path>,
+        size: NonZeroU64,
+    ) -> Result<(Self, std::sync::Arc<allocator::concurrent::Allocator>)> {
+        let (file, allocator) = Self::create::<allocator::concurrent::Allocator>(path, size)?;
+        Ok((file, std::sync::Arc::new(allocator)))
+    }
+
+    /// Create a new file whose first page holds a completion marker
+    /// 
+    /// 创建新文件，其第一页保存完成标记
+    /// 
+    /// Reserves `[0, COMPLETION_HEADER_LEN)` as a header and returns an allocator for the rest
+    /// of the file. The first header byte is the flag set by
+    /// [`mark_complete`](Self::mark_complete) and read by [`is_complete`](Self::is_complete),
+    /// so consumers polling the file can tell when every write has landed.
+    /// 
+    /// 保留 `[0, COMPLETION_HEADER_LEN)` 作为头部，并返回用于文件其余部分的分配器。
+    /// 头部的第一个字节是由 [`mark_complete`](Self::mark_complete) 设置、
+    /// 由 [`is_complete`](Self::is_complete) 读取的标志，
+    /// 使轮询该文件的消费者能够知道所有写入何时完成。
+    /// 
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, including the header
+    /// 
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），包含头部
+    /// 
+    /// # Errors
+    /// - Returns `OutOfBounds` error if `size` is smaller than the header
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    /// 
+    /// # Errors
+    /// - 如果 `size` 小于头部大小，返回 `OutOfBounds` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, nz, allocator::ALIGNMENT};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("published.bin");
+    /// let (file, mut allocator) = MmapFile::create_with_completion_marker(&path, nz(ALIGNMENT * 2))?;
+    /// let range = allocator.allocate(nz(ALIGNMENT)).unwrap();
+    /// file.write_range(range, &vec![1u8; ALIGNMENT as usize]);
+    ///
+    /// assert!(!file.is_complete()?);
+    /// file.mark_complete()?;
+    /// assert!(file.is_complete()?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn create_with_completion_marker(
+        path: impl AsRef<Path>,
+        size: NonZeroU64,
+    ) -> Result<(Self, allocator::sequential::Allocator)> {
+        let (_, allocator) = LayoutBuilder::new(size).header(Self::COMPLETION_HEADER_LEN).build()?;
+        let (file, _) = Self::create_default(path, size)?;
+        Ok((file.with_completion_marker(), allocator))
+    }
+
+    /// Open an existing file whose first page holds a completion marker
+    /// 
+    /// 打开已存在的文件，其第一页保存完成标记
+    /// 
+    /// See [`create_with_completion_marker`](Self::create_with_completion_marker).
+    /// 
+    /// 见 [`create_with_completion_marker`](Self::create_with_completion_marker)。
+    /// 
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the file is smaller than the header
+    /// - Returns corresponding I/O errors if opening or memory mapping fails
+    /// 
+    /// # Errors
+    /// - 如果文件小于头部大小，返回 `OutOfBounds` 错误
+    /// - 如果无法打开文件或映射内存，返回相应的 I/O 错误
+    pub fn open_with_completion_marker(
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, allocator::sequential::Allocator)> {
+        let (file, _) = Self::open_default(path)?;
+        let (_, allocator) = LayoutBuilder::new(file.size()).header(Self::COMPLETION_HEADER_LEN).build()?;
+        Ok((file.with_completion_marker(), allocator))
+    }
+
+    /// Enable the completion marker header
+    /// 
+    /// 启用完成标记头部
+    #[inline]
+    fn with_completion_marker(mut self) -> Self {
+        self.completion_marker = true;
+        self
+    }
+
+    /// Publish the file as fully written
+    /// 
+    /// 将文件发布为已完整写入
+    /// 
+    /// Synchronously flushes the whole file first, then sets the completion flag and flushes
+    /// it, so a consumer that sees [`is_complete`](Self::is_complete) return `true` also sees
+    /// every write made before this call. Writes still in flight on other threads are not
+    /// covered; call this once they have all finished.
+    /// 
+    /// 先同步刷新整个文件，然后设置完成标志并刷新它，因此看到 [`is_complete`](Self::is_complete)
+    /// 返回 `true` 的消费者也能看到此调用之前的所有写入。其他线程上仍在进行的写入不在保证范围内；
+    /// 应在它们全部完成后再调用此方法。
+    /// 
+    /// # Errors
+    /// - Returns `NoCompletionMarker` error if the file has no completion marker header
+    /// - Returns `ReadOnlyMapping` error if the mapping is not writable
+    /// - Returns corresponding I/O errors if flushing fails
+    /// 
+    /// # Errors
+    /// - 如果文件没有完成标记头部，返回 `NoCompletionMarker` 错误
+    /// - 如果映射不可写，返回 `ReadOnlyMapping` 错误
+    /// - 如果刷新失败，返回相应的 I/O 错误
+    pub fn mark_complete(&self) -> Result<()> {
+        if !self.completion_marker {
+            return Err(Error::NoCompletionMarker);
+        }
+
+        // Safety: the header byte is never handed out by the allocator, writes are documented
+        // to have finished so no other thread touches its page, and data is flushed before the
+        // flag so the flag never becomes durable ahead of it
+        // Safety: 头部字节永远不会被分配器分配出去，按文档约定写入已全部完成，因此没有其他线程访问其页面，
+        // 且数据先于标志刷新，因此标志永远不会先于数据持久化
+        unsafe {
+            self.inner.sync_all()?;
+            self.inner.try_write_at(0, &[1])?;
+            self.inner.sync_range(0, 1)
+        }
+    }
+
+    /// Check whether the file has been published as fully written
+    /// 
+    /// 检查文件是否已发布为完整写入
+    /// 
+    /// Reads the flag set by [`mark_complete`](Self::mark_complete). Consumers mapping the same
+    /// file should check it before reading data.
+    /// 
+    /// 读取由 [`mark_complete`](Self::mark_complete) 设置的标志。映射同一文件的消费者应在读取数据前检查它。
+    /// 
+    /// # Errors
+    /// Returns `NoCompletionMarker` error if the file has no completion marker header
+    /// 
+    /// # Errors
+    /// 如果文件没有完成标记头部，返回 `NoCompletionMarker` 错误
+    pub fn is_complete(&self) -> Result<bool> {
+        if !self.completion_marker {
+            return Err(Error::NoCompletionMarker);
+        }
+
+        // Safety: the mapping holds at least the header, and the flag is read volatile
+        // because another process may set it
+        // Safety: 映射至少包含头部，且标志可能由其他进程设置，因此使用 volatile 读取
+        Ok(unsafe { self.inner.as_ptr().read_volatile() } != 0)
+    }
+
+    /// Create a new file sized to hold `count` records with a fixed-size allocator
+    ///
+    /// 创建可容纳 `count` 条记录的新文件，并返回定长分配器
+    ///
+    /// The file is `record_size * count` bytes, and every
+    /// [`allocate`](allocator::fixed::Allocator::allocate) on the returned allocator hands
+    /// out exactly one `record_size`-aligned record slot until all `count` are used.
+    ///
+    /// 文件大小为 `record_size * count` 字节，返回的分配器每次
+    /// [`allocate`](allocator::fixed::Allocator::allocate) 都恰好分配一个按 `record_size`
+    /// 对齐的记录槽位，直到 `count` 个槽位全部用完。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `record_size`: Size of every record in bytes
+    /// - `count`: Number of records
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `record_size`: 每条记录的大小（字节）
+    /// - `count`: 记录数量
+    ///
+    /// # Errors
+    /// - Returns `SizeOverflow` error if `record_size * count` overflows `u64`
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果 `record_size * count` 溢出 `u64`，返回 `SizeOverflow` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, nz};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("records.bin");
+    /// let (file, mut allocator) = MmapFile::create_for_records(&path, nz(512), nz(4))?;
+    /// assert_eq!(file.size().get(), 2048);
+    ///
+    /// let record = allocator.allocate().unwrap();
+    /// file.write_range(record, &[7u8; 512]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[inline]
+    pub fn create_for_records(
+        path: impl AsRef<Path>,
+        record_size: NonZeroU64,
+        count: NonZeroU64,
+    ) -> Result<(Self, allocator::fixed::Allocator)> {
+        let size = record_size.checked_mul(count).ok_or(Error::SizeOverflow)?;
+        let inner = MmapFileInner::create(path, size)?;
+        let allocator = allocator::fixed::Allocator::new(size, record_size);
+        Ok((Self::from_inner(inner), allocator))
+    }
+
     /// Open an existing file and map it to memory
     /// 
     /// 打开已存在的文件并映射到内存
@@ -206,7 +583,7 @@ impl MmapFile {
         let inner = MmapFileInner::open(path)?;
         let size = inner.size();
         let allocator = A::new(size);
-        Ok((Self { inner }, allocator))
+        Ok((Self::from_inner(inner), allocator))
     }
 
     /// Open an existing file with default allocator::sequential::Allocator
@@ -252,14 +629,14 @@ impl MmapFile {
     /// 
     /// # Parameters
     /// - `range`: Allocated file range
-    /// - `data`: Data to write, length must equal `range.len()`
+    /// - `data`: Data to write (any `AsRef<[u8]>`, e.g. `Vec<u8>`, `[u8; N]` or `Bytes`), length must equal `range.len()`
     /// 
     /// # Returns
     /// Returns [`WriteReceipt`] proving the range has been successfully written
     /// 
     /// # 参数
     /// - `range`: 已分配的文件范围
-    /// - `data`: 要写入的数据，长度必须等于 `range.len()`
+    /// - `data`: 要写入的数据（任意 `AsRef<[u8]>`，例如 `Vec<u8>`、`[u8; N]` 或 `Bytes`），长度必须等于 `range.len()`
     /// 
     /// # 返回值
     /// 返回 [`WriteReceipt`] 凭据，证明该范围已被成功写入
@@ -306,8 +683,17 @@ impl MmapFile {
     /// # }
     /// ```
     /// 
+    /// # Panics
+    /// With the `debug-alloc-checks` feature, panics if `range` overlaps a different range
+    /// written before (e.g. two allocators used with the same file)
+    /// 
+    /// # Panics
+    /// 启用 `debug-alloc-checks` 特性时，如果 `range` 与之前写入的另一个范围重叠
+    /// （例如对同一文件使用了两个分配器），则 panic
     #[inline]
-    pub fn write_range(&self, range: AllocatedRange, data: &[u8]) -> WriteReceipt {
+    pub fn write_range(&self, range: AllocatedRange, data: impl AsRef<[u8]>) -> WriteReceipt {
+        let data = data.as_ref();
+
         // Check data length matches
         // 检查数据长度匹配
         debug_assert!(
@@ -315,16 +701,54 @@ impl MmapFile {
             "Data length {} doesn't match range length {}",
             data.len(), range.len()
         );
+        self.check_overlap(range);
+        trace_event!(offset = range.start(), len = range.len(), "write_range");
 
         // Safety: RangeAllocator guarantees non-overlapping ranges
         // Safety: RangeAllocator 保证范围不重叠
         unsafe { self.inner.write_at(range.start(), data); }
+        self.mark_dirty(range);
 
         // Return write receipt
         // 返回写入凭据
         WriteReceipt::new(range)
     }
 
+    /// Fill an allocated range with a repeating pattern
+    /// 
+    /// 用重复的模式填充已分配的范围
+    /// 
+    /// Tiles `pattern` across the whole range, truncating the last repetition. Useful for test
+    /// fixtures and formatting. See [`MmapFileInner::fill_pattern`].
+    /// 
+    /// 将 `pattern` 平铺到整个范围，最后一次重复会被截断。适用于测试数据和格式化。
+    /// 见 [`MmapFileInner::fill_pattern`]。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated range
+    /// - `pattern`: Bytes to repeat
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的范围
+    /// - `pattern`: 要重复的字节
+    /// 
+    /// # Panics
+    /// Panics if `pattern` is empty and the range is not, or if the mapping is not writable
+    /// 
+    /// # Panics
+    /// 如果 `pattern` 为空而范围不为空，或映射不可写，则 panic
+    pub fn fill_range_pattern(&self, range: AllocatedRange, pattern: &[u8]) -> WriteReceipt {
+        self.check_overlap(range);
+
+        // Safety: RangeAllocator guarantees non-overlapping ranges, and allocated ranges are in bounds
+        // Safety: RangeAllocator 保证范围不重叠，且已分配的范围不会越界
+        if let Err(err) = unsafe { self.inner.fill_pattern(range.start(), range.len(), pattern) } {
+            panic!("failed to fill range: {}", err);
+        }
+        self.mark_dirty(range);
+        WriteReceipt::new(range)
+    }
+
     /// Write all data to the specified range
     /// 
     /// 在指定范围写入所有数据
@@ -335,123 +759,90 @@ impl MmapFile {
     /// 
     /// # Parameters
     /// - `range`: Allocated file range
-    /// - `data`: Data to write
+    /// - `data`: Data to write (any `AsRef<[u8]>`)
     /// 
     /// # Returns
     /// Returns [`WriteReceipt`] proving the range has been successfully written
     /// 
     /// # 参数
     /// - `range`: 已分配的文件范围
-    /// - `data`: 要写入的数据
+    /// - `data`: 要写入的数据（任意 `AsRef<[u8]>`）
     /// 
     /// # 返回值
     /// 返回 [`WriteReceipt`] 凭据，证明该范围已被成功写入
     /// 
     #[inline]
-    pub fn write_range_all(&self, range: AllocatedRange, data: &[u8]) -> WriteReceipt {
+    pub fn write_range_all(&self, range: AllocatedRange, data: impl AsRef<[u8]>) -> WriteReceipt {
         self.write_range(range, data)
     }
 
-    /// Get file size
-    /// 
-    /// 获取文件大小
-    #[inline]
-    pub fn size(&self) -> NonZeroU64 {
-        self.inner.size()
-    }
-
-    /// Read data from the specified range
+    /// Write a batch of `(range, data)` pairs
     /// 
-    /// 在指定范围读取数据
+    /// 批量写入 `(range, data)` 对
     /// 
-    /// Reads data from the memory mapping into the buffer.
+    /// Each pair is written as by [`write_range`](Self::write_range). With the `rayon`
+    /// feature the writes run in parallel on rayon's thread pool, which is safe because
+    /// allocated ranges never overlap; otherwise they run one after another.
     /// 
-    /// 从内存映射中读取数据到缓冲区。
+    /// 每一对都按 [`write_range`](Self::write_range) 的方式写入。启用 `rayon` 特性时，
+    /// 写入在 rayon 线程池中并行执行，由于已分配的范围永不重叠，这是安全的；否则依次执行。
     /// 
     /// # Parameters
-    /// - `range`: Range to read
-    /// - `buf`: Buffer to receive data, length must be at least `range.len()`
+    /// - `items`: Ranges and their data, each data length must match its range length
     /// 
     /// # Returns
-    /// Number of bytes actually read
+    /// One [`WriteReceipt`] per item, in the order of `items`
     /// 
     /// # 参数
-    /// - `range`: 要读取的范围
-    /// - `buf`: 接收数据的缓冲区，长度必须至少为 `range.len()`
+    /// - `items`: 范围及其数据，每个数据的长度必须与其范围长度一致
     /// 
     /// # 返回值
-    /// 返回实际读取的字节数
-    pub fn read_range(&self, range: AllocatedRange, buf: &mut [u8]) -> Result<usize> {
-        let len = range.len() as usize;
-
-        if buf.len() < len {
-            return Err(Error::BufferTooSmall {
-                buffer_len: buf.len(),
-                range_len: range.len(),
-            });
+    /// 每个条目一个 [`WriteReceipt`] 凭据，顺序与 `items` 相同
+    /// 
+    /// # Panics
+    /// With the `debug-alloc-checks` feature, panics if a range overlaps a different range
+    /// written before
+    /// 
+    /// # Panics
+    /// 启用 `debug-alloc-checks` 特性时，如果某个范围与之前写入的另一个范围重叠，则 panic
+    pub fn write_batch(&self, items: &[(AllocatedRange, &[u8])]) -> Vec<WriteReceipt> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            items.par_iter().map(|&(range, data)| self.write_range(range, data)).collect()
         }
 
-        // Safety: Read operations are safe
-        // Safety: 读取操作是安全的
-        unsafe { self.inner.read_at(range.start(), &mut buf[..len]) }
-    }
-
-    /// Flush data to disk asynchronously
-    /// 
-    /// 异步刷新数据到磁盘
-    /// 
-    /// Initiates an asynchronous flush operation without blocking for completion.
-    /// The operating system will write data to disk in the background.
-    /// 
-    /// 发起异步刷新操作，不会阻塞等待完成。操作系统会在后台将数据写入磁盘。
-    pub fn flush(&self) -> Result<()> {
-        unsafe { self.inner.flush() }
-    }
-
-    /// Flush data to disk synchronously
-    /// 
-    /// 同步刷新数据到磁盘
-    /// 
-    /// Synchronously flushes data in memory to disk, blocking until completion.
-    /// This is slower than `flush()` but guarantees data has been written to disk.
-    /// 
-    /// 同步将内存中的数据刷新到磁盘，阻塞直到完成。
-    /// 这比 `flush()` 慢，但保证数据已经写入磁盘。
-    /// 
-    /// # Safety
-    /// 
-    /// During the flush, the caller must ensure no other threads are modifying the
-    /// mapped memory. While sync itself is a safe operation, it is marked unsafe
-    /// for API consistency as it operates on data modified through unsafe methods.
-    /// 
-    /// # Safety
-    /// 
-    /// 在刷新期间，调用者需要确保没有其他线程正在修改映射的内存。
-    /// 虽然 sync 本身是安全的操作，但为了保持 API 一致性，
-    /// 它被标记为 unsafe，因为它操作的是通过 unsafe 方法修改的数据。
-    pub unsafe fn sync_all(&self) -> Result<()> {
-        unsafe { self.inner.sync_all() }
+        #[cfg(not(feature = "rayon"))]
+        items.iter().map(|&(range, data)| self.write_range(range, data)).collect()
     }
 
-    /// Flush a specific range to disk
-    /// 
-    /// 刷新指定区域到磁盘
+    /// Write data shorter than the range
     /// 
-    /// Flushes only a portion of the file to disk, which can improve performance.
-    /// 
-    /// 只刷新文件的一部分到磁盘，可以提高性能。
+    /// 写入比范围短的数据
     /// 
-    /// By requiring a [`WriteReceipt`], this ensures only written ranges can be flushed,
-    /// providing compile-time safety guarantees.
+    /// Writes `data` at `range.start()`. Unlike [`write_range`](Self::write_range), `data`
+    /// may be shorter than the range, so callers don't need to pad buffers to the aligned size.
+    /// The returned receipt covers only `[start, start + data.len())`.
     /// 
-    /// 通过要求 [`WriteReceipt`] 凭据，确保只能刷新已写入的范围，
-    /// 提供编译期安全保证。
+    /// 在 `range.start()` 处写入 `data`。与 [`write_range`](Self::write_range) 不同，
+    /// `data` 可以比范围短，调用者无需将缓冲区填充到对齐大小。
+    /// 返回的凭据仅覆盖 `[start, start + data.len())`。
     /// 
     /// # Parameters
-    /// - `receipt`: Write receipt proving the range has been successfully written
+    /// - `range`: Allocated file range
+    /// - `data`: Data to write, length must be at most `range.len()`
+    /// - `zero_rest`: If true, `[start + data.len(), end)` is zero-filled, otherwise it is left untouched
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] covering the bytes of `data`
     /// 
     /// # 参数
-    /// - `receipt`: 写入凭据，证明该范围已被成功写入
+    /// - `range`: 已分配的文件范围
+    /// - `data`: 要写入的数据，长度不得超过 `range.len()`
+    /// - `zero_rest`: 为 true 时将 `[start + data.len(), end)` 清零，否则保持不变
+    /// 
+    /// # 返回值
+    /// 返回覆盖 `data` 字节的 [`WriteReceipt`] 凭据
     /// 
     /// # Examples
     /// 
@@ -463,7 +854,756 @@ impl MmapFile {
     /// # let path = dir.path().join("output.bin");
     /// # use std::num::NonZeroU64;
     /// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
-    /// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    /// let range = allocator.allocate(NonZeroU64::new(3000).unwrap()).unwrap();
+    ///
+    /// // Only 3000 bytes of the 4K slot are used
+    /// // 4K 槽位中只使用 3000 字节
+    /// let receipt = file.write_partial(range, &[1u8; 3000], true)?;
+    /// assert_eq!(receipt.len(), 3000);
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// 
+    /// # Errors
+    /// - Returns `DataTooLarge` error if `data` is longer than the range
+    /// - Returns `OutOfBounds` error if the range exceeds the file size
+    /// 
+    /// # Errors
+    /// - 如果 `data` 比范围长，返回 `DataTooLarge` 错误
+    /// - 如果范围超出文件大小，返回 `OutOfBounds` 错误
+    pub fn write_partial(&self, range: AllocatedRange, data: &[u8], zero_rest: bool) -> Result<WriteReceipt> {
+        if data.len() as u64 > range.len() {
+            return Err(Error::DataTooLarge {
+                data_len: data.len(),
+                range_len: range.len(),
+            });
+        }
+        self.inner.check_bounds(range.start(), range.len())?;
+        self.check_overlap(range);
+
+        // Safety: RangeAllocator guarantees non-overlapping ranges
+        // Safety: RangeAllocator 保证范围不重叠
+        unsafe { self.inner.write_at(range.start(), data); }
+
+        let written_end = range.start() + data.len() as u64;
+
+        if zero_rest {
+            // Safety: [written_end, end) lies within the exclusively owned range
+            // Safety: [written_end, end) 位于独占的范围之内
+            unsafe { self.inner.fill_pattern(written_end, range.end() - written_end, &[0])?; }
+            self.mark_dirty(range);
+        } else {
+            self.mark_dirty(AllocatedRange::from_range_unchecked(range.start(), written_end));
+        }
+
+        Ok(WriteReceipt::new(AllocatedRange::from_range_unchecked(range.start(), written_end)))
+    }
+
+    /// Write data to a range and report how many bytes were copied
+    /// 
+    /// 向范围写入数据，并报告复制的字节数
+    /// 
+    /// Copies `min(data.len(), range.len())` bytes to `range.start()`: shorter data leaves the
+    /// rest of the range untouched, longer data is truncated at the end of the range. Unlike
+    /// [`write_range`](Self::write_range), lengths need not match.
+    /// 
+    /// 将 `min(data.len(), range.len())` 字节复制到 `range.start()`：较短的数据使范围其余部分保持不变，
+    /// 较长的数据在范围末尾被截断。与 [`write_range`](Self::write_range) 不同，长度无需匹配。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `data`: Data to write
+    /// 
+    /// # Returns
+    /// A [`WriteReceipt`] covering the copied bytes and their count
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `data`: 要写入的数据
+    /// 
+    /// # 返回值
+    /// 覆盖已复制字节的 [`WriteReceipt`] 凭据，以及复制的字节数
+    pub fn write_range_counted(&self, range: AllocatedRange, data: impl AsRef<[u8]>) -> (WriteReceipt, usize) {
+        let data = data.as_ref();
+        let len = data.len().min(range.len() as usize);
+        self.check_overlap(range);
+
+        // Safety: RangeAllocator guarantees non-overlapping ranges, and at most range.len() bytes are written
+        // Safety: RangeAllocator 保证范围不重叠，且最多写入 range.len() 字节
+        let written = unsafe { self.inner.write_at(range.start(), &data[..len]) };
+
+        let written_range = AllocatedRange::from_range_unchecked(range.start(), range.start() + written as u64);
+        self.mark_dirty(written_range);
+        (WriteReceipt::new(written_range), written)
+    }
+
+    /// Write data followed by a sentinel in the last 8 bytes of the range
+    /// 
+    /// 写入数据，并在范围的最后 8 字节写入哨兵
+    /// 
+    /// Clears the sentinel in `[end - 8, end)` and syncs it, writes `data` at `range.start()`
+    /// and syncs it, then writes [`SEAL_SENTINEL`](Self::SEAL_SENTINEL) and syncs it. Since
+    /// the kernel may write pages back in any order, these syncs are what guarantee that a
+    /// valid sentinel never reaches the disk ahead of the data, including a stale one from an
+    /// earlier write. After a crash, [`is_sealed`](Self::is_sealed) thus tells whether the
+    /// range was fully persisted. The price is three synchronous flushes per call. Bytes
+    /// between `data` and the sentinel are left untouched.
+    /// 
+    /// 先清除 `[end - 8, end)` 中的哨兵并同步，再在 `range.start()` 处写入 `data` 并同步，
+    /// 最后写入 [`SEAL_SENTINEL`](Self::SEAL_SENTINEL) 并同步。由于内核可能以任意顺序写回页面，
+    /// 正是这些同步保证了有效的哨兵（包括之前写入遗留的哨兵）永远不会先于数据落盘。
+    /// 因此崩溃之后，[`is_sealed`](Self::is_sealed) 可以判断该范围是否已被完整持久化。
+    /// 代价是每次调用需要三次同步刷新。`data` 与哨兵之间的字节保持不变。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `data`: Data to write, at most `range.len() - 8` bytes
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] covering the whole range
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `data`: 要写入的数据，不超过 `range.len() - 8` 字节
+    /// 
+    /// # 返回值
+    /// 返回覆盖整个范围的 [`WriteReceipt`] 凭据
+    /// 
+    /// # Errors
+    /// - Returns `DataTooLarge` error if `data` and the sentinel do not fit in the range; the
+    ///   reported `range_len` is the space left for data
+    /// - Returns corresponding I/O errors if syncing fails; the range is then left unsealed
+    /// 
+    /// # Errors
+    /// - 如果 `data` 与哨兵放不进该范围，返回 `DataTooLarge` 错误；报告的 `range_len` 为可用于数据的空间
+    /// - 如果同步失败，返回相应的 I/O 错误；此时该范围保持未密封状态
+    pub fn write_range_sealed(&self, range: AllocatedRange, data: impl AsRef<[u8]>) -> Result<WriteReceipt> {
+        let data = data.as_ref();
+        let capacity = range.len().saturating_sub(Self::SEAL_LEN);
+        if range.len() < Self::SEAL_LEN || data.len() as u64 > capacity {
+            return Err(Error::DataTooLarge {
+                data_len: data.len(),
+                range_len: capacity,
+            });
+        }
+        self.check_overlap(range);
+
+        let seal = range.end() - Self::SEAL_LEN;
+
+        // Safety: RangeAllocator guarantees non-overlapping ranges, and all writes lie within the range
+        // Safety: RangeAllocator 保证范围不重叠，且所有写入都位于范围之内
+        unsafe {
+            self.inner.write_at(seal, [0u8; Self::SEAL_LEN as usize]);
+            self.inner.sync_range(seal, Self::SEAL_LEN as usize)?;
+            self.inner.write_at(range.start(), data);
+            self.inner.sync_range(range.start(), data.len())?;
+            self.inner.write_at(seal, Self::SEAL_SENTINEL.to_le_bytes());
+            self.inner.sync_range(seal, Self::SEAL_LEN as usize)?;
+        }
+        self.mark_dirty(range);
+
+        Ok(WriteReceipt::new(range))
+    }
+
+    /// Check whether a range ends with the sentinel of [`write_range_sealed`](Self::write_range_sealed)
+    /// 
+    /// 检查范围是否以 [`write_range_sealed`](Self::write_range_sealed) 的哨兵结尾
+    /// 
+    /// Returns `false` for ranges shorter than the sentinel. See
+    /// [`write_range_sealed`](Self::write_range_sealed) for the limits of this check.
+    /// 
+    /// 对于比哨兵短的范围返回 `false`。此检查的局限参见 [`write_range_sealed`](Self::write_range_sealed)。
+    /// 
+    /// # Parameters
+    /// - `range`: Range to check
+    /// 
+    /// # 参数
+    /// - `range`: 要检查的范围
+    pub fn is_sealed(&self, range: AllocatedRange) -> bool {
+        if range.len() < Self::SEAL_LEN {
+            return false;
+        }
+
+        // Safety: Read operations are safe
+        // Safety: 读取操作是安全的
+        unsafe { self.inner.read_u64_le_at(range.end() - Self::SEAL_LEN) }
+            .is_ok_and(|value| value == Self::SEAL_SENTINEL)
+    }
+
+    /// Write a plain value at the start of a range and keep a typed handle to it
+    /// 
+    /// 在范围起始处写入普通值，并保留指向它的类型化句柄
+    /// 
+    /// The handle reads and updates the value in place later, e.g. a header whose fields are
+    /// filled in as a build progresses. Bytes of the range past `size_of::<T>()` are untouched.
+    /// 
+    /// 句柄之后可以原地读取和更新该值，例如在构建过程中逐步填写字段的头部。
+    /// 范围中超出 `size_of::<T>()` 的字节保持不变。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range, at least `size_of::<T>()` bytes
+    /// - `value`: Value to write
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围，至少 `size_of::<T>()` 字节
+    /// - `value`: 要写入的值
+    /// 
+    /// # Errors
+    /// Returns `DataTooLarge` error if `T` does not fit in the range
+    /// 
+    /// # Errors
+    /// 如果 `T` 放不进该范围，返回 `DataTooLarge` 错误
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, allocator::{ALIGNMENT, RangeAllocator}};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("typed.bin");
+    /// # use std::num::NonZeroU64;
+    /// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
+    /// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    ///
+    /// let count = file.write_typed(range, 0u64)?;
+    /// count.update(|n| *n += 1);
+    /// assert_eq!(count.get(), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "bytemuck")]
+    pub fn write_typed<T: bytemuck::Pod>(&self, range: AllocatedRange, value: T) -> Result<super::TypedHandle<'_, T>> {
+        if size_of::<T>() as u64 > range.len() {
+            return Err(Error::DataTooLarge {
+                data_len: size_of::<T>(),
+                range_len: range.len(),
+            });
+        }
+        self.check_overlap(range);
+
+        let handle = super::TypedHandle::new(&self.inner, range);
+        handle.set(value);
+        Ok(handle)
+    }
+
+    /// Modify the bytes of a range in place
+    /// 
+    /// 原地修改范围内的字节
+    /// 
+    /// Hands `f` a mutable slice over the range's bytes in the mapping, avoiding a
+    /// read-into-buffer, mutate, write-back round trip for in-place transforms such as
+    /// incrementing a counter or flipping a flag. This is safe because the range is
+    /// exclusively allocated.
+    /// 
+    /// 将映射中该范围字节的可变切片交给 `f`，对于递增计数器、翻转标志等原地变换，
+    /// 避免了读入缓冲区、修改、再写回的往返。由于范围是独占分配的，此操作是安全的。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `f`: Closure receiving the range's bytes
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] covering the whole range
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `f`: 接收范围字节的闭包
+    /// 
+    /// # 返回值
+    /// 返回覆盖整个范围的 [`WriteReceipt`] 凭据
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, allocator::ALIGNMENT};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("output.bin");
+    /// # use std::num::NonZeroU64;
+    /// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
+    /// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    ///
+    /// // Increment a counter stored in the first 8 bytes
+    /// // 递增存储在前 8 字节中的计数器
+    /// let receipt = file.modify_range(range, |bytes| {
+    ///     let counter = u64::from_le_bytes(bytes[..8].try_into().unwrap());
+    ///     bytes[..8].copy_from_slice(&(counter + 1).to_le_bytes());
+    /// })?;
+    /// file.flush_range(receipt)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    /// 
+    /// # Errors
+    /// Returns `OutOfBounds` error if the range exceeds the file size (e.g. a range from
+    /// another allocator or a deserialized one)
+    /// 
+    /// # Errors
+    /// 如果范围超出文件大小（例如来自其他分配器或反序列化得到的范围），返回 `OutOfBounds` 错误
+    pub fn modify_range<F: FnOnce(&mut [u8])>(&self, range: AllocatedRange, f: F) -> Result<WriteReceipt> {
+        self.inner.check_bounds(range.start(), range.len())?;
+        self.check_overlap(range);
+
+        // Safety: RangeAllocator guarantees non-overlapping ranges, so this slice is not aliased
+        // Safety: RangeAllocator 保证范围不重叠，因此该切片不存在别名
+        let bytes = unsafe {
+            let ptr = self.inner.as_mut_ptr().add(range.start() as usize);
+            std::slice::from_raw_parts_mut(ptr, range.len() as usize)
+        };
+        f(bytes);
+        self.inner.mark_written(range.end());
+        self.mark_dirty(range);
+
+        Ok(WriteReceipt::new(range))
+    }
+
+    /// Overwrite a single byte within a range
+    /// 
+    /// 覆盖范围内的单个字节
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `index`: Offset of the byte relative to `range.start()`
+    /// - `byte`: New value
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] covering the single modified byte
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `index`: 字节相对于 `range.start()` 的偏移
+    /// - `byte`: 新的值
+    /// 
+    /// # 返回值
+    /// 返回覆盖被修改字节的 [`WriteReceipt`] 凭据
+    /// 
+    /// # Errors
+    /// Returns `PieceOutOfRange` error if `index` is not within the range
+    /// 
+    /// # Errors
+    /// 如果 `index` 不在范围内，返回 `PieceOutOfRange` 错误
+    pub fn modify_byte(&self, range: AllocatedRange, index: u64, byte: u8) -> Result<WriteReceipt> {
+        if index >= range.len() {
+            return Err(Error::PieceOutOfRange {
+                offset: index,
+                len: 1,
+                range_len: range.len(),
+            });
+        }
+        self.check_overlap(range);
+
+        let offset = range.start() + index;
+        // Safety: RangeAllocator guarantees non-overlapping ranges, and the byte lies within the range
+        // Safety: RangeAllocator 保证范围不重叠，且该字节位于范围之内
+        unsafe { self.inner.write_byte(offset, byte)? };
+
+        let written = AllocatedRange::from_range_unchecked(offset, offset + 1);
+        self.mark_dirty(written);
+        Ok(WriteReceipt::new(written))
+    }
+
+    /// Write several pieces at relative offsets within a range
+    /// 
+    /// 在范围内的相对偏移处写入多个片段
+    /// 
+    /// Each piece is a `(relative_offset, data)` pair landing at `range.start() + relative_offset`,
+    /// which suits sparse records such as a header and a footer in one slot. Bytes not covered
+    /// by any piece are left untouched. All pieces are validated before anything is written.
+    /// 
+    /// 每个片段是一个 `(相对偏移, 数据)` 对，写入到 `range.start() + 相对偏移` 处，
+    /// 适用于稀疏记录，例如同一槽位中的头部和尾部。未被任何片段覆盖的字节保持不变。
+    /// 写入之前会先校验所有片段。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `pieces`: `(relative_offset, data)` pairs, in any order
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] spanning from the lowest piece start to the highest piece end
+    /// (empty at `range.start()` if there are no non-empty pieces)
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `pieces`: `(相对偏移, 数据)` 对，顺序任意
+    /// 
+    /// # 返回值
+    /// 返回从最低片段起点到最高片段终点的 [`WriteReceipt`] 凭据
+    /// （没有非空片段时为位于 `range.start()` 的空凭据）
+    /// 
+    /// # Errors
+    /// - Returns `PieceOutOfRange` error if a piece does not fit within the range
+    /// - Returns `OverlappingPieces` error if two pieces overlap
+    /// 
+    /// # Errors
+    /// - 如果某个片段超出范围，返回 `PieceOutOfRange` 错误
+    /// - 如果两个片段重叠，返回 `OverlappingPieces` 错误
+    pub fn write_scattered(&self, range: AllocatedRange, pieces: &[(u64, &[u8])]) -> Result<WriteReceipt> {
+        let mut spans = Vec::with_capacity(pieces.len());
+        for &(offset, data) in pieces {
+            match offset.checked_add(data.len() as u64) {
+                Some(end) if end <= range.len() => {
+                    if !data.is_empty() {
+                        spans.push((offset, end));
+                    }
+                }
+                _ => {
+                    return Err(Error::PieceOutOfRange {
+                        offset,
+                        len: data.len(),
+                        range_len: range.len(),
+                    });
+                }
+            }
+        }
+
+        spans.sort_unstable();
+        if let Some(pair) = spans.windows(2).find(|pair| pair[1].0 < pair[0].1) {
+            return Err(Error::OverlappingPieces { offset: pair[1].0 });
+        }
+        self.check_overlap(range);
+
+        for &(offset, data) in pieces {
+            // Safety: RangeAllocator guarantees non-overlapping ranges, and every piece lies within the range
+            // Safety: RangeAllocator 保证范围不重叠，且每个片段都位于范围之内
+            unsafe { self.inner.write_at(range.start() + offset, data); }
+            self.mark_dirty(AllocatedRange::from_range_unchecked(
+                range.start() + offset,
+                range.start() + offset + data.len() as u64,
+            ));
+        }
+
+        let (low, high) = match (spans.first(), spans.iter().map(|span| span.1).max()) {
+            (Some(first), Some(high)) => (first.0, high),
+            _ => (0, 0),
+        };
+        Ok(WriteReceipt::new(AllocatedRange::from_range_unchecked(
+            range.start() + low,
+            range.start() + high,
+        )))
+    }
+
+    /// Turn the file and its allocator into a sequential [`std::io::Write`] sink
+    /// 
+    /// 将文件及其分配器转换为顺序的 [`std::io::Write`] 写入端
+    /// 
+    /// The writer takes over everything the allocator has not handed out yet and fills it
+    /// contiguously from [`next_pos`](allocator::sequential::Allocator::next_pos), one exact
+    /// sized range per `write`. See [`SeqWriter`](super::SeqWriter) for details, and call
+    /// [`finish`](super::SeqWriter::finish) to get the receipts.
+    /// 
+    /// 写入器接管分配器尚未分配出去的全部空间，并从 [`next_pos`](allocator::sequential::Allocator::next_pos)
+    /// 开始连续填充，每次 `write` 对应一个大小精确的范围。详见 [`SeqWriter`](super::SeqWriter)，
+    /// 调用 [`finish`](super::SeqWriter::finish) 获取凭据。
+    /// 
+    /// # Parameters
+    /// - `allocator`: The file's allocator, whose remaining space the writer takes over
+    /// 
+    /// # 参数
+    /// - `allocator`: 文件的分配器，写入器接管其剩余空间
+    /// 
+    /// # Panics
+    /// With the `debug-alloc-checks` feature, panics if the remaining space overlaps a range
+    /// written before
+    /// 
+    /// # Panics
+    /// 启用 `debug-alloc-checks` 特性时，如果剩余空间与之前写入的范围重叠，则 panic
+    pub fn sequential_writer(self, allocator: allocator::sequential::Allocator) -> super::SeqWriter {
+        let end = allocator.total_size().get().min(self.size().get());
+        let start = allocator.next_pos().min(end);
+        let region = AllocatedRange::from_range_unchecked(start, end);
+        self.check_overlap(region);
+        super::SeqWriter::new(self.inner.clone(), region)
+    }
+
+    /// Create a [`tokio::io::AsyncWrite`] adapter that fills `range` from its start
+    ///
+    /// 创建从起始位置填充 `range` 的 [`tokio::io::AsyncWrite`] 适配器
+    ///
+    /// Available with the `tokio` feature. See [`AsyncRangeWriter`](super::AsyncRangeWriter)
+    /// for the flushing behavior. Call [`into_receipt`](super::AsyncRangeWriter::into_receipt)
+    /// after writing to obtain the receipt.
+    ///
+    /// 需要启用 `tokio` 特性。刷新行为参见 [`AsyncRangeWriter`](super::AsyncRangeWriter)。
+    /// 写入完成后调用 [`into_receipt`](super::AsyncRangeWriter::into_receipt) 获取凭据。
+    ///
+    /// # Parameters
+    /// - `range`: Allocated file range
+    ///
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    ///
+    /// # Panics
+    /// With the `debug-alloc-checks` feature, panics if `range` overlaps a different range
+    /// written before
+    ///
+    /// # Panics
+    /// 启用 `debug-alloc-checks` 特性时，如果 `range` 与之前写入的另一个范围重叠，则 panic
+    #[cfg(feature = "tokio")]
+    pub fn async_range_writer(&self, range: AllocatedRange) -> super::AsyncRangeWriter {
+        self.check_overlap(range);
+        super::AsyncRangeWriter::new(self.inner.clone(), range)
+    }
+
+    /// Get file size
+    /// 
+    /// 获取文件大小
+    #[inline]
+    pub fn size(&self) -> NonZeroU64 {
+        self.inner.size()
+    }
+
+    /// Get file size as `usize`
+    /// 
+    /// 以 `usize` 获取文件大小
+    /// 
+    /// See [`MmapFileInner::len`].
+    /// 
+    /// 参见 [`MmapFileInner::len`]。
+    #[inline]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Read data from the specified range
+    /// 
+    /// 在指定范围读取数据
+    /// 
+    /// Reads data from the memory mapping into the buffer.
+    /// 
+    /// 从内存映射中读取数据到缓冲区。
+    /// 
+    /// Reading a range that was already written, e.g. `receipt.range()` of a
+    /// [`WriteReceipt`], can run concurrently with writes to other ranges: the allocator hands
+    /// out disjoint ranges, so writers holding other ranges never touch these bytes.
+    /// Rewriting the same range while reading it is a data race.
+    /// 
+    /// 读取已写入的范围（例如 [`WriteReceipt`] 的 `receipt.range()`）可以与其他范围上的写入并发进行：
+    /// 分配器分配的范围互不重叠，因此持有其他范围的写入者永远不会访问这些字节。
+    /// 在读取的同时重写同一范围会导致数据竞争。
+    /// 
+    /// # Parameters
+    /// - `range`: Range to read
+    /// - `buf`: Buffer to receive data, length must be at least `range.len()`
+    /// 
+    /// # Returns
+    /// Number of bytes actually read
+    /// 
+    /// # 参数
+    /// - `range`: 要读取的范围
+    /// - `buf`: 接收数据的缓冲区，长度必须至少为 `range.len()`
+    /// 
+    /// # 返回值
+    /// 返回实际读取的字节数
+    pub fn read_range(&self, range: AllocatedRange, buf: &mut [u8]) -> Result<usize> {
+        let len = range.len() as usize;
+
+        if buf.len() < len {
+            return Err(Error::BufferTooSmall {
+                buffer_len: buf.len(),
+                range_len: range.len(),
+            });
+        }
+
+        self.prefetch_on_first_read(range);
+
+        // Safety: Read operations are safe
+        // Safety: 读取操作是安全的
+        unsafe { self.inner.read_at(range.start(), &mut buf[..len]) }
+    }
+
+    /// Prefetch `range` if prefetch on first read is on and the range was not read before
+    /// 
+    /// 如果启用了首次读取预取且该范围之前未被读取，则预取 `range`
+    fn prefetch_on_first_read(&self, range: AllocatedRange) {
+        let Some(prefetched) = &self.prefetched else {
+            return;
+        };
+        if range.is_empty() {
+            return;
+        }
+
+        let first = prefetched
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .insert(range.start());
+        if first {
+            // Only a hint, so failures are not worth failing the read over
+            // 只是提示，不值得因失败而让读取失败
+            let _ = self.inner.advise_will_need(range.start(), range.len() as usize);
+            #[cfg(test)]
+            self.prefetch_advised.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    /// Get the number of prefetch advices issued by `read_range`
+    /// 
+    /// 获取 `read_range` 发出的预取建议次数
+    #[cfg(test)]
+    pub(crate) fn prefetch_advised_count(&self) -> usize {
+        self.prefetch_advised.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Flush data to disk asynchronously
+    /// 
+    /// 异步刷新数据到磁盘
+    /// 
+    /// Initiates an asynchronous flush operation without blocking for completion.
+    /// The operating system will write data to disk in the background.
+    /// 
+    /// 发起异步刷新操作，不会阻塞等待完成。操作系统会在后台将数据写入磁盘。
+    pub fn flush(&self) -> Result<()> {
+        unsafe { self.inner.flush() }
+    }
+
+    /// Check that the receipts cover the whole file
+    /// 
+    /// 检查凭据是否覆盖整个文件
+    /// 
+    /// Guards against workers that silently skipped a chunk and left a zero hole. Meant to
+    /// be called before [`sync_all`](Self::sync_all), typically in debug builds. See
+    /// [`unwritten_gaps`](super::coverage::unwritten_gaps) to get the gaps without an error.
+    /// 
+    /// 防止某个 worker 悄悄跳过某个块而留下全零空洞。适合在 [`sync_all`](Self::sync_all)
+    /// 之前调用，通常用于 debug 构建。如需不以错误形式获取空洞，参见
+    /// [`unwritten_gaps`](super::coverage::unwritten_gaps)。
+    /// 
+    /// # Parameters
+    /// - `total_size`: Number of bytes from the start of the file that must be written
+    /// - `receipts`: Receipts of the ranges written so far, in any order
+    /// 
+    /// # 参数
+    /// - `total_size`: 从文件开头起必须被写入的字节数
+    /// - `receipts`: 迄今为止已写入范围的凭据，顺序任意
+    /// 
+    /// # Errors
+    /// Returns `IncompleteWrite` error listing the unwritten ranges if `[0, total_size)` is not
+    /// fully covered
+    /// 
+    /// # Errors
+    /// 如果 `[0, total_size)` 未被完全覆盖，返回列出未写入范围的 `IncompleteWrite` 错误
+    pub fn assert_fully_written(&self, total_size: u64, receipts: &[WriteReceipt]) -> Result<()> {
+        let gaps = super::coverage::unwritten_gaps(total_size, receipts);
+        if gaps.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::IncompleteWrite { gaps })
+        }
+    }
+
+    /// Flush data to disk synchronously
+    /// 
+    /// 同步刷新数据到磁盘
+    /// 
+    /// Synchronously flushes data in memory to disk, blocking until completion.
+    /// This is slower than `flush()` but guarantees data has been written to disk.
+    /// 
+    /// 同步将内存中的数据刷新到磁盘，阻塞直到完成。
+    /// 这比 `flush()` 慢，但保证数据已经写入磁盘。
+    /// 
+    /// # Safety
+    /// 
+    /// During the flush, the caller must ensure no other threads are modifying the
+    /// mapped memory. While sync itself is a safe operation, it is marked unsafe
+    /// for API consistency as it operates on data modified through unsafe methods.
+    /// 
+    /// # Safety
+    /// 
+    /// 在刷新期间，调用者需要确保没有其他线程正在修改映射的内存。
+    /// 虽然 sync 本身是安全的操作，但为了保持 API 一致性，
+    /// 它被标记为 unsafe，因为它操作的是通过 unsafe 方法修改的数据。
+    pub unsafe fn sync_all(&self) -> Result<()> {
+        unsafe { self.inner.sync_all() }
+    }
+
+    /// Get the highest end offset written so far
+    /// 
+    /// 获取迄今为止写入的最高结束偏移
+    /// 
+    /// See [`MmapFileInner::high_watermark`].
+    /// 
+    /// 见 [`MmapFileInner::high_watermark`]。
+    #[inline]
+    pub fn high_watermark(&self) -> u64 {
+        self.inner.high_watermark()
+    }
+
+    /// Enable or disable flushing when the last clone is dropped
+    /// 
+    /// 启用或禁用最后一个克隆被丢弃时的刷新
+    /// 
+    /// See [`MmapFileInner::set_sync_on_drop`].
+    /// 
+    /// 见 [`MmapFileInner::set_sync_on_drop`]。
+    #[inline]
+    pub fn set_sync_on_drop(&self, enabled: bool) {
+        self.inner.set_sync_on_drop(enabled)
+    }
+
+    /// Set the handler receiving errors from the flush on drop
+    /// 
+    /// 设置接收丢弃时刷新错误的处理器
+    /// 
+    /// See [`MmapFileInner::set_drop_error_handler`].
+    /// 
+    /// 见 [`MmapFileInner::set_drop_error_handler`]。
+    pub fn set_drop_error_handler(&self, handler: Box<dyn Fn(Error) + Send + Sync>) {
+        self.inner.set_drop_error_handler(handler)
+    }
+
+    /// Flush data up to the high watermark to disk synchronously
+    /// 
+    /// 同步刷新高水位线以下的数据到磁盘
+    /// 
+    /// Like [`sync_all`](Self::sync_all), but only flushes `[0, high_watermark)`.
+    /// 
+    /// 与 [`sync_all`](Self::sync_all) 类似，但只刷新 `[0, high_watermark)`。
+    /// 
+    /// # Safety
+    /// 
+    /// During the flush, the caller must ensure no other threads are modifying the
+    /// mapped memory.
+    /// 
+    /// # Safety
+    /// 
+    /// 在刷新期间，调用者需要确保没有其他线程正在修改映射的内存。
+    pub unsafe fn sync_to_watermark(&self) -> Result<()> {
+        unsafe { self.inner.sync_to_watermark() }
+    }
+
+    /// Flush a specific range to disk
+    /// 
+    /// 刷新指定区域到磁盘
+    /// 
+    /// Flushes only a portion of the file to disk, which can improve performance.
+    /// 
+    /// 只刷新文件的一部分到磁盘，可以提高性能。
+    /// 
+    /// By requiring a [`WriteReceipt`], this ensures only written ranges can be flushed,
+    /// providing compile-time safety guarantees.
+    /// 
+    /// 通过要求 [`WriteReceipt`] 凭据，确保只能刷新已写入的范围，
+    /// 提供编译期安全保证。
+    /// 
+    /// # Parameters
+    /// - `receipt`: Write receipt proving the range has been successfully written
+    /// 
+    /// # 参数
+    /// - `receipt`: 写入凭据，证明该范围已被成功写入
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::{MmapFile, Result, allocator::ALIGNMENT};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("output.bin");
+    /// # use std::num::NonZeroU64;
+    /// let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
+    /// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
     ///
     /// // Write and get receipt
     /// // 写入并获得凭据
@@ -479,6 +1619,351 @@ impl MmapFile {
         let range = receipt.range();
         unsafe { self.inner.flush_range(range.start(), range.len() as usize) }
     }
+
+    /// Write data to a range and synchronously flush exactly that range
+    /// 
+    /// 将数据写入范围，并同步刷新恰好该范围
+    /// 
+    /// Combines [`write_range`](Self::write_range) with a blocking flush of the same pages,
+    /// so the data is durable when this returns `Ok`. Suited to journal-style writers that
+    /// need each record on disk before proceeding.
+    /// 
+    /// 将 [`write_range`](Self::write_range) 与对相同页面的阻塞刷新结合，
+    /// 返回 `Ok` 时数据已持久化。适用于需要每条记录落盘后才能继续的日志式写入。
+    /// 
+    /// # Parameters
+    /// - `range`: Allocated file range
+    /// - `data`: Data to write (any `AsRef<[u8]>`), length must equal `range.len()`
+    /// 
+    /// # Returns
+    /// Returns [`WriteReceipt`] proving the range has been written and flushed
+    /// 
+    /// # 参数
+    /// - `range`: 已分配的文件范围
+    /// - `data`: 要写入的数据（任意 `AsRef<[u8]>`），长度必须等于 `range.len()`
+    /// 
+    /// # 返回值
+    /// 返回 [`WriteReceipt`] 凭据，证明该范围已被写入并刷新
+    /// 
+    /// # Errors
+    /// Returns corresponding I/O errors if the flush fails
+    /// 
+    /// # Errors
+    /// 如果刷新失败，返回相应的 I/O 错误
+    pub fn write_and_sync_range(&self, range: AllocatedRange, data: impl AsRef<[u8]>) -> Result<WriteReceipt> {
+        let receipt = self.write_range(range, data);
+        // Safety: the range was just written by this caller and RangeAllocator guarantees
+        // no other thread writes to it
+        // Safety: 该范围刚被调用者写入，且 RangeAllocator 保证没有其他线程写入它
+        unsafe { self.inner.sync_range(range.start(), range.len() as usize)? };
+        Ok(receipt)
+    }
+
+    /// Enable or disable prefetching ranges on their first read
+    /// 
+    /// 启用或禁用在范围首次读取时预取
+    /// 
+    /// When enabled, the first [`read_range`](Self::read_range) of a range asks the kernel to
+    /// read the whole range ahead (see [`MmapFileInner::advise_will_need`]), overlapping I/O for
+    /// the bytes that follow. Ranges are tracked by start offset in a set behind a mutex, so each
+    /// read pays a lock and a hash lookup, and the set grows by one entry per distinct range read.
+    /// Suited to read-mostly files with a moderate number of large ranges. Clones made afterwards
+    /// share the set; disabling drops it, so re-enabling starts afresh.
+    /// 
+    /// 启用后，范围的首次 [`read_range`](Self::read_range) 会请求内核预读整个范围
+    /// （见 [`MmapFileInner::advise_will_need`]），使后续字节的 I/O 重叠进行。
+    /// 范围按起始偏移记录在互斥锁保护的集合中，因此每次读取都要付出一次加锁和一次哈希查找，
+    /// 且每读取一个不同的范围，集合就增加一项。适用于范围数量适中、范围较大且以读为主的文件。
+    /// 之后创建的克隆共享该集合；禁用会丢弃集合，因此重新启用会从头开始。
+    /// 
+    /// # Parameters
+    /// - `enabled`: Whether to prefetch on first read
+    /// 
+    /// # 参数
+    /// - `enabled`: 是否在首次读取时预取
+    pub fn set_prefetch_on_first_read(&mut self, enabled: bool) {
+        match (enabled, &self.prefetched) {
+            (true, None) => self.prefetched = Some(Default::default()),
+            (false, Some(_)) => self.prefetched = None,
+            _ => {}
+        }
+    }
+
+    /// Enable or disable tracking of dirty pages
+    /// 
+    /// 启用或禁用脏页跟踪
+    /// 
+    /// When enabled, every write through this file sets a bit per 4K page it touches in an
+    /// atomic bitset (one bit per page, allocated up front), so [`sync_dirty`](Self::sync_dirty)
+    /// can flush only the pages modified since the last call. This suits sparse updates to
+    /// large files, where flushing the whole mapping costs far more than the pages changed.
+    /// The setting is shared by all clones, including ones made earlier, and the bitset follows
+    /// [`MmapFileInner::resize`]; disabling drops it, so re-enabling starts clean. Writes through
+    /// [`MmapFileInner`] or raw pointers are not tracked.
+    /// 
+    /// 启用后，通过此文件的每次写入都会在原子位图中为其涉及的每个 4K 页面设置一位
+    /// （每页一位，预先分配），使 [`sync_dirty`](Self::sync_dirty) 可以只刷新自上次调用以来修改过的页面。
+    /// 适用于对大文件的稀疏更新，此时刷新整个映射的开销远大于实际改动的页面。
+    /// 该设置由所有克隆共享（包括之前创建的克隆），位图会随 [`MmapFileInner::resize`] 调整；
+    /// 禁用会丢弃位图，因此重新启用会从干净状态开始。通过 [`MmapFileInner`] 或原始指针进行的写入不会被跟踪。
+    /// 
+    /// # Parameters
+    /// - `enabled`: Whether to track dirty pages
+    /// 
+    /// # 参数
+    /// - `enabled`: 是否跟踪脏页
+    pub fn set_dirty_tracking(&self, enabled: bool) {
+        self.inner.set_dirty_tracking(enabled);
+    }
+
+    /// Number of 4K pages in the file, counting a partial last page
+    /// 
+    /// 文件中 4K 页面的数量（包括不完整的最后一页）
+    #[inline]
+    fn page_count(&self) -> u64 {
+        self.size().get().div_ceil(allocator::ALIGNMENT)
+    }
+
+    /// Iterate over the indices of 4K pages written since the last [`sync_dirty`](Self::sync_dirty)
+    /// 
+    /// 遍历自上次 [`sync_dirty`](Self::sync_dirty) 以来写入过的 4K 页面索引
+    /// 
+    /// Page `i` covers `[i * ALIGNMENT, (i + 1) * ALIGNMENT)`. Indices are yielded in ascending
+    /// order from a snapshot taken word by word when this is called, so writes racing with the
+    /// call may or may not show up. Without [dirty tracking](Self::set_dirty_tracking) every page is reported,
+    /// since nothing is known to be clean.
+    /// 
+    /// 页面 `i` 覆盖 `[i * ALIGNMENT, (i + 1) * ALIGNMENT)`。索引按升序产生，
+    /// 来自逐字读取的快照，因此与调用并发的写入可能出现也可能不出现。
+    /// 未启用[脏页跟踪](Self::set_dirty_tracking)时会报告所有页面，因为无法确定任何页面是干净的。
+    pub fn dirty_pages(&self) -> impl Iterator<Item = u64> + use<> {
+        use std::sync::atomic::Ordering;
+
+        let words: Option<Vec<u64>> = self.inner.with_dirty(|words| {
+            words.map(|words| words.iter().map(|word| word.load(Ordering::Relaxed)).collect())
+        });
+        let pages = self.page_count();
+        (0..pages.div_ceil(64))
+            .flat_map(move |word| {
+                let mut bits = match &words {
+                    Some(words) => words.get(word as usize).copied().unwrap_or(0),
+                    None => u64::MAX,
+                };
+                std::iter::from_fn(move || {
+                    if bits == 0 {
+                        return None;
+                    }
+                    let bit = bits.trailing_zeros() as u64;
+                    bits &= bits - 1;
+                    Some(word * 64 + bit)
+                })
+            })
+            .take_while(move |&page| page < pages)
+    }
+
+    /// Synchronously flush only the dirty pages, then mark them clean
+    /// 
+    /// 只同步刷新脏页，然后将其标记为干净
+    /// 
+    /// Takes the dirty bits word by word and flushes each run of contiguous dirty pages with
+    /// one `msync`. Writes set a bit only after their bytes are in place, and a bit is cleared
+    /// before its page is flushed, so a write racing with the call either makes it into this
+    /// flush or leaves its page dirty for the next one. Without
+    /// [dirty tracking](Self::set_dirty_tracking) the whole file is flushed.
+    /// 
+    /// 逐字取出脏位，并对每段连续的脏页执行一次 `msync`。写入只在其字节就位后才设置脏位，
+    /// 而脏位在其页面刷新之前被清除，因此与调用并发的写入要么包含在本次刷新中，
+    /// 要么使其页面保持为脏页留待下次刷新。
+    /// 未启用[脏页跟踪](Self::set_dirty_tracking)时会刷新整个文件。
+    /// 
+    /// # Errors
+    /// Returns corresponding I/O errors if flushing fails; pages not yet flushed stay dirty
+    /// 
+    /// # Errors
+    /// 如果刷新失败，返回相应的 I/O 错误；尚未刷新的页面保持为脏页
+    pub fn sync_dirty(&self) -> Result<()> {
+        use std::sync::atomic::Ordering;
+
+        let size = self.size().get();
+        self.inner.with_dirty(|words| {
+            let Some(words) = words else {
+                // Safety: flushing does not touch the mapped bytes
+                // Safety: 刷新不会修改映射的字节
+                return unsafe { self.inner.sync_range(0, size as usize) };
+            };
+
+            // Flush pages [first, end), marking them dirty again if that fails
+            // 刷新页面 [first, end)，失败时将其重新标记为脏页
+            let flush = |first: u64, end: u64| {
+                let start = first * allocator::ALIGNMENT;
+                let len = (end * allocator::ALIGNMENT).min(size) - start;
+                // Safety: flushing does not touch the mapped bytes
+                // Safety: 刷新不会修改映射的字节
+                unsafe { self.inner.sync_range(start, len as usize) }
+                    .inspect_err(|_| super::mmap_file_inner::set_dirty_bits(words, start, len))
+            };
+
+            // Pending run of contiguous dirty pages as [first, end)
+            // 待刷新的连续脏页 [first, end)
+            let mut run: Option<(u64, u64)> = None;
+            for (index, word) in words.iter().enumerate() {
+                // Acquire pairs with the release in set_dirty_bits, so the flush sees the data
+                // Acquire 与 set_dirty_bits 中的 release 配对，使刷新能看到数据
+                let mut bits = word.swap(0, Ordering::Acquire);
+                while bits != 0 {
+                    let page = index as u64 * 64 + bits.trailing_zeros() as u64;
+                    run = match run {
+                        Some((first, end)) if end == page => Some((first, end + 1)),
+                        Some((first, end)) => {
+                            if let Err(err) = flush(first, end) {
+                                // Pages of this word not flushed yet stay dirty
+                                // 此字中尚未刷新的页面保持为脏页
+                                word.fetch_or(bits, Ordering::Relaxed);
+                                return Err(err);
+                            }
+                            Some((page, page + 1))
+                        }
+                        None => Some((page, page + 1)),
+                    };
+                    bits &= bits - 1;
+                }
+            }
+
+            match run {
+                Some((first, end)) => flush(first, end),
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Set the largest gap between ranges that receipt flushes still merge across
+    /// 
+    /// 设置凭据刷新时仍会跨越合并的范围之间的最大间隙
+    /// 
+    /// `msync` works on whole pages anyway, so flushing one span that covers a small
+    /// unwritten gap is often cheaper than issuing two calls. Applies to
+    /// [`flush_receipts`](Self::flush_receipts), [`flush_receipts_iter`](Self::flush_receipts_iter)
+    /// and [`flush_all_ranges`](Self::flush_all_ranges) of this handle; clones made afterwards
+    /// inherit the setting. Defaults to 0, which merges only contiguous or overlapping ranges.
+    /// 
+    /// `msync` 本来就以整页为单位，因此刷新一个覆盖小段未写入间隙的区间通常比发出两次调用更便宜。
+    /// 作用于此句柄的 [`flush_receipts`](Self::flush_receipts)、[`flush_receipts_iter`](Self::flush_receipts_iter)
+    /// 和 [`flush_all_ranges`](Self::flush_all_ranges)；之后创建的克隆会继承该设置。
+    /// 默认为 0，即只合并连续或重叠的范围。
+    /// 
+    /// # Parameters
+    /// - `gap_bytes`: Maximum gap in bytes to merge across
+    /// 
+    /// # 参数
+    /// - `gap_bytes`: 可跨越合并的最大间隙（字节）
+    #[inline]
+    pub fn set_flush_coalesce_gap(&mut self, gap_bytes: u64) {
+        self.flush_coalesce_gap = gap_bytes;
+    }
+
+    /// Flush the ranges of several receipts to disk
+    /// 
+    /// 刷新多个凭据对应的区域到磁盘
+    /// 
+    /// Receipts are sorted by start position and adjacent or overlapping ranges are
+    /// coalesced, so a batch of consecutive writes results in a single flush. Ranges
+    /// separated by at most [`set_flush_coalesce_gap`](Self::set_flush_coalesce_gap)
+    /// bytes are merged as well.
+    /// 
+    /// 凭据按起始位置排序，相邻或重叠的范围会被合并，
+    /// 因此一批连续写入只会触发一次刷新。间隔不超过
+    /// [`set_flush_coalesce_gap`](Self::set_flush_coalesce_gap) 字节的范围也会被合并。
+    /// 
+    /// # Parameters
+    /// - `receipts`: Write receipts proving the ranges have been written
+    /// 
+    /// # 参数
+    /// - `receipts`: 写入凭据，证明这些范围已被写入
+    pub fn flush_receipts(&self, receipts: &[WriteReceipt]) -> Result<()> {
+        let mut sorted = receipts.to_vec();
+        sorted.sort_unstable_by_key(|receipt| receipt.start());
+        self.flush_receipts_iter(sorted)
+    }
+
+    /// Synchronously flush a set of receipts as one ordered batch
+    /// 
+    /// 将一组凭据作为一个有序批次同步刷新
+    /// 
+    /// Issues a memory fence, flushes every merged range synchronously, then issues another
+    /// fence. When this returns `Ok`, the data of every receipt, as of the call, is on disk.
+    /// 
+    /// 先发出内存屏障，同步刷新每个合并后的区域，再发出一次内存屏障。
+    /// 返回 `Ok` 时，每个凭据在调用时刻的数据都已写入磁盘。
+    /// 
+    /// # Ordering
+    /// - Other processes mapping the same file share the page cache, so they observe writes
+    ///   as soon as they happen, independent of any flush. This method orders durability, not
+    ///   visibility.
+    /// - The leading fence ensures writes made by other threads that handed their receipts to
+    ///   this thread (through a join, channel or lock) are included in the flush.
+    /// - The trailing fence ensures anything this thread does after the call (e.g. publishing
+    ///   a commit marker) is ordered after the flushed data.
+    /// - The ranges are flushed one after another; a crash in the middle can leave some of
+    ///   them on disk and others not. Write a commit marker after this call returns if an
+    ///   all-or-nothing view is required.
+    /// 
+    /// # 顺序语义
+    /// - 映射同一文件的其他进程共享页缓存，因此写入一发生就对它们可见，与是否刷新无关。
+    ///   此方法保证的是持久化顺序，而不是可见性。
+    /// - 前置屏障保证将凭据交给当前线程的其他线程（通过 join、通道或锁）所做的写入都包含在刷新中。
+    /// - 后置屏障保证当前线程在调用之后的操作（例如发布提交标记）排在已刷新的数据之后。
+    /// - 各区域依次刷新；中途崩溃可能导致部分区域已落盘而其他区域没有。
+    ///   如需全有或全无的视图，请在此调用返回后再写入提交标记。
+    /// 
+    /// # Parameters
+    /// - `receipts`: Write receipts proving the ranges have been written
+    /// 
+    /// # 参数
+    /// - `receipts`: 写入凭据，证明这些范围已被写入
+    pub fn flush_all_ranges(&self, receipts: &[WriteReceipt]) -> Result<()> {
+        use std::sync::atomic::{fence, Ordering};
+
+        let mut sorted = receipts.to_vec();
+        sorted.sort_unstable_by_key(|receipt| receipt.start());
+
+        fence(Ordering::SeqCst);
+        coalesce_receipts(sorted, self.flush_coalesce_gap, |start, end| unsafe {
+            self.inner.sync_range(start, (end - start) as usize)
+        })?;
+        fence(Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    /// Flush the ranges of receipts produced by an iterator to disk
+    /// 
+    /// 刷新迭代器产生的凭据对应的区域到磁盘
+    /// 
+    /// Unlike [`flush_receipts`](Self::flush_receipts), receipts are merged in a single
+    /// streaming pass without collecting or sorting them: each receipt that is adjacent to
+    /// or overlaps the current span extends it, otherwise the current span is flushed and a
+    /// new one starts. Receipts arriving in order (e.g. from a sequential allocator or a
+    /// channel of ordered workers) coalesce fully; out-of-order receipts are still flushed
+    /// correctly, just with more flush calls.
+    /// 
+    /// 与 [`flush_receipts`](Self::flush_receipts) 不同，凭据在单次流式遍历中合并，
+    /// 不需要收集或排序：与当前区间相邻或重叠的凭据会扩展该区间，
+    /// 否则刷新当前区间并开始新的区间。按顺序到达的凭据（例如来自顺序分配器或有序 worker 的通道）
+    /// 会被完全合并；乱序的凭据仍会被正确刷新，只是刷新调用次数更多。
+    /// 
+    /// # Parameters
+    /// - `receipts`: Iterator of write receipts
+    /// 
+    /// # 参数
+    /// - `receipts`: 写入凭据的迭代器
+    pub fn flush_receipts_iter<I>(&self, receipts: I) -> Result<()>
+    where
+        I: IntoIterator<Item = WriteReceipt>,
+    {
+        coalesce_receipts(receipts, self.flush_coalesce_gap, |start, end| unsafe {
+            self.inner.flush_range(start, (end - start) as usize)
+        })
+    }
 }
 
 /// Implement Debug for MmapFile
@@ -502,3 +1987,43 @@ impl std::fmt::Debug for MmapFile {
 unsafe impl Send for MmapFile {}
 unsafe impl Sync for MmapFile {}
 
+/// Merge a stream of receipts into maximal contiguous spans `[start, end)`
+/// 
+/// 将凭据流合并为最大的连续区间 `[start, end)`
+/// 
+/// Calls `flush` once for every span, in the order spans are completed. Receipts whose
+/// start lies at most `gap` bytes past the current span are merged into it.
+/// 
+/// 每完成一个区间调用一次 `flush`，顺序与区间完成的顺序一致。
+/// 起始位置距离当前区间末尾不超过 `gap` 字节的凭据会被合并进该区间。
+pub(crate) fn coalesce_receipts<I, F>(receipts: I, gap: u64, mut flush: F) -> Result<()>
+where
+    I: IntoIterator<Item = WriteReceipt>,
+    F: FnMut(u64, u64) -> Result<()>,
+{
+    let mut current: Option<(u64, u64)> = None;
+
+    for receipt in receipts {
+        if receipt.is_empty() {
+            continue;
+        }
+
+        let (start, end) = (receipt.start(), receipt.end());
+        current = match current {
+            Some((cur_start, cur_end)) if start <= cur_end.saturating_add(gap) && end >= cur_start => {
+                Some((cur_start.min(start), cur_end.max(end)))
+            }
+            Some((cur_start, cur_end)) => {
+                flush(cur_start, cur_end)?;
+                Some((start, end))
+            }
+            None => Some((start, end)),
+        };
+    }
+
+    match current {
+        Some((start, end)) => flush(start, end),
+        None => Ok(()),
+    }
+}
+
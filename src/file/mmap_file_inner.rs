@@ -2,13 +2,18 @@
 //! 
 //! 基于 memmap2 的 Unsafe 无锁文件实现
 
-use memmap2::MmapMut;
+use memmap2::{MmapMut, MmapOptions};
 use std::cell::UnsafeCell;
-use std::fs::OpenOptions;
-use std::path::Path;
-use std::sync::Arc;
+use std::fs::{File, OpenOptions};
+use std::mem::MaybeUninit;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 use std::num::NonZeroU64;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
 use super::error::{Error, Result};
+use super::mapping::Mapping;
+use super::protection::Protection;
+use super::sys;
 
 /// High-performance memory-mapped file (Unsafe lock-free version)
 ///
@@ -46,13 +51,13 @@ use super::error::{Error, Result};
 ///
 /// # Limitations
 ///
-/// - File size must be specified at creation and cannot be dynamically expanded
+/// - File size must be specified at creation; changing it requires [`resize`](Self::resize)
 /// - Maximum file size is limited by system virtual memory
 /// - ⚠️ Users must ensure that concurrent writes do not overlap (runtime responsibility)
 ///
 /// # 限制
 ///
-/// - 创建时必须指定文件大小，不支持动态扩展
+/// - 创建时必须指定文件大小，修改大小需要调用 [`resize`](Self::resize)
 /// - 文件大小上限受系统虚拟内存限制
 /// - ⚠️ 用户需要确保不会并发写入重叠的内存区域（运行时责任）
 ///
@@ -72,6 +77,19 @@ use super::error::{Error, Result};
 /// 
 /// 那么就是完全安全的。但这些保证需要用户自行维护。
 ///
+/// # Integer Helpers
+///
+/// The `read_*_at` / `write_*_at` integer helpers (e.g. [`write_u32_le_at`](Self::write_u32_le_at))
+/// are bounds-checked: they return `OutOfBounds` error if the value does not fit within the
+/// file. Readers have the same safety requirements as [`read_at`](Self::read_at), writers
+/// the same as [`write_at`](Self::write_at).
+///
+/// # 整数读写
+///
+/// `read_*_at` / `write_*_at` 整数读写方法（例如 [`write_u32_le_at`](Self::write_u32_le_at)）
+/// 带有边界检查：如果值超出文件范围，返回 `OutOfBounds` 错误。读取方法的安全要求与
+/// [`read_at`](Self::read_at) 相同，写入方法与 [`write_at`](Self::write_at) 相同。
+///
 /// # Examples
 ///
 /// ```
@@ -101,6 +119,21 @@ use super::error::{Error, Result};
 /// ```
 #[derive(Clone)]
 pub struct MmapFileInner {
+    /// State shared by all clones of this mapping
+    /// 
+    /// 此映射所有克隆共享的状态
+    shared: Arc<Shared>,
+}
+
+/// Callback receiving errors from the flush on drop
+/// 
+/// 接收丢弃时刷新错误的回调
+type DropErrorHandler = Box<dyn Fn(Error) + Send + Sync>;
+
+/// State shared by all clones of a mapping
+/// 
+/// 映射的所有克隆共享的状态
+struct Shared {
     /// Mutable reference to memory mapping, using UnsafeCell for interior mutability
     /// 
     /// 内存映射的可变引用，使用 UnsafeCell 允许内部可变性
@@ -110,15 +143,139 @@ pub struct MmapFileInner {
     /// 
     /// # Safety
     /// 只要不同线程写入不重叠的区域，就是安全的
-    mmap: Arc<UnsafeCell<MmapMut>>,
-    
-    /// File size in bytes
+    mmap: UnsafeCell<Mapping>,
+
+    /// Backing file, retained so the mapping can be resized
+    /// 
+    /// 底层文件，保留以便调整映射大小
+    file: File,
+
+    /// Path the file was created or opened from, `None` if not backed by a named file
+    /// 
+    /// 创建或打开文件时使用的路径，不由具名文件支持时为 `None`
+    path: Option<PathBuf>,
+
+    /// File size in bytes (never 0)
+    /// 
+    /// 文件大小（永不为 0）
+    size: AtomicU64,
+
+    /// Current protection, encoded with [`Protection::to_u8`]
+    /// 
+    /// 当前的内存保护，使用 [`Protection::to_u8`] 编码
+    protection: AtomicU8,
+
+    /// Highest end offset written so far
+    /// 
+    /// 迄今为止写入的最高结束偏移
+    high_watermark: AtomicU64,
+
+    /// Whether the first 8 bytes hold a generation counter bumped by `sync_all`
+    /// 
+    /// 前 8 字节是否保存由 `sync_all` 递增的代数计数器
+    generation_header: AtomicBool,
+
+    /// Whether the mapping is private copy-on-write, opened by `open_copy_on_write`
+    /// 
+    /// 映射是否为由 `open_copy_on_write` 打开的私有写时复制映射
+    copy_on_write: AtomicBool,
+
+    /// Whether the mapping is flushed when the last clone is dropped
+    /// 
+    /// 最后一个克隆被丢弃时是否刷新映射
+    sync_on_drop: AtomicBool,
+
+    /// Receives errors from the flush performed on drop
+    /// 
+    /// 接收丢弃时刷新产生的错误
+    drop_error_handler: Mutex<Option<DropErrorHandler>>,
+
+    /// Whether dirty page tracking is on, checked before taking the `dirty` lock
+    /// 
+    /// 是否启用脏页跟踪，在获取 `dirty` 锁之前检查
+    dirty_tracking: AtomicBool,
+
+    /// Bitset of 4K pages written through [`MmapFile`](super::MmapFile) since the last
+    /// `sync_dirty`, `None` when dirty tracking is off
+    /// 
+    /// 自上次 `sync_dirty` 以来通过 [`MmapFile`](super::MmapFile) 写入过的 4K 页面位图，
+    /// 未启用脏页跟踪时为 `None`
+    dirty: RwLock<Option<Box<[AtomicU64]>>>,
+
+    /// Makes the flush on drop fail, for testing the error path
+    /// 
+    /// 使丢弃时的刷新失败，用于测试错误路径
+    #[cfg(test)]
+    fail_drop_flush: AtomicBool,
+}
+
+impl Shared {
+    /// Flush performed when the last clone is dropped or closed
     /// 
-    /// 文件大小
-    size: NonZeroU64,
+    /// 最后一个克隆被丢弃或关闭时执行的刷新
+    fn flush_on_drop(&mut self) -> Result<()> {
+        #[cfg(test)]
+        if *self.fail_drop_flush.get_mut() {
+            return Err(Error::Io(std::io::Error::other("injected flush failure")));
+        }
+
+        self.mmap.get_mut().flush()?;
+        Ok(())
+    }
+}
+
+/// Flush on drop if enabled, routing errors to the handler instead of panicking
+/// 
+/// 如果启用则在丢弃时刷新，将错误交给处理器而不是 panic
+impl Drop for Shared {
+    fn drop(&mut self) {
+        if !*self.sync_on_drop.get_mut() {
+            return;
+        }
+
+        if let Err(err) = self.flush_on_drop() {
+            let handler = self
+                .drop_error_handler
+                .get_mut()
+                .unwrap_or_else(PoisonError::into_inner);
+            if let Some(handler) = handler {
+                handler(err);
+            }
+        }
+    }
 }
 
 impl MmapFileInner {
+    /// Length of the generation counter header in bytes
+    /// 
+    /// 代数计数器头部的长度（字节）
+    pub const GENERATION_HEADER_LEN: u64 = 8;
+
+    /// Wrap a freshly created writable mapping
+    /// 
+    /// 包装新创建的可写映射
+    fn from_mmap(mmap: impl Into<Mapping>, file: File, path: Option<PathBuf>, size: NonZeroU64) -> Self {
+        Self {
+            #[allow(clippy::arc_with_non_send_sync)]
+            shared: Arc::new(Shared {
+                mmap: UnsafeCell::new(mmap.into()),
+                file,
+                path,
+                size: AtomicU64::new(size.get()),
+                protection: AtomicU8::new(Protection::ReadWrite.to_u8()),
+                high_watermark: AtomicU64::new(0),
+                generation_header: AtomicBool::new(false),
+                copy_on_write: AtomicBool::new(false),
+                sync_on_drop: AtomicBool::new(false),
+                drop_error_handler: Mutex::new(None),
+                dirty_tracking: AtomicBool::new(false),
+                dirty: RwLock::new(None),
+                #[cfg(test)]
+                fail_drop_flush: AtomicBool::new(false),
+            }),
+        }
+    }
+
     /// Create a new file and map it to memory
     ///
     /// 创建新文件并映射到内存
@@ -128,6 +285,18 @@ impl MmapFileInner {
     ///
     /// 如果文件已存在会被截断。文件会被预分配到指定大小。
     ///
+    /// # Note
+    /// The size is set with `set_len`, which on most filesystems creates a sparse file
+    /// without reserving disk blocks. If the disk fills up, the first write to an unbacked
+    /// page raises `SIGBUS` (unix) or an in-page error (Windows) instead of returning an
+    /// error. Use [`create_preallocated`](Self::create_preallocated) to detect insufficient
+    /// space up front.
+    ///
+    /// # 注意
+    /// 文件大小通过 `set_len` 设置，在大多数文件系统上这会创建稀疏文件而不预留磁盘块。
+    /// 如果磁盘写满，第一次写入没有实际存储的页面时会触发 `SIGBUS`（unix）或页内错误（Windows），
+    /// 而不是返回错误。使用 [`create_preallocated`](Self::create_preallocated) 可以预先检测空间不足。
+    ///
     /// # Parameters
     /// - `path`: File path
     /// - `size`: File size in bytes, must be > 0
@@ -154,14 +323,190 @@ impl MmapFileInner {
     ///
     /// # Errors
     /// - Returns `InvalidFileSize` error if size is 0
+    /// - Returns `FileTooLargeForPlatform` error if size exceeds `usize::MAX`
     /// - Returns corresponding I/O errors if file creation or memory mapping fails
     ///
     /// # Errors
     /// - 如果 size 为 0，返回 `InvalidFileSize` 错误
+    /// - 如果 size 超过 `usize::MAX`，返回 `FileTooLargeForPlatform` 错误
     /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
     pub fn create(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
+        Self::create_with_truncate(path, size, true)
+    }
+
+    /// Create a file of the given size, choosing whether existing content is truncated
+    ///
+    /// 创建指定大小的文件，并选择是否截断已有内容
+    ///
+    /// With `truncate` set this is [`create`](Self::create): an existing file is wiped and the
+    /// mapping starts out zeroed. Without it, an existing file keeps its content and is only
+    /// resized to `size`, so bytes past its old length read as zero and bytes past `size` are
+    /// cut off. Unlike [`create_or_open`](Self::create_or_open), the content is kept even when
+    /// the size differs.
+    ///
+    /// 设置 `truncate` 时与 [`create`](Self::create) 相同：已存在的文件会被清空，映射初始为零。
+    /// 不设置时，已存在的文件保留其内容，仅被调整为 `size`，因此超出原长度的字节读为零，
+    /// 超出 `size` 的字节被截掉。与 [`create_or_open`](Self::create_or_open) 不同，
+    /// 即使大小不同也会保留内容。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, must be > 0
+    /// - `truncate`: Whether to wipe an existing file first
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），必须大于 0
+    /// - `truncate`: 是否先清空已存在的文件
+    ///
+    /// # Errors
+    /// - Returns `FileTooLargeForPlatform` error if size exceeds `usize::MAX`
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果 size 超过 `usize::MAX`，返回 `FileTooLargeForPlatform` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    pub fn create_with_truncate(path: impl AsRef<Path>, size: NonZeroU64, truncate: bool) -> Result<Self> {
+        let path = path.as_ref();
+        let file = Self::create_file(path, size, truncate)?;
+
+        // Create memory mapping
+        // 创建内存映射
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        Ok(Self::from_mmap(mmap, file, Some(path.to_path_buf()), size))
+    }
+
+    /// Create a new file whose size is rounded up to the page size
+    ///
+    /// 创建大小向上对齐到页面大小的新文件
+    ///
+    /// Behaves like [`create`](Self::create), but rounds `size` up to a multiple of the OS
+    /// page size (at least one page), so the mapping fully covers the requested bytes.
+    /// The actual mapped size is available from [`size`](Self::size).
+    ///
+    /// 行为与 [`create`](Self::create) 相同，但将 `size` 向上对齐到操作系统页面大小的整数倍
+    /// （至少一页），使映射完全覆盖请求的字节。实际映射的大小可通过 [`size`](Self::size) 获取。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: Minimum file size in bytes, may be 0
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 最小文件大小（字节），可以为 0
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Result};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("aligned.bin");
+    /// let file = MmapFileInner::create_page_aligned(&path, 100)?;
+    /// assert!(file.size().get() >= 100);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// - Returns `SizeOverflow` error if rounding `size` up overflows `u64`
+    /// - Returns `FileTooLargeForPlatform` error if the rounded size does not fit the platform
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果将 `size` 向上对齐时溢出 `u64`，返回 `SizeOverflow` 错误
+    /// - 如果对齐后的大小超出平台限制，返回 `FileTooLargeForPlatform` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    pub fn create_page_aligned(path: impl AsRef<Path>, size: u64) -> Result<Self> {
+        let page_size = sys::page_size() as u64;
+        let aligned = size
+            .max(1)
+            .checked_next_multiple_of(page_size)
+            .ok_or(Error::SizeOverflow)?;
+
+        // aligned >= 1 by construction
+        // 按构造 aligned >= 1
+        Self::create(path, NonZeroU64::new(aligned).unwrap())
+    }
 
+    /// Open a file of the given size, creating or truncating it only when needed
+    ///
+    /// 打开指定大小的文件，仅在需要时创建或截断
+    ///
+    /// If `path` already holds a file of exactly `size` bytes, its content is mapped as is,
+    /// like [`open`](Self::open). Otherwise the file is created, or truncated and resized to
+    /// `size`, like [`create`](Self::create), and starts out zeroed. Useful for output files
+    /// that should be reused across runs instead of being wiped by every `create`.
+    ///
+    /// 如果 `path` 处已存在恰好 `size` 字节的文件，则按原样映射其内容，与 [`open`](Self::open) 相同。
+    /// 否则创建该文件，或将其截断并调整为 `size`，与 [`create`](Self::create) 相同，内容初始为零。
+    /// 适用于需要在多次运行之间复用、而不是每次 `create` 都被清空的输出文件。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节）
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Result, nz};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("reused.bin");
+    /// let file = MmapFileInner::create_or_open(&path, nz(1024))?;
+    /// unsafe { file.write_all_at(0, b"kept") };
+    /// drop(file);
+    ///
+    /// // Same size: the content is kept
+    /// // 大小相同：内容被保留
+    /// let file = MmapFileInner::create_or_open(&path, nz(1024))?;
+    /// assert_eq!(unsafe { file.read_slice(0, 4)? }, b"kept");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// - Returns `FileTooLargeForPlatform` error if size exceeds `usize::MAX`
+    /// - Returns corresponding I/O errors if opening, resizing or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果 size 超过 `usize::MAX`，返回 `FileTooLargeForPlatform` 错误
+    /// - 如果无法打开文件、调整大小或映射内存，返回相应的 I/O 错误
+    pub fn create_or_open(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
         let path = path.as_ref();
+        check_platform_size(size.get())?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        if file.metadata()?.len() != size.get() {
+            // Drop the old content first so the resized file starts zeroed, as with `create`
+            // 先丢弃旧内容，使调整大小后的文件与 `create` 一样初始为零
+            file.set_len(0)?;
+            file.set_len(size.get())?;
+        }
+
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+
+        Ok(Self::from_mmap(mmap, file, Some(path.to_path_buf()), size))
+    }
+
+    /// Create (or optionally truncate) the backing file and pre-allocate its size
+    ///
+    /// 创建（或按需截断）底层文件并预分配大小
+    pub(crate) fn create_file(path: &Path, size: NonZeroU64, truncate: bool) -> Result<File> {
+        check_platform_size(size.get())?;
 
         // Create file and pre-allocate size
         // 创建文件并预分配大小
@@ -169,20 +514,101 @@ impl MmapFileInner {
             .read(true)
             .write(true)
             .create(true)
-            .truncate(true)
+            .truncate(truncate)
             .open(path)?;
 
         file.set_len(size.get())?;
 
-        // Create memory mapping
-        // 创建内存映射
+        Ok(file)
+    }
+
+    /// Create a new file with its disk blocks reserved up front
+    ///
+    /// 创建新文件并预先预留其磁盘块
+    ///
+    /// Behaves like [`create`](Self::create), but additionally reserves the file's blocks
+    /// (`posix_fallocate` on Linux/Android/FreeBSD, `fcntl(F_PREALLOCATE)` on Apple platforms,
+    /// `FileAllocationInfo` on Windows). If the disk can't hold the file, this fails with
+    /// an `Io` error (`ENOSPC` on unix) instead of a `SIGBUS` on a later write.
+    ///
+    /// 行为与 [`create`](Self::create) 相同，但会额外预留文件的磁盘块
+    /// （Linux/Android/FreeBSD 上使用 `posix_fallocate`，Apple 平台上使用 `fcntl(F_PREALLOCATE)`，
+    /// Windows 上使用 `FileAllocationInfo`）。如果磁盘容纳不下该文件，此调用会返回 `Io` 错误
+    /// （unix 上为 `ENOSPC`），而不是在之后的写入时触发 `SIGBUS`。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, must be > 0
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），必须大于 0
+    ///
+    /// # Errors
+    /// - Returns an `Io` error if there isn't enough disk space
+    /// - Returns an `Io` error of kind `Unsupported` on platforms without preallocation
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果磁盘空间不足，返回 `Io` 错误
+    /// - 在不支持预分配的平台上，返回类型为 `Unsupported` 的 `Io` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    pub fn create_preallocated(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
+        let path = path.as_ref();
+        let file = Self::create_file(path, size, true)?;
+        sys::preallocate(&file, size.get())?;
+
         let mmap = unsafe { MmapMut::map_mut(&file)? };
 
-        Ok(Self {
-            #[allow(clippy::arc_with_non_send_sync)]
-            mmap: Arc::new(UnsafeCell::new(mmap)),
-            size,
-        })
+        Ok(Self::from_mmap(mmap, file, Some(path.to_path_buf()), size))
+    }
+
+    /// Create a new file whose pages are populated and locked in memory
+    ///
+    /// 创建新文件，并将其页面预先填充并锁定在内存中
+    ///
+    /// Behaves like [`create`](Self::create), but maps the file with `MAP_POPULATE` and then
+    /// locks the whole mapping (`mlock` on unix, `VirtualLock` on Windows), so that later
+    /// writes never page-fault or get paged out. Intended for workloads that can't tolerate
+    /// runtime page faults, such as real-time audio.
+    ///
+    /// 行为与 [`create`](Self::create) 相同，但使用 `MAP_POPULATE` 映射文件，随后锁定整个映射
+    /// （unix 上使用 `mlock`，Windows 上使用 `VirtualLock`），使后续写入不会触发缺页或被换出。
+    /// 适用于无法容忍运行时缺页的场景，例如实时音频。
+    ///
+    /// # Note
+    /// Locked memory is limited by `RLIMIT_MEMLOCK` on unix (often only a few MB for
+    /// unprivileged users) and by the working set size on Windows. Exceeding the limit makes
+    /// this call fail with the corresponding I/O error.
+    ///
+    /// # 注意
+    /// 在 unix 上锁定内存受 `RLIMIT_MEMLOCK` 限制（非特权用户通常只有几 MB），
+    /// 在 Windows 上受工作集大小限制。超过限制时此调用会返回相应的 I/O 错误。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, must be > 0
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），必须大于 0
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if file creation, memory mapping or locking fails
+    ///
+    /// # Errors
+    /// 如果无法创建文件、映射内存或锁定内存，返回相应的 I/O 错误
+    pub fn create_eager(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
+        let path = path.as_ref();
+        let file = Self::create_file(path, size, true)?;
+
+        let mmap = unsafe { MmapOptions::new().populate().map_mut(&file)? };
+
+        // Safety: mmap is a live mapping of mmap.len() bytes
+        // Safety: mmap 是长度为 mmap.len() 字节的有效映射
+        unsafe { sys::lock(mmap.as_ptr(), mmap.len())? };
+
+        Ok(Self::from_mmap(mmap, file, Some(path.to_path_buf()), size))
     }
 
     /// Open an existing file and map it to memory
@@ -227,74 +653,359 @@ impl MmapFileInner {
             0 => return Err(Error::EmptyFile),
             size => NonZeroU64::new(size).unwrap(),
         };
+        check_platform_size(size.get())?;
 
         let mmap = unsafe { MmapMut::map_mut(&file)? };
 
-        Ok(Self {
-            #[allow(clippy::arc_with_non_send_sync)]
-            mmap: Arc::new(UnsafeCell::new(mmap)),
-            size,
-        })
+        Ok(Self::from_mmap(mmap, file, Some(path.to_path_buf()), size))
     }
 
-    /// Write data at the specified position (lock-free operation)
+    /// Open an existing file as a private copy-on-write mapping
     ///
-    /// 在指定位置写入数据（无锁操作）
+    /// 以私有写时复制映射的方式打开已存在的文件
     ///
-    /// This is an extremely fast operation that writes directly to mapped memory
-    /// without requiring any locks.
-    /// 
-    /// 这是一个极快的操作，直接写入映射内存，不需要任何锁。
-    /// 
-    /// # Safety
-    /// 
-    /// The caller must ensure:
-    /// - Different threads do not write to overlapping memory regions concurrently
-    /// - No reads occur to the same region during writes
+    /// Maps the file with `MAP_PRIVATE` (`PAGE_WRITECOPY` on Windows): the mapping can be
+    /// written, but writes are private to this mapping and never reach the backing file,
+    /// which is only opened for reading. Useful for scratch space derived from a read-only
+    /// base. Flushing succeeds but has nothing to write back, and operations that remap the
+    /// file ([`resize`](Self::resize), [`refresh`](Self::refresh), [`remap`](Self::remap))
+    /// fail, since they would drop the private changes. [`persist`](Self::persist) is rejected, and
+    /// [`copy_to`](Self::copy_to) copies the mapped memory, private writes included.
     ///
-    /// Violating these constraints leads to data races, which is undefined behavior.
-    /// 
-    /// # Safety
-    /// 
-    /// 调用者需要确保：
-    /// - 不同线程不会并发写入重叠的内存区域
-    /// - 不会在写入时读取同一区域
+    /// Mappings in this crate are configured through named constructors rather than a
+    /// builder, so copy-on-write is selected with this constructor instead of a
+    /// `.copy_on_write(true)` option.
     ///
-    /// 违反这些约束会导致数据竞争，这是未定义行为。
+    /// 以 `MAP_PRIVATE`（Windows 上为 `PAGE_WRITECOPY`）映射文件：映射可写，
+    /// 但写入仅对此映射可见，永远不会到达底层文件，底层文件也只以只读方式打开。
+    /// 适用于基于只读底本派生的临时空间。刷新会成功但没有任何内容需要写回；
+    /// 重新映射文件的操作（[`resize`](Self::resize)、[`refresh`](Self::refresh)、[`remap`](Self::remap)）
+    /// 会失败，因为它们会丢弃私有修改。[`persist`](Self::persist) 会被拒绝，
+    /// [`copy_to`](Self::copy_to) 复制映射的内存，包括私有写入。
     ///
-    /// # Parameters
-    /// - `offset`: Write position (byte offset from file start)
-    /// - `data`: Data to write
+    /// 本库通过具名构造函数而不是构建器来配置映射，因此写时复制通过此构造函数选择，
+    /// 而不是 `.copy_on_write(true)` 选项。
     ///
-    /// # Returns
-    /// Number of bytes actually written
+    /// # Parameters
+    /// - `path`: File path
     ///
     /// # 参数
-    /// - `offset`: 写入位置（从文件开头的字节偏移）
-    /// - `data`: 要写入的数据
+    /// - `path`: 文件路径
     ///
-    /// # 返回值
-    /// 返回实际写入的字节数
+    /// # Errors
+    /// - Returns `EmptyFile` error if the file is empty
+    /// - Returns corresponding I/O errors if opening or memory mapping fails
     ///
-    /// # Examples
+    /// # Errors
+    /// - 如果文件为空，返回 `EmptyFile` 错误
+    /// - 如果无法打开文件或映射内存，返回相应的 I/O 错误
+    pub fn open_copy_on_write(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).open(path)?;
+
+        let size = match file.metadata()?.len() {
+            0 => return Err(Error::EmptyFile),
+            size => NonZeroU64::new(size).unwrap(),
+        };
+        check_platform_size(size.get())?;
+
+        let mmap = unsafe { MmapOptions::new().map_copy(&file)? };
+
+        let inner = Self::from_mmap(mmap, file, Some(path.to_path_buf()), size);
+        inner.shared.copy_on_write.store(true, Ordering::Relaxed);
+        Ok(inner)
+    }
+
+    /// Create a new file and map it at a fixed virtual address
     ///
-    /// ```
-    /// # use ranged_mmap::{MmapFileInner, Result};
-    /// # use tempfile::tempdir;
-    /// # fn main() -> Result<()> {
-    /// # let dir = tempdir()?;
-    /// # let path = dir.path().join("output.bin");
-    /// # use std::num::NonZeroU64;
-    /// let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap())?;
+    /// 创建新文件并将其映射到固定的虚拟地址
     ///
-    /// // Concurrent writes to non-overlapping regions using std::thread
-    /// // 使用 std::thread 并发写入不重叠区域
-    /// let file1 = file.clone();
-    /// let file2 = file.clone();
+    /// Maps the file with `MAP_FIXED`, so [`as_ptr`](Self::as_ptr) returns exactly `addr`.
+    /// Meant for the rare layouts that depend on absolute addresses, such as restoring a
+    /// snapshot containing raw pointers. The file is truncated if it exists. Operations that
+    /// remap the file ([`resize`](Self::resize), [`refresh`](Self::refresh), [`remap`](Self::remap))
+    /// move the mapping to an address chosen by the kernel.
     ///
-    /// std::thread::scope(|s| {
-    ///     // Safety: Two threads write to non-overlapping regions [0, 5) and [100, 105)
-    ///     // Safety: 两个线程写入不重叠的区域 [0, 5) 和 [100, 105)
+    /// 使用 `MAP_FIXED` 映射文件，因此 [`as_ptr`](Self::as_ptr) 恰好返回 `addr`。
+    /// 适用于依赖绝对地址的少见布局，例如恢复包含裸指针的快照。如果文件已存在，会被截断。
+    /// 重新映射文件的操作（[`resize`](Self::resize)、[`refresh`](Self::refresh)、[`remap`](Self::remap)）
+    /// 会将映射移动到由内核选择的地址。
+    ///
+    /// # Safety
+    ///
+    /// This is extremely dangerous. `MAP_FIXED` silently replaces any existing mapping in
+    /// `[addr, addr + size)`: the heap, a thread stack, the program's code or another file
+    /// mapping can be clobbered without any error, and whatever owned that memory will later
+    /// read garbage or unmap this file from under it. The caller must own the whole address
+    /// range, typically by reserving it first (e.g. with an anonymous mapping that is then
+    /// given up, not unmapped), and nothing may still reference memory in it. `addr` must be
+    /// page-aligned.
+    ///
+    /// # Safety
+    ///
+    /// 这极其危险。`MAP_FIXED` 会悄无声息地替换 `[addr, addr + size)` 中已有的任何映射：
+    /// 堆、线程栈、程序代码或其他文件映射都可能在没有任何错误的情况下被覆盖，
+    /// 原本拥有这些内存的代码之后会读到垃圾数据，或从本文件下方解除映射。
+    /// 调用者必须拥有整个地址区间，通常先预留它（例如使用之后放弃而非解除映射的匿名映射），
+    /// 且不能再有任何东西引用其中的内存。`addr` 必须页对齐。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes
+    /// - `addr`: Address to map the file at
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节）
+    /// - `addr`: 映射文件的地址
+    ///
+    /// # Errors
+    /// - Returns `FileTooLargeForPlatform` error if size exceeds `usize::MAX`
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails, e.g.
+    ///   `EINVAL` for an unaligned `addr`
+    ///
+    /// # Errors
+    /// - 如果 size 超过 `usize::MAX`，返回 `FileTooLargeForPlatform` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误，例如 `addr` 未对齐时为 `EINVAL`
+    #[cfg(unix)]
+    pub unsafe fn create_at_address(path: impl AsRef<Path>, size: NonZeroU64, addr: *mut u8) -> Result<Self> {
+        let path = path.as_ref();
+        let file = Self::create_file(path, size, true)?;
+
+        let mmap = unsafe { super::mapping::FixedMapping::map(&file, size.get() as usize, addr)? };
+
+        Ok(Self::from_mmap(Mapping::Fixed(mmap), file, Some(path.to_path_buf()), size))
+    }
+
+    /// Create a new file and map it with explicit protection
+    ///
+    /// 创建新文件并以指定的内存保护映射
+    ///
+    /// Behaves like [`create`](Self::create), then applies `prot` to the whole mapping.
+    /// See [`Protection`] for the W^X implications of executable mappings.
+    ///
+    /// 行为与 [`create`](Self::create) 相同，随后将 `prot` 应用到整个映射。
+    /// 可执行映射的 W^X 影响见 [`Protection`]。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, must be > 0
+    /// - `prot`: Initial protection of the mapping
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），必须大于 0
+    /// - `prot`: 映射的初始内存保护
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Protection, Result};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("code.bin");
+    /// # use std::num::NonZeroU64;
+    /// // Map writable, write code, then flip to read+exec (never both at once)
+    /// // 以可写方式映射，写入代码后切换为读+执行（两者从不同时存在）
+    /// let file = MmapFileInner::create_with_prot(&path, NonZeroU64::new(4096).unwrap(), Protection::ReadWrite)?;
+    /// unsafe {
+    ///     file.write_all_at(0, &[0xC3]);
+    ///     file.set_protection(Protection::ReadOnly)?;
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if file creation, memory mapping or changing the
+    /// protection fails
+    ///
+    /// # Errors
+    /// 如果无法创建文件、映射内存或修改内存保护，返回相应的 I/O 错误
+    pub fn create_with_prot(path: impl AsRef<Path>, size: NonZeroU64, prot: Protection) -> Result<Self> {
+        let file = Self::create(path, size)?;
+
+        if prot != Protection::ReadWrite {
+            // Safety: No other handle to this mapping exists yet
+            // Safety: 此映射尚不存在其他句柄
+            unsafe { file.set_protection(prot)? };
+        }
+
+        Ok(file)
+    }
+
+    /// Create a new file whose first 8 bytes hold a generation counter
+    ///
+    /// 创建新文件，其前 8 字节保存代数计数器
+    ///
+    /// Reserves `[0, GENERATION_HEADER_LEN)` for a `u64` counter (native byte order) that
+    /// every [`sync_all`](Self::sync_all) increments, readable via
+    /// [`generation`](Self::generation). Callers must not write to the reserved bytes.
+    ///
+    /// 保留 `[0, GENERATION_HEADER_LEN)` 存放 `u64` 计数器（本机字节序），每次
+    /// [`sync_all`](Self::sync_all) 都会递增它，可通过 [`generation`](Self::generation) 读取。
+    /// 调用者不得写入保留的字节。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes, including the header
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节），包含头部
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if `size` is smaller than the header
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果 `size` 小于头部大小，返回 `OutOfBounds` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    pub fn create_with_generation(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
+        Self::create(path, size)?.with_generation_header()
+    }
+
+    /// Open an existing file whose first 8 bytes hold a generation counter
+    ///
+    /// 打开已存在的文件，其前 8 字节保存代数计数器
+    ///
+    /// See [`create_with_generation`](Self::create_with_generation).
+    ///
+    /// 见 [`create_with_generation`](Self::create_with_generation)。
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the file is smaller than the header
+    /// - Returns corresponding I/O errors if opening or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果文件小于头部大小，返回 `OutOfBounds` 错误
+    /// - 如果无法打开文件或映射内存，返回相应的 I/O 错误
+    pub fn open_with_generation(path: impl AsRef<Path>) -> Result<Self> {
+        Self::open(path)?.with_generation_header()
+    }
+
+    /// Enable the generation counter header
+    ///
+    /// 启用代数计数器头部
+    fn with_generation_header(self) -> Result<Self> {
+        self.check_bounds(0, Self::GENERATION_HEADER_LEN)?;
+        self.shared.generation_header.store(true, Ordering::Relaxed);
+        Ok(self)
+    }
+
+    /// Change the protection of the whole mapping
+    ///
+    /// 修改整个映射的内存保护
+    ///
+    /// Uses `mprotect` on unix and `VirtualProtect` on Windows. The new protection is
+    /// shared by all clones of this mapping.
+    ///
+    /// 在 unix 上使用 `mprotect`，在 Windows 上使用 `VirtualProtect`。
+    /// 新的内存保护对此映射的所有克隆生效。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure:
+    /// - No other thread is accessing the mapping while the protection changes
+    /// - No writes (including through raw pointers and slices from
+    ///   [`as_atomic_bytes`](Self::as_atomic_bytes)) occur while the mapping is not
+    ///   [`ReadWrite`](Protection::ReadWrite); such writes fault and terminate the process
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保：
+    /// - 修改保护期间没有其他线程访问该映射
+    /// - 映射不是 [`ReadWrite`](Protection::ReadWrite) 时不会发生任何写入（包括通过原始指针以及
+    ///   从 [`as_atomic_bytes`](Self::as_atomic_bytes) 获取的切片）；这样的写入会触发内存错误并终止进程
+    ///
+    /// # Parameters
+    /// - `prot`: New protection
+    ///
+    /// # 参数
+    /// - `prot`: 新的内存保护
+    pub unsafe fn set_protection(&self, prot: Protection) -> Result<()> {
+        unsafe {
+            let mmap = &mut *self.shared.mmap.get();
+            sys::protect(mmap.as_mut_ptr(), mmap.len(), prot)?;
+        }
+
+        self.shared.protection.store(prot.to_u8(), Ordering::Release);
+        Ok(())
+    }
+
+    /// Get the current protection of the mapping
+    ///
+    /// 获取映射当前的内存保护
+    ///
+    /// Returns the protection last applied through [`set_protection`](Self::set_protection)
+    /// by any clone, tracked in the handle rather than queried from the kernel.
+    ///
+    /// 返回任一克隆最近一次通过 [`set_protection`](Self::set_protection) 设置的内存保护，
+    /// 该值记录在句柄中，而不是向内核查询。
+    #[inline]
+    pub fn protection(&self) -> Protection {
+        Protection::from_u8(self.shared.protection.load(Ordering::Acquire))
+    }
+
+    /// Write data at the specified position (lock-free operation)
+    ///
+    /// 在指定位置写入数据（无锁操作）
+    ///
+    /// This is an extremely fast operation that writes directly to mapped memory
+    /// without requiring any locks.
+    /// 
+    /// 这是一个极快的操作，直接写入映射内存，不需要任何锁。
+    /// 
+    /// # Safety
+    /// 
+    /// The caller must ensure:
+    /// - Different threads do not write to overlapping memory regions concurrently
+    /// - No reads occur to the same region during writes
+    ///
+    /// Violating these constraints leads to data races, which is undefined behavior.
+    /// 
+    /// # Safety
+    /// 
+    /// 调用者需要确保：
+    /// - 不同线程不会并发写入重叠的内存区域
+    /// - 不会在写入时读取同一区域
+    ///
+    /// 违反这些约束会导致数据竞争，这是未定义行为。
+    ///
+    /// # Parameters
+    /// - `offset`: Write position (byte offset from file start)
+    /// - `data`: Data to write (any `AsRef<[u8]>`, e.g. `Vec<u8>`, `[u8; N]` or `Bytes`)
+    ///
+    /// # Returns
+    /// Number of bytes actually written
+    ///
+    /// # 参数
+    /// - `offset`: 写入位置（从文件开头的字节偏移）
+    /// - `data`: 要写入的数据（任意 `AsRef<[u8]>`，例如 `Vec<u8>`、`[u8; N]` 或 `Bytes`）
+    ///
+    /// # 返回值
+    /// 返回实际写入的字节数
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Result};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("output.bin");
+    /// # use std::num::NonZeroU64;
+    /// let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap())?;
+    ///
+    /// // Concurrent writes to non-overlapping regions using std::thread
+    /// // 使用 std::thread 并发写入不重叠区域
+    /// let file1 = file.clone();
+    /// let file2 = file.clone();
+    ///
+    /// std::thread::scope(|s| {
+    ///     // Safety: Two threads write to non-overlapping regions [0, 5) and [100, 105)
+    ///     // Safety: 两个线程写入不重叠的区域 [0, 5) 和 [100, 105)
     ///     s.spawn(|| unsafe { file1.write_at(0, b"hello") });
     ///     s.spawn(|| unsafe { file2.write_at(100, b"world") });
     /// });
@@ -305,26 +1016,105 @@ impl MmapFileInner {
     /// ```
     ///
     #[inline]
-    pub unsafe fn write_at(&self, offset: u64, data: &[u8]) -> usize {
-        let offset_usize = offset as usize;
+    pub unsafe fn write_at(&self, offset: u64, data: impl AsRef<[u8]>) -> usize {
+        let data = data.as_ref();
         let len = data.len();
 
         debug_assert!(
-            offset_usize.saturating_add(len) <= self.size.get() as usize,
+            offset.saturating_add(len as u64) <= self.size().get(),
             "Write would exceed file size: offset={}, len={}, file_size={}",
-            offset, len, self.size.get()
+            offset, len, self.size().get()
         );
 
         // Safety: We assume the caller ensures different threads don't write to overlapping regions
         // Safety: 我们假设调用者确保不同线程不会写入重叠区域
+        let offset_usize = offset as usize;
         unsafe {
-            let mmap = &mut *self.mmap.get();
+            let mmap = &mut *self.shared.mmap.get();
             mmap[offset_usize..offset_usize + len].copy_from_slice(data);
         }
 
+        self.mark_written(offset + len as u64);
+
         len
     }
 
+    /// Raise the high watermark to at least `end`
+    ///
+    /// 将高水位线提升到至少 `end`
+    ///
+    /// Loads first so that writes below the watermark don't contend on the shared counter.
+    ///
+    /// 先读取再更新，使低于水位线的写入不会争用共享计数器。
+    #[inline]
+    pub(crate) fn mark_written(&self, end: u64) {
+        let watermark = &self.shared.high_watermark;
+        if end > watermark.load(Ordering::Relaxed) {
+            watermark.fetch_max(end, Ordering::Relaxed);
+        }
+    }
+
+    /// Enable or disable dirty page tracking for every clone of this mapping
+    ///
+    /// 为此映射的所有克隆启用或禁用脏页跟踪
+    ///
+    /// Enabling allocates a clean bitset with one bit per 4K page; disabling drops it.
+    ///
+    /// 启用时分配一个每 4K 页面一位的干净位图；禁用时丢弃位图。
+    pub(crate) fn set_dirty_tracking(&self, enabled: bool) {
+        let mut dirty = self.shared.dirty.write().unwrap_or_else(PoisonError::into_inner);
+        match (enabled, dirty.is_some()) {
+            (true, false) => *dirty = Some(dirty_bitset(self.size().get())),
+            (false, true) => *dirty = None,
+            _ => {}
+        }
+        self.shared.dirty_tracking.store(enabled, Ordering::Release);
+    }
+
+    /// Set the dirty bits of every 4K page `[offset, offset + len)` touches
+    ///
+    /// 设置 `[offset, offset + len)` 所涉及的每个 4K 页面的脏位
+    ///
+    /// Must be called after the bytes are written, so whoever clears the bit also sees them.
+    ///
+    /// 必须在字节写入之后调用，使清除脏位的一方也能看到这些字节。
+    #[inline]
+    pub(crate) fn mark_dirty(&self, offset: u64, len: u64) {
+        if len == 0 || !self.shared.dirty_tracking.load(Ordering::Acquire) {
+            return;
+        }
+        let dirty = self.shared.dirty.read().unwrap_or_else(PoisonError::into_inner);
+        if let Some(words) = dirty.as_deref() {
+            set_dirty_bits(words, offset, len);
+        }
+    }
+
+    /// Run `f` on the dirty bitset, or on `None` when dirty tracking is off
+    ///
+    /// 在脏页位图上运行 `f`，未启用脏页跟踪时传入 `None`
+    pub(crate) fn with_dirty<R>(&self, f: impl FnOnce(Option<&[AtomicU64]>) -> R) -> R {
+        let dirty = self.shared.dirty.read().unwrap_or_else(PoisonError::into_inner);
+        f(dirty.as_deref())
+    }
+
+    /// Resize the dirty bitset to `size` bytes, keeping the bits of pages that remain
+    ///
+    /// 将脏页位图调整为 `size` 字节，保留仍然存在的页面的脏位
+    fn resize_dirty(&self, size: u64) {
+        let mut dirty = self.shared.dirty.write().unwrap_or_else(PoisonError::into_inner);
+        let Some(words) = dirty.as_mut() else { return };
+
+        let mut resized = dirty_bitset(size);
+        for (new, old) in resized.iter_mut().zip(words.iter_mut()) {
+            *new.get_mut() = *old.get_mut();
+        }
+        let tail = size.div_ceil(super::allocator::ALIGNMENT) % 64;
+        if let (Some(last), true) = (resized.last_mut(), tail != 0) {
+            *last.get_mut() &= (1 << tail) - 1;
+        }
+        *words = resized;
+    }
+
     /// Write all data at the specified position
     ///
     /// 在指定位置写入所有数据
@@ -359,6 +1149,136 @@ impl MmapFileInner {
         unsafe { self.write_at(offset, data); }
     }
 
+    /// Write data at the specified position, reporting failures as errors
+    ///
+    /// 在指定位置写入数据，并以错误形式报告失败
+    ///
+    /// Like [`write_at`](Self::write_at), but checks bounds and protection up front. With
+    /// the `sigbus-guard` feature on unix, a SIGBUS raised because the filesystem is full is
+    /// caught and returned as `StorageFull` instead of killing the process. The pages that
+    /// faulted keep their previous contents; pages written before the fault may already hold
+    /// the new data.
+    ///
+    /// 与 [`write_at`](Self::write_at) 相同，但会预先检查边界和保护。
+    /// 在 unix 上启用 `sigbus-guard` feature 时，因文件系统已满而触发的 SIGBUS 会被捕获并
+    /// 返回 `StorageFull`，而不会终止进程。出错的页面保留原有内容；出错之前写入的页面可能已包含新数据。
+    ///
+    /// To recover, the guard replaces each faulting page with an anonymous page and maps the
+    /// file back over it afterwards. The remapped pages are no longer locked in memory (see
+    /// [`create_eager`](Self::create_eager)); their protection stays
+    /// [`ReadWrite`](Protection::ReadWrite), which the call requires anyway.
+    ///
+    /// 为了恢复，保护机制会将每个出错的页面替换为匿名页面，之后再将文件重新映射回该页面。
+    /// 重新映射的页面不再锁定在内存中（见 [`create_eager`](Self::create_eager)）；
+    /// 其内存保护保持为 [`ReadWrite`](Protection::ReadWrite)，而这本就是调用的前提。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure:
+    /// - The requirements of [`write_at`](Self::write_at) hold
+    /// - With `sigbus-guard`, no other thread (including other clones) reads or writes any
+    ///   page that `[offset, offset + data.len())` touches during the call, not even a
+    ///   different range of the same page: a faulting page is swapped out as a whole, so
+    ///   concurrent writes to it would be lost and reads would see zeros
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保：
+    /// - 满足 [`write_at`](Self::write_at) 的要求
+    /// - 启用 `sigbus-guard` 时，调用期间没有其他线程（包括其他克隆）读写 `[offset, offset + data.len())`
+    ///   所涉及的任何页面，即使是同一页面中的不同范围也不行：出错的页面会被整体换出，
+    ///   因此对其的并发写入会丢失，读取会看到 0
+    ///
+    /// # Parameters
+    /// - `offset`: Write position
+    /// - `data`: Data to write
+    ///
+    /// # 参数
+    /// - `offset`: 写入位置
+    /// - `data`: 要写入的数据
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the write exceeds the file size
+    /// - Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite)
+    /// - Returns `StorageFull` error if the filesystem ran out of space (`sigbus-guard` only)
+    ///
+    /// # Errors
+    /// - 如果写入超出文件大小，返回 `OutOfBounds` 错误
+    /// - 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误
+    /// - 如果文件系统空间不足，返回 `StorageFull` 错误（仅限 `sigbus-guard`）
+    pub unsafe fn try_write_at(&self, offset: u64, data: &[u8]) -> Result<usize> {
+        self.check_bounds(offset, data.len() as u64)?;
+        if !self.protection().is_writable() {
+            return Err(Error::ReadOnlyMapping);
+        }
+
+        #[cfg(all(unix, feature = "sigbus-guard"))]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            let len = self.size().get() as usize;
+            let base = unsafe { self.as_mut_ptr() };
+            let faulted = unsafe {
+                super::sigbus::guarded(base, len, self.shared.file.as_raw_fd(), || {
+                    self.write_at(offset, data);
+                })?
+            };
+            if faulted {
+                return Err(Error::StorageFull {
+                    offset,
+                    len: data.len(),
+                });
+            }
+            Ok(data.len())
+        }
+
+        #[cfg(not(all(unix, feature = "sigbus-guard")))]
+        Ok(unsafe { self.write_at(offset, data) })
+    }
+
+    /// Write data while holding the only handle to the mapping
+    ///
+    /// 在持有映射唯一句柄的情况下写入数据
+    ///
+    /// A safe counterpart of [`try_write_at`](Self::try_write_at): `&mut self` plus the check
+    /// that no other clone exists rule out any concurrent access from this process, so the
+    /// write cannot race. Other processes mapping the same file are outside this guarantee.
+    ///
+    /// [`try_write_at`](Self::try_write_at) 的安全版本：`&mut self` 加上不存在其他克隆的检查，
+    /// 排除了本进程中的任何并发访问，因此写入不会产生竞争。映射同一文件的其他进程不在此保证范围内。
+    ///
+    /// # Parameters
+    /// - `offset`: Write position
+    /// - `data`: Data to write
+    ///
+    /// # Returns
+    /// Number of bytes written
+    ///
+    /// # 参数
+    /// - `offset`: 写入位置
+    /// - `data`: 要写入的数据
+    ///
+    /// # 返回值
+    /// 返回写入的字节数
+    ///
+    /// # Errors
+    /// - Returns `MappingInUse` error if other clones of this mapping are alive
+    /// - Otherwise the same errors as [`try_write_at`](Self::try_write_at)
+    ///
+    /// # Errors
+    /// - 如果此映射的其他克隆仍然存活，返回 `MappingInUse` 错误
+    /// - 否则与 [`try_write_at`](Self::try_write_at) 的错误相同
+    pub fn write_at_exclusive(&mut self, offset: u64, data: &[u8]) -> Result<usize> {
+        let clones = Arc::strong_count(&self.shared) - 1;
+        if clones > 0 {
+            return Err(Error::MappingInUse { clones });
+        }
+
+        // Safety: this is the only handle and it is borrowed mutably, so nothing else can access the mapping
+        // Safety: 这是唯一的句柄且被可变借用，因此没有其他代码能访问该映射
+        unsafe { self.try_write_at(offset, data) }
+    }
+
     /// Read data at the specified position
     ///
     /// 在指定位置读取数据
@@ -392,25 +1312,263 @@ impl MmapFileInner {
     /// # 返回值
     /// 返回实际读取的字节数
     pub unsafe fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
-        let offset_usize = offset as usize;
         let len = buf.len();
 
-        if offset_usize >= self.size.get() as usize {
+        if offset >= self.size().get() {
             return Ok(0);
         }
 
-        let available = (self.size.get() as usize).saturating_sub(offset_usize).min(len);
+        // The size fits in usize (checked on map), so the offset does too
+        // 大小在映射时已检查能放入 usize，因此偏移量也能
+        let offset_usize = offset as usize;
+        let available = (self.size().get() as usize).saturating_sub(offset_usize).min(len);
 
         // Safety: Read operation is safe as long as no concurrent writes to the same region
         // Safety: 读取操作，只要不和写入同一区域并发就是安全的
         unsafe {
-            let mmap = &*self.mmap.get();
+            let mmap = &*self.shared.mmap.get();
             buf[..available].copy_from_slice(&mmap[offset_usize..offset_usize + available]);
         }
 
         Ok(available)
     }
 
+    /// Read data at the specified position into an uninitialized buffer
+    ///
+    /// 在指定位置读取数据到未初始化的缓冲区
+    ///
+    /// Same as [`read_at`](Self::read_at), but copies straight into uninitialized memory, so
+    /// large reads don't pay for zeroing a buffer that is about to be overwritten. Works well
+    /// with [`Vec::spare_capacity_mut`].
+    ///
+    /// 与 [`read_at`](Self::read_at) 相同，但直接拷贝到未初始化的内存中，
+    /// 因此大块读取无需为即将被覆盖的缓冲区支付清零开销。适合与 [`Vec::spare_capacity_mut`] 配合使用。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`read_at`](Self::read_at).
+    ///
+    /// # Safety
+    ///
+    /// 与 [`read_at`](Self::read_at) 的要求相同。
+    ///
+    /// # Parameters
+    /// - `offset`: Read position
+    /// - `buf`: Uninitialized buffer to receive data
+    ///
+    /// # 参数
+    /// - `offset`: 读取位置
+    /// - `buf`: 接收数据的未初始化缓冲区
+    ///
+    /// # Returns
+    /// The initialized prefix of `buf`, shorter than `buf` when the read reaches the end of the file
+    ///
+    /// # 返回值
+    /// `buf` 中已初始化的前缀；读取到达文件末尾时比 `buf` 短
+    pub unsafe fn read_into_uninit<'a>(
+        &self,
+        offset: u64,
+        buf: &'a mut [MaybeUninit<u8>],
+    ) -> Result<&'a mut [u8]> {
+        let offset_usize = offset.min(self.size().get()) as usize;
+        let available = (self.size().get() as usize).saturating_sub(offset_usize).min(buf.len());
+
+        // Safety: the source is within the mapping, the destination holds at least
+        // `available` bytes, and the copied prefix is initialized afterwards
+        // Safety: 源位于映射之内，目标至少能容纳 `available` 字节，拷贝后前缀即已初始化
+        unsafe {
+            let mmap = &*self.shared.mmap.get();
+            let dst = buf.as_mut_ptr() as *mut u8;
+            std::ptr::copy_nonoverlapping(mmap.as_ptr().add(offset_usize), dst, available);
+            Ok(std::slice::from_raw_parts_mut(dst, available))
+        }
+    }
+
+    /// Read a single byte at `offset`
+    ///
+    /// 在 `offset` 处读取单个字节
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`read_at`](Self::read_at).
+    ///
+    /// # Safety
+    ///
+    /// 与 [`read_at`](Self::read_at) 的要求相同。
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if `offset` is not within the file
+    ///
+    /// # Errors
+    /// 如果 `offset` 不在文件范围内，返回 `OutOfBounds` 错误
+    #[inline]
+    pub unsafe fn read_byte(&self, offset: u64) -> Result<u8> {
+        self.check_bounds(offset, 1)?;
+        // Safety: the offset is in bounds; the caller rules out concurrent writes to it
+        // Safety: 偏移量在边界内；调用者保证没有对其的并发写入
+        Ok(unsafe { *self.as_ptr().add(offset as usize) })
+    }
+
+    /// Write a single byte at `offset`
+    ///
+    /// 在 `offset` 处写入单个字节
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`write_at`](Self::write_at).
+    ///
+    /// # Safety
+    ///
+    /// 与 [`write_at`](Self::write_at) 的要求相同。
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if `offset` is not within the file
+    ///
+    /// # Errors
+    /// 如果 `offset` 不在文件范围内，返回 `OutOfBounds` 错误
+    #[inline]
+    pub unsafe fn write_byte(&self, offset: u64, byte: u8) -> Result<()> {
+        self.check_bounds(offset, 1)?;
+        // Safety: the offset is in bounds; the caller rules out overlapping writers
+        // Safety: 偏移量在边界内；调用者保证没有重叠的写入者
+        unsafe { *self.as_mut_ptr().add(offset as usize) = byte };
+        self.mark_written(offset + 1);
+        Ok(())
+    }
+
+    /// Write a `u32` at `offset` in little-endian byte order
+    ///
+    /// 以小端字节序在 `offset` 处写入 `u32`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn write_u32_le_at(&self, offset: u64, value: u32) -> Result<()> {
+        unsafe { self.write_array_at(offset, value.to_le_bytes()) }
+    }
+
+    /// Read a `u32` at `offset` in little-endian byte order
+    ///
+    /// 以小端字节序在 `offset` 处读取 `u32`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn read_u32_le_at(&self, offset: u64) -> Result<u32> {
+        unsafe { self.read_array_at(offset).map(u32::from_le_bytes) }
+    }
+
+    /// Write a `u32` at `offset` in big-endian byte order
+    ///
+    /// 以大端字节序在 `offset` 处写入 `u32`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn write_u32_be_at(&self, offset: u64, value: u32) -> Result<()> {
+        unsafe { self.write_array_at(offset, value.to_be_bytes()) }
+    }
+
+    /// Read a `u32` at `offset` in big-endian byte order
+    ///
+    /// 以大端字节序在 `offset` 处读取 `u32`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn read_u32_be_at(&self, offset: u64) -> Result<u32> {
+        unsafe { self.read_array_at(offset).map(u32::from_be_bytes) }
+    }
+
+    /// Write a `u64` at `offset` in little-endian byte order
+    ///
+    /// 以小端字节序在 `offset` 处写入 `u64`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn write_u64_le_at(&self, offset: u64, value: u64) -> Result<()> {
+        unsafe { self.write_array_at(offset, value.to_le_bytes()) }
+    }
+
+    /// Read a `u64` at `offset` in little-endian byte order
+    ///
+    /// 以小端字节序在 `offset` 处读取 `u64`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn read_u64_le_at(&self, offset: u64) -> Result<u64> {
+        unsafe { self.read_array_at(offset).map(u64::from_le_bytes) }
+    }
+
+    /// Write a `u64` at `offset` in big-endian byte order
+    ///
+    /// 以大端字节序在 `offset` 处写入 `u64`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn write_u64_be_at(&self, offset: u64, value: u64) -> Result<()> {
+        unsafe { self.write_array_at(offset, value.to_be_bytes()) }
+    }
+
+    /// Read a `u64` at `offset` in big-endian byte order
+    ///
+    /// 以大端字节序在 `offset` 处读取 `u64`
+    ///
+    /// # Safety
+    /// See [Integer Helpers](Self#integer-helpers) for the safety requirements and errors.
+    ///
+    /// # Safety
+    /// 安全要求与错误见[整数读写](Self#integer-helpers)。
+    #[inline]
+    pub unsafe fn read_u64_be_at(&self, offset: u64) -> Result<u64> {
+        unsafe { self.read_array_at(offset).map(u64::from_be_bytes) }
+    }
+
+    /// Bounds-checked write of a fixed-size byte array
+    ///
+    /// 带边界检查地写入定长字节数组
+    #[inline]
+    unsafe fn write_array_at<const N: usize>(&self, offset: u64, bytes: [u8; N]) -> Result<()> {
+        self.check_bounds(offset, N as u64)?;
+        unsafe { self.write_at(offset, bytes) };
+        Ok(())
+    }
+
+    /// Bounds-checked read of a fixed-size byte array
+    ///
+    /// 带边界检查地读取定长字节数组
+    #[inline]
+    unsafe fn read_array_at<const N: usize>(&self, offset: u64) -> Result<[u8; N]> {
+        self.check_bounds(offset, N as u64)?;
+        let mut bytes = [0u8; N];
+        unsafe { self.read_at(offset, &mut bytes)? };
+        Ok(bytes)
+    }
+
     /// Flush data to disk asynchronously
     ///
     /// 异步刷新数据到磁盘
@@ -452,7 +1610,7 @@ impl MmapFileInner {
     /// ```
     pub unsafe fn flush(&self) -> Result<()> {
         unsafe {
-            let mmap = &*self.mmap.get();
+            let mmap = &*self.shared.mmap.get();
             Ok(mmap.flush_async()?)
         }
     }
@@ -498,12 +1656,200 @@ impl MmapFileInner {
     /// # }
     /// ```
     pub unsafe fn sync_all(&self) -> Result<()> {
+        trace_span!("sync_all", size = self.size().get());
+
+        // A read-only mapping can't have changed, and writing would fault
+        // 只读映射不会发生变化，且写入会触发内存错误
+        if let Some(counter) = self.generation_counter()
+            && self.protection().is_writable()
+        {
+            counter.fetch_add(1, Ordering::Release);
+        }
+
         unsafe {
-            let mmap = &*self.mmap.get();
+            let mmap = &*self.shared.mmap.get();
             Ok(mmap.flush()?)
         }
     }
 
+    /// Synchronously flush the whole file, then release its pages from memory
+    ///
+    /// 同步刷新整个文件，然后从内存中释放其页面
+    ///
+    /// Runs [`sync_all`](Self::sync_all), then advises the kernel that the whole mapping is
+    /// no longer needed (`madvise(MADV_DONTNEED)` on unix, removal from the working set on
+    /// Windows). Useful after a checkpoint of a large batch, so the now clean pages stop
+    /// pressuring other processes. Later accesses fault the data back in from the file.
+    ///
+    /// 执行 [`sync_all`](Self::sync_all)，然后告知内核整个映射不再需要
+    /// （unix 上为 `madvise(MADV_DONTNEED)`，Windows 上为从工作集中移除）。
+    /// 适用于大批量写入完成检查点之后，使已变干净的页面不再给其他进程带来内存压力。
+    /// 之后的访问会从文件中重新读入数据。
+    ///
+    /// Locked pages cannot be released, so pages locked by [`create_eager`](Self::create_eager)
+    /// are unlocked first and stay unlocked afterwards.
+    ///
+    /// 锁定的页面无法释放，因此由 [`create_eager`](Self::create_eager) 锁定的页面会先被解锁，
+    /// 之后保持未锁定状态。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no other thread (including other clones) writes to the
+    /// mapping during the call; a write landing between the flush and the release could be
+    /// dropped.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保调用期间没有其他线程（包括其他克隆）写入该映射；
+    /// 在刷新和释放之间发生的写入可能会丢失。
+    ///
+    /// # Errors
+    /// - Returns `CopyOnWriteMapping` error for mappings from [`open_copy_on_write`](Self::open_copy_on_write),
+    ///   whose private changes would be discarded
+    /// - Returns corresponding I/O errors if flushing or releasing fails
+    ///
+    /// # Errors
+    /// - 对于 [`open_copy_on_write`](Self::open_copy_on_write) 打开的映射，返回 `CopyOnWriteMapping` 错误，
+    ///   因为其私有修改会被丢弃
+    /// - 如果刷新或释放失败，返回相应的 I/O 错误
+    pub unsafe fn sync_and_release(&self) -> Result<()> {
+        if self.shared.copy_on_write.load(Ordering::Relaxed) {
+            return Err(Error::CopyOnWriteMapping);
+        }
+
+        unsafe {
+            self.sync_all()?;
+            Ok(sys::release(self.as_mut_ptr(), self.size().get() as usize)?)
+        }
+    }
+
+    /// Flush the mapping, then sync the backing file with chosen durability
+    ///
+    /// 刷新映射，然后以选定的持久性级别同步底层文件
+    ///
+    /// Runs a synchronous `msync` of the whole mapping, then `fdatasync` on the backing file
+    /// when `data_only` is true, or `fsync` when it is false. Data-only syncs skip metadata
+    /// such as timestamps, which makes repeated checkpoints of a fixed-size file cheaper.
+    ///
+    /// 对整个映射执行同步 `msync`，然后在 `data_only` 为 true 时对底层文件执行 `fdatasync`，
+    /// 为 false 时执行 `fsync`。仅数据的同步会跳过时间戳等元数据，
+    /// 使固定大小文件的重复检查点开销更低。
+    ///
+    /// # Parameters
+    /// - `data_only`: Sync only file data (`fdatasync`) instead of data and metadata (`fsync`)
+    ///
+    /// # 参数
+    /// - `data_only`: 只同步文件数据（`fdatasync`），而不是数据和元数据（`fsync`）
+    ///
+    /// # Safety
+    ///
+    /// During the checkpoint, the caller must ensure no other threads are modifying the
+    /// mapped memory.
+    ///
+    /// # Safety
+    ///
+    /// 在检查点期间，调用者需要确保没有其他线程正在修改映射的内存。
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if flushing or syncing fails
+    ///
+    /// # Errors
+    /// 如果刷新或同步失败，返回相应的 I/O 错误
+    pub unsafe fn checkpoint(&self, data_only: bool) -> Result<()> {
+        trace_span!("checkpoint", data_only);
+
+        unsafe {
+            let mmap = &*self.shared.mmap.get();
+            mmap.flush()?;
+        }
+
+        if data_only {
+            self.shared.file.sync_data()?;
+        } else {
+            self.shared.file.sync_all()?;
+        }
+        Ok(())
+    }
+
+    /// Get the generation counter
+    ///
+    /// 获取代数计数器
+    ///
+    /// For mappings created with [`create_with_generation`](Self::create_with_generation) or
+    /// [`open_with_generation`](Self::open_with_generation), returns the counter stored in
+    /// the header, which every [`sync_all`](Self::sync_all) increments. Other processes
+    /// mapping the same file can poll it to detect changes. Returns 0 for mappings without
+    /// the header.
+    ///
+    /// 对于通过 [`create_with_generation`](Self::create_with_generation) 或
+    /// [`open_with_generation`](Self::open_with_generation) 创建的映射，返回保存在头部的计数器，
+    /// 每次 [`sync_all`](Self::sync_all) 都会递增它。映射同一文件的其他进程可以轮询它以检测变化。
+    /// 没有该头部的映射返回 0。
+    #[inline]
+    pub fn generation(&self) -> u64 {
+        self.generation_counter()
+            .map_or(0, |counter| counter.load(Ordering::Acquire))
+    }
+
+    /// Get the generation counter in the header, if enabled
+    ///
+    /// 获取头部中的代数计数器（如果已启用）
+    #[inline]
+    fn generation_counter(&self) -> Option<&AtomicU64> {
+        if !self.shared.generation_header.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        // Safety: the mapping is page-aligned and at least GENERATION_HEADER_LEN bytes long,
+        // and the header is only accessed atomically
+        // Safety: 映射页对齐且至少 GENERATION_HEADER_LEN 字节，且头部只通过原子操作访问
+        Some(unsafe { &*(self.as_ptr() as *const AtomicU64) })
+    }
+
+    /// Get the highest end offset written so far
+    ///
+    /// 获取迄今为止写入的最高结束偏移
+    ///
+    /// Tracks the maximum `offset + len` over all writes through this mapping and its clones,
+    /// regardless of write order. Writes made through raw pointers are not tracked.
+    ///
+    /// 跟踪通过此映射及其克隆进行的所有写入中 `offset + len` 的最大值，与写入顺序无关。
+    /// 通过原始指针进行的写入不会被跟踪。
+    #[inline]
+    pub fn high_watermark(&self) -> u64 {
+        self.shared.high_watermark.load(Ordering::Relaxed)
+    }
+
+    /// Flush data up to the high watermark to disk synchronously
+    ///
+    /// 同步刷新高水位线以下的数据到磁盘
+    ///
+    /// Like [`sync_all`](Self::sync_all), but only flushes `[0, high_watermark)`.
+    /// For sparse writers with a low watermark this avoids scanning the rest of the mapping.
+    ///
+    /// 与 [`sync_all`](Self::sync_all) 类似，但只刷新 `[0, high_watermark)`。
+    /// 对于水位线较低的稀疏写入者，可以避免扫描映射的其余部分。
+    ///
+    /// # Safety
+    /// 
+    /// During the flush, the caller must ensure no other threads are modifying the
+    /// mapped memory.
+    /// 
+    /// # Safety
+    /// 
+    /// 在刷新期间，调用者需要确保没有其他线程正在修改映射的内存。
+    pub unsafe fn sync_to_watermark(&self) -> Result<()> {
+        let watermark = self.high_watermark() as usize;
+        if watermark == 0 {
+            return Ok(());
+        }
+
+        unsafe {
+            let mmap = &*self.shared.mmap.get();
+            Ok(mmap.flush_range(0, watermark)?)
+        }
+    }
+
     /// Flush a specific range to disk
     ///
     /// 刷新指定区域到磁盘
@@ -525,30 +1871,862 @@ impl MmapFileInner {
     /// - `offset`: Start position of the flush range
     /// - `len`: Length of the flush range
     ///
-    /// # 参数
-    /// - `offset`: 刷新区域的起始位置
-    /// - `len`: 刷新区域的长度
-    pub unsafe fn flush_range(&self, offset: u64, len: usize) -> Result<()> {
-        let offset_usize = offset as usize;
+    /// # 参数
+    /// - `offset`: 刷新区域的起始位置
+    /// - `len`: 刷新区域的长度
+    pub unsafe fn flush_range(&self, offset: u64, len: usize) -> Result<()> {
+        debug_assert!(
+            offset.saturating_add(len as u64) <= self.size().get(),
+            "Flush range exceeds file size: offset={}, len={}, file_size={}",
+            offset, len, self.size().get()
+        );
+        trace_span!("flush_range", offset, len);
+        trace_event!(offset, len, "flush_range");
+
+        let offset_usize = offset as usize;
+        unsafe {
+            let mmap = &*self.shared.mmap.get();
+            Ok(mmap.flush_async_range(offset_usize, len)?)
+        }
+    }
+
+    /// Flush a specific range to disk and report how many pages it spans
+    ///
+    /// 刷新指定区域到磁盘，并报告其跨越的页面数
+    ///
+    /// Same as [`flush_range`](Self::flush_range). The count is the number of OS pages
+    /// touched by `[offset, offset + len)`, i.e. the page-aligned span handed to the kernel,
+    /// not the number of pages that were actually dirty. Useful for tracking flush volume.
+    ///
+    /// 与 [`flush_range`](Self::flush_range) 相同。计数为 `[offset, offset + len)` 所触及的
+    /// 操作系统页面数，即交给内核的页对齐区间，而不是实际为脏页的数量。适用于统计刷新量。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`flush_range`](Self::flush_range).
+    ///
+    /// # Safety
+    ///
+    /// 与 [`flush_range`](Self::flush_range) 的要求相同。
+    ///
+    /// # Parameters
+    /// - `offset`: Start position of the flush range
+    /// - `len`: Length of the flush range
+    ///
+    /// # Returns
+    /// Number of pages in the flushed span, 0 if `len` is 0
+    ///
+    /// # 参数
+    /// - `offset`: 刷新区域的起始位置
+    /// - `len`: 刷新区域的长度
+    ///
+    /// # 返回值
+    /// 刷新区间中的页面数，`len` 为 0 时为 0
+    pub unsafe fn flush_range_counted(&self, offset: u64, len: usize) -> Result<usize> {
+        unsafe { self.flush_range(offset, len)? };
+        if len == 0 {
+            return Ok(0);
+        }
+
+        let page_size = sys::page_size();
+        let first = offset as usize / page_size;
+        let last = (offset as usize + len).div_ceil(page_size);
+        Ok(last - first)
+    }
+
+    /// Flush a specific range to disk synchronously
+    ///
+    /// 同步刷新指定区域到磁盘
+    ///
+    /// Like [`flush_range`](Self::flush_range), but blocks until the range has been written.
+    ///
+    /// 与 [`flush_range`](Self::flush_range) 类似，但会阻塞直到该区域写入完成。
+    ///
+    /// # Safety
+    /// 
+    /// During the flush, the caller must ensure no other threads are modifying
+    /// memory in that region.
+    /// 
+    /// # Safety
+    /// 
+    /// 在刷新期间，调用者需要确保没有其他线程正在修改该区域的内存。
+    ///
+    /// # Parameters
+    /// - `offset`: Start position of the flush range
+    /// - `len`: Length of the flush range
+    ///
+    /// # 参数
+    /// - `offset`: 刷新区域的起始位置
+    /// - `len`: 刷新区域的长度
+    pub unsafe fn sync_range(&self, offset: u64, len: usize) -> Result<()> {
+        debug_assert!(
+            offset.saturating_add(len as u64) <= self.size().get(),
+            "Sync range exceeds file size: offset={}, len={}, file_size={}",
+            offset, len, self.size().get()
+        );
+
+        let offset_usize = offset as usize;
+        unsafe {
+            let mmap = &*self.shared.mmap.get();
+            Ok(mmap.flush_range(offset_usize, len)?)
+        }
+    }
+
+    /// Query which pages of a region are resident in memory
+    ///
+    /// 查询区域中哪些页面驻留在内存中
+    ///
+    /// Returns one `bool` per OS page touched by `[offset, offset + len)`, using `mincore`
+    /// on unix and `QueryWorkingSetEx` on Windows. Useful for skipping prefetch of data
+    /// that is already cached. The result is a snapshot and may be stale immediately.
+    ///
+    /// 对 `[offset, offset + len)` 涉及的每个操作系统页面返回一个 `bool`，
+    /// 在 unix 上使用 `mincore`，在 Windows 上使用 `QueryWorkingSetEx`。
+    /// 可用于跳过已缓存数据的预取。结果只是一个快照，可能立即过时。
+    ///
+    /// # Parameters
+    /// - `offset`: Start position of the region
+    /// - `len`: Length of the region
+    ///
+    /// # 参数
+    /// - `offset`: 区域的起始位置
+    /// - `len`: 区域的长度
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the region exceeds the file size
+    /// - Returns corresponding I/O errors if the query fails
+    ///
+    /// # Errors
+    /// - 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    /// - 如果查询失败，返回相应的 I/O 错误
+    pub fn resident_pages(&self, offset: u64, len: usize) -> Result<Vec<bool>> {
+        self.check_bounds(offset, len as u64)?;
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+
+        let page_size = sys::page_size();
+        let first = offset as usize / page_size;
+        let last = (offset as usize + len).div_ceil(page_size);
+
+        // Safety: [first, last) pages lie within the mapping, whose base is page-aligned
+        // Safety: [first, last) 页面位于映射之内，且映射基址页对齐
+        unsafe { Ok(sys::residency(self.as_ptr().add(first * page_size), last - first)?) }
+    }
+
+    /// Ask the kernel to prefetch a region
+    ///
+    /// 请求内核预取区域
+    ///
+    /// Issues `madvise(MADV_WILLNEED)` on unix and `PrefetchVirtualMemory` on Windows for the
+    /// pages covering `[offset, offset + len)`, so the reads are started in the background
+    /// before the data is touched. Purely advisory: the contents are not affected.
+    ///
+    /// 在 unix 上对覆盖 `[offset, offset + len)` 的页面调用 `madvise(MADV_WILLNEED)`，
+    /// 在 Windows 上调用 `PrefetchVirtualMemory`，使读取在访问数据之前就在后台开始。
+    /// 仅为建议：不影响内容。
+    ///
+    /// # Parameters
+    /// - `offset`: Start of the region
+    /// - `len`: Length of the region
+    ///
+    /// # 参数
+    /// - `offset`: 区域起始位置
+    /// - `len`: 区域长度
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the region exceeds the file size
+    /// - Returns corresponding I/O errors if the advice fails
+    ///
+    /// # Errors
+    /// - 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    /// - 如果建议失败，返回相应的 I/O 错误
+    pub fn advise_will_need(&self, offset: u64, len: usize) -> Result<()> {
+        self.check_bounds(offset, len as u64)?;
+        if len == 0 {
+            return Ok(());
+        }
+
+        let page_size = sys::page_size();
+        let first = offset as usize / page_size * page_size;
+        let end = offset as usize + len;
+
+        // Safety: [first, end) lies within the mapping, whose base is page-aligned
+        // Safety: [first, end) 位于映射之内，且映射基址页对齐
+        unsafe { Ok(sys::will_need(self.as_ptr().add(first), end - first)?) }
+    }
+
+    /// Commit the pages of a region ahead of writing
+    ///
+    /// 在写入之前提交区域的页面
+    ///
+    /// Writes to every page of `[offset, offset + len)` up front, so the kernel performs its
+    /// on-demand page allocation and zero-fill now rather than on the first real write.
+    /// Intended for latency-sensitive writers. Unlike a read-only prefault, this dirties the
+    /// pages. Each touched byte is rewritten with its current value, so contents are unchanged.
+    ///
+    /// 预先写入 `[offset, offset + len)` 的每个页面，使内核现在就完成按需分配页面和清零，
+    /// 而不是在第一次真正写入时。适用于对延迟敏感的写入者。与只读预取不同，这会使页面变脏。
+    /// 每个被访问的字节都以其当前值重新写入，因此内容不变。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`write_at`](Self::write_at): no other thread may read or write
+    /// the region concurrently.
+    ///
+    /// # Safety
+    ///
+    /// 与 [`write_at`](Self::write_at) 的要求相同：其他线程不得并发读写该区域。
+    ///
+    /// # Parameters
+    /// - `offset`: Region start
+    /// - `len`: Region length in bytes
+    ///
+    /// # 参数
+    /// - `offset`: 区域起始位置
+    /// - `len`: 区域长度（字节）
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the region exceeds the file size
+    /// - Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite)
+    ///
+    /// # Errors
+    /// - 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    /// - 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误
+    pub unsafe fn commit_range(&self, offset: u64, len: usize) -> Result<()> {
+        self.check_bounds(offset, len as u64)?;
+        if !self.protection().is_writable() {
+            return Err(Error::ReadOnlyMapping);
+        }
+        if len == 0 {
+            return Ok(());
+        }
+
+        let page_size = sys::page_size();
+        let start = offset as usize;
+        let end = start + len;
+        let base = unsafe { self.as_mut_ptr() };
+
+        // Touch the first byte of the region, then the start of every following page
+        // 访问区域的第一个字节，然后访问之后每个页面的起始位置
+        let mut pos = start;
+        while pos < end {
+            // Safety: pos lies within the bounds-checked region
+            // Safety: pos 位于已检查边界的区域之内
+            unsafe {
+                let ptr = base.add(pos);
+                ptr.write_volatile(ptr.read_volatile());
+            }
+            pos = (pos / page_size + 1) * page_size;
+        }
+
+        Ok(())
+    }
+
+    /// Check that `[offset, offset + len)` lies within the file
+    ///
+    /// 检查 `[offset, offset + len)` 是否位于文件之内
+    #[inline]
+    pub(crate) fn check_bounds(&self, offset: u64, len: u64) -> Result<()> {
+        match offset.checked_add(len) {
+            Some(end) if end <= self.size().get() => Ok(()),
+            _ => Err(Error::OutOfBounds {
+                offset,
+                len,
+                size: self.size().get(),
+            }),
+        }
+    }
+
+    /// Get the path the file was created or opened from
+    /// 
+    /// 获取创建或打开文件时使用的路径
+    /// 
+    /// Returns the path exactly as passed to the constructor (not canonicalized), or `None`
+    /// for mappings not backed by a named file.
+    /// 
+    /// 返回传给构造函数的原始路径（未规范化），不由具名文件支持的映射返回 `None`。
+    #[inline]
+    pub fn path(&self) -> Option<&Path> {
+        self.shared.path.as_deref()
+    }
+
+    /// Get file size
+    /// 
+    /// 获取文件大小
+    #[inline]
+    pub fn size(&self) -> NonZeroU64 {
+        // Safety: size is only ever stored from a NonZeroU64
+        // Safety: size 只会从 NonZeroU64 存入
+        unsafe { NonZeroU64::new_unchecked(self.shared.size.load(Ordering::Acquire)) }
+    }
+
+    /// Get file size as `usize`
+    /// 
+    /// 以 `usize` 获取文件大小
+    /// 
+    /// The size was checked to fit in `usize` when the file was mapped, so the cast is lossless.
+    /// There is no `is_empty`: a mapping is never empty.
+    /// 
+    /// 文件映射时已检查大小能放入 `usize`，因此转换不会丢失信息。没有 `is_empty`：映射永远不为空。
+    #[inline]
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> usize {
+        self.size().get() as usize
+    }
+
+    /// Copy the whole mapping into a new file and map it
+    ///
+    /// 将整个映射复制到新文件并映射它
+    ///
+    /// Creates (or truncates) `dest` with the same size and copies the current contents,
+    /// including writes not yet flushed. On Linux/Android the copy is done in the kernel
+    /// with `copy_file_range` when the filesystems allow it; otherwise it is copied through
+    /// memory in chunks. Mappings from [`open_copy_on_write`](Self::open_copy_on_write) are
+    /// always copied through memory, so their private writes are kept. The returned mapping
+    /// is independent of `self`.
+    ///
+    /// 创建（或截断）相同大小的 `dest` 并复制当前内容，包括尚未刷新的写入。
+    /// 在 Linux/Android 上，如果文件系统允许，会使用 `copy_file_range` 在内核中复制；
+    /// 否则按块通过内存复制。[`open_copy_on_write`](Self::open_copy_on_write) 打开的映射
+    /// 总是通过内存复制，以保留其私有写入。返回的映射与 `self` 相互独立。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no writes occur to the mapping during the copy.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保复制期间不会写入该映射。
+    ///
+    /// # Parameters
+    /// - `dest`: Path of the new file
+    ///
+    /// # 参数
+    /// - `dest`: 新文件的路径
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if the file cannot be created, copied or mapped
+    ///
+    /// # Errors
+    /// 如果无法创建、复制或映射文件，返回相应的 I/O 错误
+    pub unsafe fn copy_to(&self, dest: impl AsRef<Path>) -> Result<MmapFileInner> {
+        const CHUNK: usize = 1024 * 1024;
+
+        let dest = dest.as_ref();
+        let size = self.size();
+        let file = Self::create_file(dest, size, true)?;
+        // Private writes of a copy-on-write mapping never reach the file
+        // 写时复制映射的私有写入永远不会到达文件
+        let copied = !self.shared.copy_on_write.load(Ordering::Relaxed)
+            && sys::copy_file(&self.shared.file, &file, size.get())?;
+
+        let mmap = unsafe { MmapMut::map_mut(&file)? };
+        let copy = Self::from_mmap(mmap, file, Some(dest.to_path_buf()), size);
+
+        if !copied {
+            let src = unsafe { &*self.shared.mmap.get() };
+            let dst = unsafe { &mut *copy.shared.mmap.get() };
+            for (to, from) in dst.chunks_mut(CHUNK).zip(src.chunks(CHUNK)) {
+                to.copy_from_slice(from);
+            }
+        }
+        copy.mark_written(self.high_watermark());
+
+        Ok(copy)
+    }
+
+    /// Flush, unmap and atomically rename the backing file to `final_path`
+    ///
+    /// 刷新、解除映射，并将底层文件原子地重命名为 `final_path`
+    ///
+    /// Implements the write-to-temp-then-rename pattern: the file is synced, the mapping and
+    /// file handle are closed, and the file is renamed, so readers of `final_path` see either
+    /// the old file or the complete new one. On unix the parent directory of `final_path` is
+    /// synced afterwards so the rename itself survives a crash. `final_path` must be on the
+    /// same filesystem as the current path.
+    ///
+    /// 实现"先写临时文件再重命名"模式：同步文件，关闭映射和文件句柄，然后重命名文件，
+    /// 因此 `final_path` 的读者要么看到旧文件，要么看到完整的新文件。
+    /// 在 unix 上之后还会同步 `final_path` 的父目录，使重命名本身在崩溃后依然有效。
+    /// `final_path` 必须与当前路径位于同一文件系统。
+    ///
+    /// # Parameters
+    /// - `final_path`: Destination path, replaced if it exists
+    ///
+    /// # 参数
+    /// - `final_path`: 目标路径，若已存在则被替换
+    ///
+    /// # Errors
+    /// - Returns `CopyOnWriteMapping` error for mappings from [`open_copy_on_write`](Self::open_copy_on_write),
+    ///   whose writes never reach the file being renamed
+    /// - Returns `MappingInUse` error if other clones of this mapping are alive
+    /// - Returns `NoBackingPath` error if the mapping has no path
+    /// - Returns corresponding I/O errors if the flush or rename fails
+    ///
+    /// # Errors
+    /// - 对于 [`open_copy_on_write`](Self::open_copy_on_write) 打开的映射，返回 `CopyOnWriteMapping` 错误，
+    ///   其写入永远不会到达被重命名的文件
+    /// - 如果此映射的其他克隆仍然存活，返回 `MappingInUse` 错误
+    /// - 如果映射没有路径，返回 `NoBackingPath` 错误
+    /// - 如果刷新或重命名失败，返回相应的 I/O 错误
+    pub fn persist(self, final_path: impl AsRef<Path>) -> Result<()> {
+        if self.shared.copy_on_write.load(Ordering::Relaxed) {
+            return Err(Error::CopyOnWriteMapping);
+        }
+        let clones = Arc::strong_count(&self.shared) - 1;
+        if clones > 0 {
+            return Err(Error::MappingInUse { clones });
+        }
+        let Some(path) = self.shared.path.clone() else {
+            return Err(Error::NoBackingPath);
+        };
+
+        // Safety: this is the only handle, so no other thread can be writing
+        // Safety: 这是唯一的句柄，因此没有其他线程可能在写入
+        unsafe { self.sync_all()? };
+
+        // Close the mapping and the file before renaming; Windows refuses to rename a mapped file
+        // 重命名之前关闭映射和文件；Windows 不允许重命名已映射的文件
+        self.shared.sync_on_drop.store(false, Ordering::Relaxed);
+        drop(self);
+
+        let final_path = final_path.as_ref();
+        std::fs::rename(&path, final_path)?;
+
+        #[cfg(unix)]
+        if let Some(parent) = final_path.parent() {
+            let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+            File::open(parent)?.sync_all()?;
+        }
+
+        Ok(())
+    }
+
+    /// Find the first byte at which two mappings differ
+    ///
+    /// 查找两个映射第一个不同字节的位置
+    ///
+    /// Compares a machine word at a time and only inspects individual bytes within the
+    /// first differing word.
+    ///
+    /// 每次比较一个机器字，只在第一个不同的字内逐字节检查。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no writes occur to either mapping during the comparison.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保比较期间不会写入任一映射。
+    ///
+    /// # Parameters
+    /// - `other`: Mapping to compare against
+    ///
+    /// # Returns
+    /// `None` if the contents are identical, otherwise `Some(offset)` of the first difference
+    ///
+    /// # 参数
+    /// - `other`: 要比较的映射
+    ///
+    /// # 返回值
+    /// 内容相同时返回 `None`，否则返回第一个不同字节的偏移 `Some(offset)`
+    ///
+    /// # Errors
+    /// Returns `SizeMismatch` error if the two mappings have different sizes
+    ///
+    /// # Errors
+    /// 如果两个映射大小不同，返回 `SizeMismatch` 错误
+    pub unsafe fn diff(&self, other: &MmapFileInner) -> Result<Option<u64>> {
+        const WORD: usize = std::mem::size_of::<usize>();
+
+        if self.size() != other.size() {
+            return Err(Error::SizeMismatch {
+                size: self.size().get(),
+                other: other.size().get(),
+            });
+        }
+
+        let a: &[u8] = unsafe { &*self.shared.mmap.get() };
+        let b: &[u8] = unsafe { &*other.shared.mmap.get() };
+
+        let first_diff = |base: usize, x: &[u8], y: &[u8]| {
+            x.iter()
+                .zip(y)
+                .position(|(p, q)| p != q)
+                .map(|pos| (base + pos) as u64)
+        };
+
+        let words = a.chunks_exact(WORD).zip(b.chunks_exact(WORD));
+        for (i, (x, y)) in words.enumerate() {
+            let wx = usize::from_ne_bytes(x.try_into().unwrap());
+            let wy = usize::from_ne_bytes(y.try_into().unwrap());
+            if wx != wy {
+                return Ok(first_diff(i * WORD, x, y));
+            }
+        }
+
+        let tail = a.len() - a.len() % WORD;
+        Ok(first_diff(tail, &a[tail..], &b[tail..]))
+    }
+
+    /// Enable or disable flushing when the last clone is dropped
+    /// 
+    /// 启用或禁用最后一个克隆被丢弃时的刷新
+    /// 
+    /// Disabled by default. The flush on drop never panics: errors are passed to the
+    /// handler set with [`set_drop_error_handler`](Self::set_drop_error_handler), or
+    /// discarded if none is set. Call [`sync_all`](Self::sync_all) explicitly when the
+    /// outcome matters.
+    /// 
+    /// 默认禁用。丢弃时的刷新永远不会 panic：错误会传给通过
+    /// [`set_drop_error_handler`](Self::set_drop_error_handler) 设置的处理器，
+    /// 未设置时则被丢弃。需要确认结果时请显式调用 [`sync_all`](Self::sync_all)。
+    /// 
+    /// # Parameters
+    /// - `enabled`: Whether to flush on drop
+    /// 
+    /// # 参数
+    /// - `enabled`: 是否在丢弃时刷新
+    #[inline]
+    pub fn set_sync_on_drop(&self, enabled: bool) {
+        self.shared.sync_on_drop.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Set the handler receiving errors from the flush on drop
+    /// 
+    /// 设置接收丢弃时刷新错误的处理器
+    /// 
+    /// The handler runs inside `Drop`, possibly during unwinding, so it must not panic.
+    /// 
+    /// 处理器在 `Drop` 中运行，可能处于栈展开期间，因此不得 panic。
+    /// 
+    /// # Parameters
+    /// - `handler`: Called with the error if the flush on drop fails
+    /// 
+    /// # 参数
+    /// - `handler`: 丢弃时刷新失败时以错误调用
+    pub fn set_drop_error_handler(&self, handler: Box<dyn Fn(Error) + Send + Sync>) {
+        *self
+            .shared
+            .drop_error_handler
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(handler);
+    }
+
+    /// Flush synchronously and release this handle, reporting errors
+    /// 
+    /// 同步刷新并释放此句柄，同时报告错误
+    /// 
+    /// An explicit, fallible alternative to dropping the handle. On the last handle the mapping
+    /// is flushed, then unmapped and the file closed; the flush on drop is skipped since it
+    /// has already been done. If other clones are alive the mapping is only flushed and this
+    /// handle released, leaving the mapping to the remaining clones.
+    /// 
+    /// 丢弃句柄的显式、可失败的替代方式。对于最后一个句柄，映射会先被刷新，然后解除映射并关闭文件；
+    /// 由于已经刷新过，丢弃时的刷新会被跳过。如果仍有其他克隆存活，则只刷新映射并释放此句柄，
+    /// 映射留给其余克隆使用。
+    /// 
+    /// # Errors
+    /// Returns corresponding I/O errors if the flush fails; the handle is released regardless
+    /// 
+    /// # Errors
+    /// 如果刷新失败，返回相应的 I/O 错误；无论如何句柄都会被释放
+    pub fn close(self) -> Result<()> {
+        match Arc::try_unwrap(self.shared) {
+            Ok(mut shared) => {
+                *shared.sync_on_drop.get_mut() = false;
+                shared.flush_on_drop()
+            }
+            Err(shared) => {
+                // Safety: msync hands the pages to the kernel without reading them through Rust
+                // Safety: msync 将页面交给内核，不会通过 Rust 读取它们
+                unsafe { (*shared.mmap.get()).flush()? };
+                Ok(())
+            }
+        }
+    }
+
+    /// Make the flush on drop fail
+    /// 
+    /// 使丢弃时的刷新失败
+    #[cfg(test)]
+    pub(crate) fn inject_drop_flush_failure(&self) {
+        self.shared.fail_drop_flush.store(true, Ordering::Relaxed);
+    }
+
+    /// Resize the backing file and remap it
+    ///
+    /// 调整底层文件大小并重新映射
+    ///
+    /// When the file grows, the newly added `[old_size, new_size)` range is explicitly
+    /// zero-filled after remapping, so it reads back as 0 on every platform regardless of
+    /// how the filesystem extends files. This touches every new page, materializing it in
+    /// memory (and on disk once flushed). When the file shrinks, the high watermark is
+    /// clamped to the new size.
+    ///
+    /// 文件增长时，重新映射后会显式将新增的 `[old_size, new_size)` 区间清零，
+    /// 因此无论文件系统如何扩展文件，在所有平台上读取到的都是 0。
+    /// 这会访问每个新页面，使其在内存中实际分配（刷盘后也会在磁盘上分配）。
+    /// 文件缩小时，高水位线会被截断到新的大小。
+    ///
+    /// The current protection is re-applied to the new mapping. Pages locked by
+    /// [`create_eager`](Self::create_eager) are not locked again.
+    ///
+    /// 当前的内存保护会重新应用到新映射上。由 [`create_eager`](Self::create_eager)
+    /// 锁定的页面不会被重新锁定。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure:
+    /// - No other thread (including other clones) is accessing the mapping during the resize
+    /// - No pointers obtained from [`as_ptr`](Self::as_ptr) or [`as_mut_ptr`](Self::as_mut_ptr)
+    ///   and no slices from [`as_atomic_bytes`](Self::as_atomic_bytes) are used afterwards;
+    ///   the mapping may move
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保：
+    /// - 调整大小期间没有其他线程（包括其他克隆）访问该映射
+    /// - 之后不再使用从 [`as_ptr`](Self::as_ptr) 或 [`as_mut_ptr`](Self::as_mut_ptr)
+    ///   获取的指针，以及从 [`as_atomic_bytes`](Self::as_atomic_bytes) 获取的切片；映射可能会移动
+    ///
+    /// # Parameters
+    /// - `new_size`: New file size in bytes
+    ///
+    /// # 参数
+    /// - `new_size`: 新的文件大小（字节）
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if resizing the file or remapping fails. The file and
+    /// the mapping are then left at the old size, with their protection and page locks. On
+    /// Windows the old mapping has to be released first, so it is mapped again without page
+    /// locks; if even that fails, the handle keeps a 1-byte placeholder mapping and
+    /// [`size`](Self::size) becomes 1, so later accesses stay in bounds.
+    ///
+    /// # Errors
+    /// 如果调整文件大小或重新映射失败，返回相应的 I/O 错误。此时文件和映射保持原大小，
+    /// 内存保护和页面锁定也保持不变。在 Windows 上必须先释放旧映射，因此会重新映射文件但不再锁定页面；
+    /// 如果这也失败，句柄将保留一个 1 字节的占位映射，且 [`size`](Self::size) 变为 1，
+    /// 使之后的访问不会越界。
+    pub unsafe fn resize(&self, new_size: NonZeroU64) -> Result<()> {
+        check_platform_size(new_size.get())?;
+
+        let old_size = self.size().get();
+        let mmap = unsafe { &mut *self.shared.mmap.get() };
+
+        // Windows refuses to resize a mapped file, so the old mapping has to go first there
+        // Windows 不允许调整已映射文件的大小，因此在其上必须先释放旧映射
+        #[cfg(windows)]
+        {
+            *mmap = MmapMut::map_anon(1)?.into();
+        }
+
+        match self.map_resized(old_size, new_size.get()) {
+            Ok(resized) => *mmap = resized,
+            Err(err) => {
+                #[cfg(windows)]
+                self.restore_mapping(mmap, old_size);
+                return Err(err);
+            }
+        }
+
+        self.shared.size.store(new_size.get(), Ordering::Release);
+        self.shared.high_watermark.fetch_min(new_size.get(), Ordering::Relaxed);
+        self.resize_dirty(new_size.get());
+        Ok(())
+    }
+
+    /// Resize the backing file and map it at the new size
+    ///
+    /// 调整底层文件大小并按新大小映射
+    ///
+    /// The new mapping is zero-filled beyond `old_size` and protected like the current one.
+    /// On failure the file is set back to `old_size`, and on unix the current mapping is
+    /// never touched: a shrinking file is mapped before it is truncated.
+    ///
+    /// 新映射中超出 `old_size` 的部分会被清零，并应用与当前映射相同的内存保护。
+    /// 失败时文件会恢复为 `old_size`；在 unix 上当前映射始终不受影响：缩小文件时先映射再截断。
+    fn map_resized(&self, old_size: u64, new_size: u64) -> Result<Mapping> {
+        let file = &self.shared.file;
+        let map = || -> Result<Mapping> {
+            let mut mmap: Mapping = unsafe { MmapOptions::new().len(new_size as usize).map_mut(file)? }.into();
+            if new_size > old_size {
+                mmap[old_size as usize..].fill(0);
+            }
+            self.reapply_protection(&mut mmap)?;
+            Ok(mmap)
+        };
+
+        if new_size > old_size || cfg!(windows) {
+            file.set_len(new_size)?;
+            map().inspect_err(|_| {
+                let _ = file.set_len(old_size);
+            })
+        } else {
+            let mmap = map()?;
+            file.set_len(new_size)?;
+            Ok(mmap)
+        }
+    }
+
+    /// Map the file again at `size` after its mapping was released for a failed operation
+    ///
+    /// 在映射因操作失败而被释放后，按 `size` 重新映射文件
+    ///
+    /// If mapping fails again, the size shrinks to that of the placeholder left in `mmap`, so
+    /// bounds checks keep every access inside it.
+    ///
+    /// 如果再次映射失败，大小会缩小为 `mmap` 中占位映射的大小，使边界检查将所有访问限制在其中。
+    #[cfg(windows)]
+    fn restore_mapping(&self, mmap: &mut Mapping, size: u64) {
+        let restored = unsafe { MmapOptions::new().len(size as usize).map_mut(&self.shared.file) }
+            .map(Mapping::from)
+            .map_err(Error::from)
+            .and_then(|mut restored| {
+                self.reapply_protection(&mut restored)?;
+                Ok(restored)
+            });
+
+        match restored {
+            Ok(restored) => *mmap = restored,
+            Err(_) => {
+                let placeholder = mmap.len() as u64;
+                self.shared.size.store(placeholder, Ordering::Release);
+                self.shared.high_watermark.fetch_min(placeholder, Ordering::Relaxed);
+                self.resize_dirty(placeholder);
+            }
+        }
+    }
+
+    /// Remap if the backing file was grown externally
+    ///
+    /// 如果底层文件被外部扩展，则重新映射
+    ///
+    /// Stats the backing file; if its length grew beyond the current [`size`](Self::size),
+    /// remaps to the new length. Useful in producer/consumer setups where another handle or
+    /// process grows the file with [`resize`](Self::resize). A file that shrank is left
+    /// mapped at the current size.
+    ///
+    /// 查询底层文件的状态；如果其长度超过当前 [`size`](Self::size)，则按新长度重新映射。
+    /// 适用于生产者/消费者场景，由另一个句柄或进程通过 [`resize`](Self::resize) 扩展文件。
+    /// 文件缩小时保持当前映射大小不变。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`resize`](Self::resize): no concurrent access during the call,
+    /// and no previously obtained pointers are used afterwards.
+    ///
+    /// # Safety
+    ///
+    /// 与 [`resize`](Self::resize) 的要求相同：调用期间没有并发访问，
+    /// 之后不再使用之前获取的指针。
+    ///
+    /// # Returns
+    /// Returns `true` if the mapping was grown, `false` if the size is unchanged
+    ///
+    /// # 返回值
+    /// 如果映射已扩展返回 `true`，大小未变返回 `false`
+    ///
+    /// # Errors
+    /// - Returns `CopyOnWriteMapping` error for mappings from [`open_copy_on_write`](Self::open_copy_on_write),
+    ///   which would otherwise be remapped as a shared mapping of the base file
+    /// - Returns corresponding I/O errors if querying the file or remapping fails; the current
+    ///   mapping is then kept
+    ///
+    /// # Errors
+    /// - 对于 [`open_copy_on_write`](Self::open_copy_on_write) 打开的映射，返回 `CopyOnWriteMapping` 错误，
+    ///   否则它会被重新映射为底层文件的共享映射
+    /// - 如果查询文件或重新映射失败，返回相应的 I/O 错误；此时保留当前映射
+    pub unsafe fn refresh(&self) -> Result<bool> {
+        if self.shared.copy_on_write.load(Ordering::Relaxed) {
+            return Err(Error::CopyOnWriteMapping);
+        }
+
+        let len = self.shared.file.metadata()?.len();
+        if len <= self.size().get() {
+            return Ok(false);
+        }
+        check_platform_size(len)?;
+
+        // Protect the new mapping before swapping it in, so a failure keeps the old one
+        // 在替换之前为新映射设置保护，使失败时保留旧映射
+        let mut remapped: Mapping = unsafe { MmapMut::map_mut(&self.shared.file)? }.into();
+        self.reapply_protection(&mut remapped)?;
+
+        // Use the mapped length, in case the file grew again after the check
+        // 使用映射的实际长度，以防检查之后文件再次增长
+        let size = remapped.len() as u64;
+        let mmap = unsafe { &mut *self.shared.mmap.get() };
+        *mmap = remapped;
+        self.shared.size.store(size, Ordering::Release);
+        self.resize_dirty(size);
+        Ok(true)
+    }
+
+    /// Flush, unmap and remap the file at the same size
+    ///
+    /// 刷新、解除映射并以相同大小重新映射文件
+    ///
+    /// Writes all dirty pages back synchronously, then maps the file again and tears down the
+    /// old mapping, dropping every resident page of this process. This is heavier than
+    /// `MADV_DONTNEED` but fully resets the mapping, which suits long-lived handles that
+    /// occasionally need to give memory back. The current protection is reapplied.
+    ///
+    /// 同步写回所有脏页，然后重新映射文件并拆除旧映射，丢弃本进程所有驻留的页面。
+    /// 这比 `MADV_DONTNEED` 更重，但会完全重置映射，适用于偶尔需要归还内存的长期句柄。
+    /// 当前的内存保护会被重新应用。
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`resize`](Self::resize): no concurrent access during the call
+    /// (including other clones), and no previously obtained pointers or slices are used
+    /// afterwards; the mapping may move.
+    ///
+    /// # Safety
+    ///
+    /// 与 [`resize`](Self::resize) 的要求相同：调用期间没有并发访问（包括其他克隆），
+    /// 之后不再使用之前获取的指针或切片；映射可能会移动。
+    ///
+    /// # Errors
+    /// - Returns `CopyOnWriteMapping` error for mappings from [`open_copy_on_write`](Self::open_copy_on_write),
+    ///   whose private changes would be dropped
+    /// - Returns corresponding I/O errors if flushing or remapping fails; the current mapping
+    ///   is then kept
+    ///
+    /// # Errors
+    /// - 对于 [`open_copy_on_write`](Self::open_copy_on_write) 打开的映射，返回 `CopyOnWriteMapping` 错误，
+    ///   因为其私有修改会被丢弃
+    /// - 如果刷新或重新映射失败，返回相应的 I/O 错误；此时保留当前映射
+    pub unsafe fn remap(&self) -> Result<()> {
+        if self.shared.copy_on_write.load(Ordering::Relaxed) {
+            return Err(Error::CopyOnWriteMapping);
+        }
 
-        debug_assert!(
-            offset_usize.saturating_add(len) <= self.size.get() as usize,
-            "Flush range exceeds file size: offset={}, len={}, file_size={}",
-            offset, len, self.size.get()
-        );
+        let size = self.size().get();
+        let mmap = unsafe { &mut *self.shared.mmap.get() };
+        mmap.flush()?;
 
-        unsafe {
-            let mmap = &*self.mmap.get();
-            Ok(mmap.flush_async_range(offset_usize, len)?)
-        }
+        // Map again before dropping the old mapping, so a failure leaves it in place;
+        // unmapping it on the swap still drops all of its pages
+        // 先重新映射再丢弃旧映射，使失败时旧映射保持不变；替换时解除旧映射仍会丢弃其所有页面
+        let mut remapped: Mapping = unsafe { MmapOptions::new().len(size as usize).map_mut(&self.shared.file)? }.into();
+        self.reapply_protection(&mut remapped)?;
+        *mmap = remapped;
+        Ok(())
     }
 
-    /// Get file size
-    /// 
-    /// 获取文件大小
-    #[inline]
-    pub fn size(&self) -> NonZeroU64 {
-        self.size
+    /// Re-apply the stored protection to a freshly created mapping
+    ///
+    /// 将保存的内存保护重新应用到新建的映射上
+    fn reapply_protection(&self, mmap: &mut Mapping) -> Result<()> {
+        let prot = self.protection();
+        if prot != Protection::ReadWrite {
+            // Safety: mmap is a live mapping of mmap.len() bytes
+            // Safety: mmap 是长度为 mmap.len() 字节的有效映射
+            unsafe { sys::protect(mmap.as_mut_ptr(), mmap.len(), prot)? };
+        }
+        Ok(())
     }
 
     /// Fill the entire file with a specified byte
@@ -574,11 +2752,97 @@ impl MmapFileInner {
     ///
     /// # 参数
     /// - `byte`: 填充字节
+    ///
+    /// # Errors
+    /// Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite)
+    ///
+    /// # Errors
+    /// 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误
     pub unsafe fn fill(&self, byte: u8) -> Result<()> {
+        if !self.protection().is_writable() {
+            return Err(Error::ReadOnlyMapping);
+        }
+
         unsafe {
-            let mmap = &mut *self.mmap.get();
+            let mmap = &mut *self.shared.mmap.get();
             mmap.fill(byte);
         }
+        self.mark_written(self.size().get());
+        Ok(())
+    }
+
+    /// Fill a region with a repeating pattern
+    ///
+    /// 用重复的模式填充指定区域
+    ///
+    /// Tiles `pattern` across `[offset, offset + len)`; the last repetition is truncated when
+    /// `len` is not a multiple of the pattern length. The pattern is copied once and then
+    /// doubled with in-place copies, so long regions take few copy calls.
+    ///
+    /// 将 `pattern` 平铺到 `[offset, offset + len)`；当 `len` 不是模式长度的整数倍时，
+    /// 最后一次重复会被截断。模式只拷贝一次，之后通过原地拷贝倍增，因此长区域只需少量拷贝调用。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no other threads are reading or writing the region during the fill.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保在填充期间没有其他线程读写该区域。
+    ///
+    /// # Parameters
+    /// - `offset`: Start of the region
+    /// - `len`: Length of the region
+    /// - `pattern`: Bytes to repeat
+    ///
+    /// # 参数
+    /// - `offset`: 区域起始位置
+    /// - `len`: 区域长度
+    /// - `pattern`: 要重复的字节
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the region exceeds the file size
+    /// - Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite)
+    ///
+    /// # Errors
+    /// - 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    /// - 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误
+    ///
+    /// # Panics
+    /// Panics if `pattern` is empty and `len` is not 0
+    ///
+    /// # Panics
+    /// 如果 `pattern` 为空且 `len` 不为 0，则 panic
+    pub unsafe fn fill_pattern(&self, offset: u64, len: u64, pattern: &[u8]) -> Result<()> {
+        self.check_bounds(offset, len)?;
+        if !self.protection().is_writable() {
+            return Err(Error::ReadOnlyMapping);
+        }
+        if len == 0 {
+            return Ok(());
+        }
+        assert!(!pattern.is_empty(), "pattern must not be empty");
+        trace_event!(offset, len, pattern_len = pattern.len(), "fill_pattern");
+
+        let start = offset as usize;
+        let len = len as usize;
+        unsafe {
+            let mmap = &mut *self.shared.mmap.get();
+            let region = &mut mmap[start..start + len];
+
+            let first = pattern.len().min(len);
+            region[..first].copy_from_slice(&pattern[..first]);
+
+            // The filled prefix is always whole repetitions, so doubling it keeps the tiling
+            // 已填充的前缀总是由完整的重复组成，因此倍增它能保持平铺
+            let mut filled = first;
+            while filled < len {
+                let n = filled.min(len - filled);
+                region.copy_within(..n, filled);
+                filled += n;
+            }
+        }
+        self.mark_written(offset + len as u64);
         Ok(())
     }
 
@@ -599,10 +2863,141 @@ impl MmapFileInner {
     /// 
     /// 调用者需要确保在清零期间没有其他线程正在读写文件的任何部分。
     /// 此操作会修改整个文件内容。
+    ///
+    /// # Errors
+    /// Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite)
+    ///
+    /// # Errors
+    /// 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误
     pub unsafe fn zero(&self) -> Result<()> {
         unsafe { self.fill(0) }
     }
 
+    /// Count occurrences of each byte value across the whole file
+    ///
+    /// 统计整个文件中每个字节值出现的次数
+    ///
+    /// Meant for debugging, e.g. spotting regions that were never written. The mapping is
+    /// scanned in 64K chunks, each counted into a local histogram that is then merged.
+    ///
+    /// 用于调试，例如找出从未写入的区域。映射以 64K 为块进行扫描，
+    /// 每块先计入局部直方图，再合并到结果中。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no other threads are writing to the file during the scan.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保扫描期间没有其他线程写入文件。
+    ///
+    /// # Returns
+    /// `histogram[b]` is the number of bytes equal to `b`
+    ///
+    /// # 返回值
+    /// `histogram[b]` 为值等于 `b` 的字节数
+    pub unsafe fn byte_histogram(&self) -> [u64; 256] {
+        const CHUNK: usize = 64 * 1024;
+
+        let mut histogram = [0u64; 256];
+        for chunk in unsafe { self.as_slice() }.chunks(CHUNK) {
+            // u32 counters cannot overflow within a chunk
+            // 在一个块内 u32 计数器不会溢出
+            let mut local = [0u32; 256];
+            for &byte in chunk {
+                local[byte as usize] += 1;
+            }
+            for (total, count) in histogram.iter_mut().zip(local) {
+                *total += count as u64;
+            }
+        }
+        histogram
+    }
+
+    /// Fraction of bytes in the file that are zero
+    ///
+    /// 文件中值为零的字节所占的比例
+    ///
+    /// # Safety
+    ///
+    /// Same requirements as [`byte_histogram`](Self::byte_histogram).
+    ///
+    /// # Safety
+    ///
+    /// 与 [`byte_histogram`](Self::byte_histogram) 的要求相同。
+    ///
+    /// # Returns
+    /// A value in `[0.0, 1.0]`
+    ///
+    /// # 返回值
+    /// 位于 `[0.0, 1.0]` 的值
+    pub unsafe fn zero_ratio(&self) -> f64 {
+        let zeros = unsafe { self.as_slice() }.iter().filter(|&&byte| byte == 0).count();
+        zeros as f64 / self.len() as f64
+    }
+
+    /// Format a region as an `xxd`-style hex dump
+    ///
+    /// 将区域格式化为 `xxd` 风格的十六进制转储
+    ///
+    /// Each line shows the absolute file offset, 16 bytes in groups of two, and their ASCII
+    /// rendering with non-printable bytes shown as `.`, e.g.
+    /// `00001000: 4865 6c6c 6f00 0000 0000 0000 0000 0000  Hello...........`.
+    /// Every line ends with a newline.
+    ///
+    /// 每行显示绝对文件偏移、按两字节分组的 16 个字节，以及它们的 ASCII 表示（不可打印字节显示为 `.`），
+    /// 例如 `00001000: 4865 6c6c 6f00 0000 0000 0000 0000 0000  Hello...........`。每行都以换行符结尾。
+    ///
+    /// # Safety
+    ///
+    /// The caller must ensure no other threads are writing to the region during the dump.
+    ///
+    /// # Safety
+    ///
+    /// 调用者需要确保转储期间没有其他线程写入该区域。
+    ///
+    /// # Parameters
+    /// - `offset`: Start of the region
+    /// - `len`: Length of the region
+    ///
+    /// # 参数
+    /// - `offset`: 区域起始位置
+    /// - `len`: 区域长度
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if the region exceeds the file size
+    ///
+    /// # Errors
+    /// 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    pub unsafe fn hexdump(&self, offset: u64, len: usize) -> Result<String> {
+        use std::fmt::Write;
+
+        self.check_bounds(offset, len as u64)?;
+        let start = offset as usize;
+        let data = &unsafe { self.as_slice() }[start..start + len];
+
+        let mut out = String::with_capacity(len.div_ceil(16) * 68);
+        for (line, chunk) in data.chunks(16).enumerate() {
+            let _ = write!(out, "{:08x}:", offset + line as u64 * 16);
+            for i in 0..16 {
+                if i % 2 == 0 {
+                    out.push(' ');
+                }
+                match chunk.get(i) {
+                    Some(byte) => { let _ = write!(out, "{:02x}", byte); }
+                    None => out.push_str("  "),
+                }
+            }
+            out.push_str("  ");
+            out.extend(chunk.iter().map(|&byte| match byte {
+                0x20..=0x7e => byte as char,
+                _ => '.',
+            }));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
     /// Read a specific region into a new Vec
     ///
     /// 读取指定区域到新的 Vec
@@ -626,13 +3021,80 @@ impl MmapFileInner {
     /// # 参数
     /// - `offset`: 读取起始位置
     /// - `len`: 读取长度
+    ///
+    /// # Errors
+    /// Returns `ReadLengthTooLarge` error if `len` exceeds the file size. This is checked
+    /// before allocating, so a bogus `len` cannot trigger a huge allocation. A `len` reaching
+    /// past the end of the file is otherwise clamped, and the returned Vec is shorter.
+    ///
+    /// # Errors
+    /// 如果 `len` 超出文件大小，返回 `ReadLengthTooLarge` 错误。该检查在分配之前进行，
+    /// 因此错误的 `len` 不会触发巨大的分配。否则超出文件末尾的 `len` 会被截断，返回的 Vec 会更短。
     pub unsafe fn read_slice(&self, offset: u64, len: usize) -> Result<Vec<u8>> {
-        let mut buf = vec![0u8; len];
-        let bytes_read = unsafe { self.read_at(offset, &mut buf)? };
-        buf.truncate(bytes_read);
+        let size = self.size().get();
+        if len as u64 > size {
+            return Err(Error::ReadLengthTooLarge { len, size });
+        }
+
+        let len = len.min(size.saturating_sub(offset) as usize);
+        let mut buf = Vec::with_capacity(len);
+        let bytes_read = unsafe { self.read_into_uninit(offset, buf.spare_capacity_mut())?.len() };
+        // Safety: `read_into_uninit` initialized the first `bytes_read` bytes
+        // Safety: `read_into_uninit` 已初始化前 `bytes_read` 字节
+        unsafe { buf.set_len(bytes_read) };
         Ok(buf)
     }
 
+    /// Read a specific region as [`Bytes`](bytes::Bytes) without copying
+    ///
+    /// 以零拷贝方式将指定区域读取为 [`Bytes`](bytes::Bytes)
+    ///
+    /// Available with the `bytes` feature. The returned `Bytes` points directly into the
+    /// mapping and owns a clone of this handle, so the mapping stays alive until the last
+    /// `Bytes` (and the last handle) is dropped, even if the original handle goes away first.
+    /// The bytes are not a snapshot: later writes to the region show through.
+    ///
+    /// 需要启用 `bytes` 特性。返回的 `Bytes` 直接指向映射，并持有此句柄的一个克隆，
+    /// 因此即使原句柄先被丢弃，映射也会一直存活到最后一个 `Bytes`（以及最后一个句柄）被丢弃。
+    /// 这些字节不是快照：之后对该区域的写入会反映出来。
+    ///
+    /// # Safety
+    ///
+    /// While the returned `Bytes` (or any clone of it) is alive, the caller must ensure:
+    /// - No writes occur to the region, since reads through the `Bytes` would race with them
+    /// - The mapping is not [`resize`](Self::resize)d or [`refresh`](Self::refresh)ed, which
+    ///   would unmap the memory it points to
+    ///
+    /// # Safety
+    ///
+    /// 在返回的 `Bytes`（或其任何克隆）存活期间，调用者需要确保：
+    /// - 不会写入该区域，否则通过 `Bytes` 的读取会与写入发生数据竞争
+    /// - 不会对映射调用 [`resize`](Self::resize) 或 [`refresh`](Self::refresh)，否则其指向的内存会被解除映射
+    ///
+    /// # Parameters
+    /// - `offset`: Read start position
+    /// - `len`: Read length
+    ///
+    /// # 参数
+    /// - `offset`: 读取起始位置
+    /// - `len`: 读取长度
+    ///
+    /// # Errors
+    /// Returns `OutOfBounds` error if the region exceeds the file size
+    ///
+    /// # Errors
+    /// 如果区域超出文件大小，返回 `OutOfBounds` 错误
+    #[cfg(feature = "bytes")]
+    pub unsafe fn read_bytes(&self, offset: u64, len: usize) -> Result<bytes::Bytes> {
+        self.check_bounds(offset, len as u64)?;
+
+        Ok(bytes::Bytes::from_owner(MappedRegion {
+            file: self.clone(),
+            offset: offset as usize,
+            len,
+        }))
+    }
+
     /// Get a raw pointer to the underlying mmap
     /// 
     /// 获取底层 mmap 的原始指针
@@ -651,11 +3113,35 @@ impl MmapFileInner {
     #[inline]
     pub fn as_ptr(&self) -> *const u8 {
         unsafe {
-            let mmap = &*self.mmap.get();
+            let mmap = &*self.shared.mmap.get();
             mmap.as_ptr()
         }
     }
 
+    /// View the whole mapping as a byte slice
+    /// 
+    /// 将整个映射视为字节切片
+    /// 
+    /// For a view through a handle that cannot write, open the file with
+    /// [`ReadOnlyMmapFile`](super::ReadOnlyMmapFile) and use [`bytes`](super::ReadOnlyMmapFile::bytes).
+    /// 
+    /// 如需通过无法写入的句柄获取视图，请用 [`ReadOnlyMmapFile`](super::ReadOnlyMmapFile) 打开文件并使用
+    /// [`bytes`](super::ReadOnlyMmapFile::bytes)。
+    /// 
+    /// # Safety
+    /// 
+    /// The caller must ensure no write (through any clone, thread or process) touches the
+    /// mapping while the slice is alive, and the mapping is not resized or remapped.
+    /// 
+    /// # Safety
+    /// 
+    /// 调用者需要确保切片存活期间，没有任何写入（通过任何克隆、线程或进程）触及该映射，
+    /// 并且映射不会被调整大小或重新映射。
+    #[inline]
+    pub unsafe fn as_slice(&self) -> &[u8] {
+        unsafe { &*self.shared.mmap.get() }
+    }
+
     /// Get a mutable raw pointer to the underlying mmap
     /// 
     /// 获取底层 mmap 的可变原始指针
@@ -676,10 +3162,171 @@ impl MmapFileInner {
     #[inline]
     pub unsafe fn as_mut_ptr(&self) -> *mut u8 {
         unsafe {
-            let mmap = &mut *self.mmap.get();
+            let mmap = &mut *self.shared.mmap.get();
             mmap.as_mut_ptr()
         }
     }
+
+    /// View the whole mapping as a slice of atomic bytes
+    ///
+    /// 将整个映射视为原子字节切片
+    ///
+    /// Atomic operations through a shared reference are sound from any number of threads,
+    /// which makes this suited to lock-free byte flags such as a presence bitmap. The slice
+    /// has `size` elements. Mixing these atomic accesses with the non-atomic `unsafe` methods
+    /// (e.g. [`write_at`](Self::write_at)) on the same bytes is a data race.
+    ///
+    /// 通过共享引用进行的原子操作在任意数量的线程中都是健全的，因此适用于无锁字节标志，
+    /// 例如存在位图。切片包含 `size` 个元素。在同一字节上将这些原子访问与非原子的 `unsafe` 方法
+    /// （例如 [`write_at`](Self::write_at)）混用会导致数据竞争。
+    ///
+    /// The slice stays valid until the mapping is changed by one of the `unsafe` methods
+    /// [`resize`](Self::resize), [`refresh`](Self::refresh), [`remap`](Self::remap) or
+    /// [`set_protection`](Self::set_protection), whose safety requirements cover it.
+    ///
+    /// 该切片在映射被 `unsafe` 方法 [`resize`](Self::resize)、[`refresh`](Self::refresh)、
+    /// [`remap`](Self::remap) 或 [`set_protection`](Self::set_protection) 修改之前一直有效，
+    /// 这些方法的安全要求涵盖了该切片。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Result};
+    /// # use tempfile::tempdir;
+    /// # use std::sync::atomic::Ordering;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("flags.bin");
+    /// # use std::num::NonZeroU64;
+    /// let file = MmapFileInner::create(&path, NonZeroU64::new(64).unwrap())?;
+    /// let flags = file.as_atomic_bytes()?;
+    /// flags[3].store(1, Ordering::Relaxed);
+    /// assert_eq!(flags[3].load(Ordering::Relaxed), 1);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    /// Returns `ReadOnlyMapping` error if the mapping is not [`ReadWrite`](Protection::ReadWrite),
+    /// since an atomic store would fault
+    ///
+    /// # Errors
+    /// 如果映射不是 [`ReadWrite`](Protection::ReadWrite)，返回 `ReadOnlyMapping` 错误，
+    /// 因为原子存储会触发内存错误
+    #[inline]
+    pub fn as_atomic_bytes(&self) -> Result<&[AtomicU8]> {
+        if !self.protection().is_writable() {
+            return Err(Error::ReadOnlyMapping);
+        }
+        let len = self.size().get() as usize;
+        // Safety: AtomicU8 has the same size and alignment as u8, the mapping is valid for
+        // `len` bytes while `self` is borrowed, and only atomic accesses go through the slice
+        // Safety: AtomicU8 与 u8 的大小和对齐相同，在借用 `self` 期间映射的 `len` 字节有效，
+        // 并且通过该切片只进行原子访问
+        Ok(unsafe { std::slice::from_raw_parts(self.as_ptr().cast::<AtomicU8>(), len) })
+    }
+
+    /// Split the mapping into non-overlapping mutable slices
+    /// 
+    /// 将映射拆分为互不重叠的可变切片
+    /// 
+    /// Works like [`slice::chunks_mut`] over the whole mapping: every slice is `chunk_size`
+    /// bytes except possibly the last. Intended for handing disjoint regions to SIMD kernels
+    /// on different threads. Writes through these slices are not tracked by
+    /// [`high_watermark`](Self::high_watermark).
+    /// 
+    /// 与在整个映射上调用 [`slice::chunks_mut`] 相同：除最后一个外，每个切片都是 `chunk_size` 字节。
+    /// 适用于将互不相交的区域交给不同线程上的 SIMD 内核。
+    /// 通过这些切片的写入不会被 [`high_watermark`](Self::high_watermark) 跟踪。
+    /// 
+    /// # Safety
+    /// 
+    /// The caller must ensure:
+    /// - Each slice is used by at most one thread at a time
+    /// - No other access to the mapping (including other clones, `write_at`, `read_at`, or
+    ///   another `split_mut`) happens while the slices are alive
+    /// - The mapping is not resized or re-protected while the slices are alive
+    /// 
+    /// # Safety
+    /// 
+    /// 调用者需要确保：
+    /// - 每个切片同一时间最多由一个线程使用
+    /// - 切片存活期间不会以其他方式访问该映射（包括其他克隆、`write_at`、`read_at` 或
+    ///   另一次 `split_mut`）
+    /// - 切片存活期间不会调整映射大小或修改内存保护
+    /// 
+    /// # Parameters
+    /// - `chunk_size`: Size of each slice in bytes
+    /// 
+    /// # 参数
+    /// - `chunk_size`: 每个切片的大小（字节）
+    /// 
+    /// # Panics
+    /// Panics if `chunk_size` is 0
+    /// 
+    /// # Panics
+    /// 如果 `chunk_size` 为 0 则 panic
+    #[allow(clippy::mut_from_ref)]
+    pub unsafe fn split_mut(&self, chunk_size: usize) -> Vec<&mut [u8]> {
+        assert!(chunk_size != 0, "chunk_size must be non-zero");
+
+        let len = self.size().get() as usize;
+        // Safety: the caller guarantees exclusive access to the mapping while the slices live
+        // Safety: 调用者保证切片存活期间独占访问该映射
+        let bytes = unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr(), len) };
+        bytes.chunks_mut(chunk_size).collect()
+    }
+
+    /// Get an identity shared by all clones of this mapping
+    /// 
+    /// 获取此映射所有克隆共享的标识
+    /// 
+    /// The value is the address of the shared state, so it is stable for the lifetime of the
+    /// mapping and equal across clones. Two separate mappings of the same path (e.g. opened
+    /// twice) have different ids. The [`PartialEq`], [`Eq`] and [`Hash`] impls use this
+    /// identity, which makes a handle usable as a map key for the underlying mapping.
+    /// 
+    /// 该值是共享状态的地址，因此在映射的生命周期内保持稳定，并且在各克隆之间相等。
+    /// 同一路径的两个独立映射（例如打开两次）具有不同的标识。
+    /// [`PartialEq`]、[`Eq`] 和 [`Hash`] 的实现使用此标识，因此句柄可以作为底层映射的 map 键。
+    #[inline]
+    pub fn id(&self) -> usize {
+        Arc::as_ptr(&self.shared) as usize
+    }
+
+    /// Get the raw file descriptor of the backing file
+    /// 
+    /// 获取底层文件的原始文件描述符
+    /// 
+    /// Useful for handing the file to fd-based facilities such as io_uring or an async
+    /// runtime's registered files, e.g. to schedule `fsync` alongside mmap writes.
+    /// The descriptor is owned by this mapping and stays valid as long as any clone is alive;
+    /// do not close it.
+    /// 
+    /// 适用于将文件交给基于 fd 的设施，例如 io_uring 或异步运行时的注册文件，
+    /// 以便与 mmap 写入配合调度 `fsync`。
+    /// 该描述符归此映射所有，只要任一克隆存活就保持有效；不要关闭它。
+    #[cfg(unix)]
+    #[inline]
+    pub fn as_raw_fd(&self) -> std::os::unix::io::RawFd {
+        use std::os::unix::io::AsRawFd;
+        self.shared.file.as_raw_fd()
+    }
+
+    /// Get the raw handle of the backing file
+    /// 
+    /// 获取底层文件的原始句柄
+    /// 
+    /// The handle is owned by this mapping and stays valid as long as any clone is alive;
+    /// do not close it.
+    /// 
+    /// 该句柄归此映射所有，只要任一克隆存活就保持有效；不要关闭它。
+    #[cfg(windows)]
+    #[inline]
+    pub fn as_raw_handle(&self) -> std::os::windows::io::RawHandle {
+        use std::os::windows::io::AsRawHandle;
+        self.shared.file.as_raw_handle()
+    }
 }
 
 /// Implement Debug for MmapFileInner
@@ -688,12 +3335,37 @@ impl MmapFileInner {
 impl std::fmt::Debug for MmapFileInner {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("MmapFileInner")
-            .field("size", &self.size)
+            .field("path", &self.path())
+            .field("size", &self.size())
+            .field("protection", &self.protection())
+            .field("high_watermark", &self.high_watermark())
             .field("mmap", &"MmapMut")
             .finish()
     }
 }
 
+/// Compare mappings by identity: clones are equal, separate mappings are not
+/// 
+/// 按标识比较映射：克隆相等，独立的映射不相等
+impl PartialEq for MmapFileInner {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+impl Eq for MmapFileInner {}
+
+/// Hash by identity, consistent with [`PartialEq`]
+/// 
+/// 按标识计算哈希，与 [`PartialEq`] 保持一致
+impl std::hash::Hash for MmapFileInner {
+    #[inline]
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id().hash(state);
+    }
+}
+
 // Implement Send and Sync
 // Safety: Safe as long as users ensure different threads write to non-overlapping regions
 // 
@@ -702,3 +3374,86 @@ impl std::fmt::Debug for MmapFileInner {
 unsafe impl Send for MmapFileInner {}
 unsafe impl Sync for MmapFileInner {}
 
+/// Ensure a file of `size` bytes can be mapped on this platform
+///
+/// 确保 `size` 字节的文件可以在当前平台上映射
+///
+/// Once this holds, every in-bounds offset also fits in `usize`.
+///
+/// 满足此条件后，所有边界内的偏移量也都能放入 `usize`。
+#[inline]
+pub(crate) fn check_platform_size(size: u64) -> Result<()> {
+    check_size_limit(size, usize::MAX)
+}
+
+/// Ensure `size` does not exceed `max` addressable bytes
+///
+/// 确保 `size` 不超过 `max` 个可寻址字节
+#[inline]
+pub(crate) fn check_size_limit(size: u64, max: usize) -> Result<()> {
+    if size > max as u64 {
+        return Err(Error::FileTooLargeForPlatform { size, max });
+    }
+    Ok(())
+}
+
+/// Allocate a clean dirty bitset with one bit per 4K page of `size` bytes
+///
+/// 为 `size` 字节分配干净的脏页位图，每 4K 页面一位
+fn dirty_bitset(size: u64) -> Box<[AtomicU64]> {
+    let words = size.div_ceil(super::allocator::ALIGNMENT).div_ceil(64);
+    (0..words).map(|_| AtomicU64::new(0)).collect()
+}
+
+/// Set the bits of every 4K page `[offset, offset + len)` touches
+///
+/// 设置 `[offset, offset + len)` 所涉及的每个 4K 页面的位
+///
+/// Uses release ordering, so a thread that takes the bit with acquire ordering also sees the
+/// bytes written before it was set. Pages beyond the bitset are ignored.
+///
+/// 使用 release 顺序，因此以 acquire 顺序取走该位的线程也能看到置位之前写入的字节。
+/// 超出位图的页面会被忽略。
+pub(crate) fn set_dirty_bits(words: &[AtomicU64], offset: u64, len: u64) {
+    if len == 0 {
+        return;
+    }
+
+    let first = offset / super::allocator::ALIGNMENT;
+    let last = (offset + len - 1) / super::allocator::ALIGNMENT;
+    for word in first / 64..=last / 64 {
+        let lo = if word == first / 64 { first % 64 } else { 0 };
+        let hi = if word == last / 64 { last % 64 } else { 63 };
+        let mask = (u64::MAX >> (63 - hi)) & (u64::MAX << lo);
+        match words.get(word as usize) {
+            Some(bits) => bits.fetch_or(mask, Ordering::Release),
+            None => break,
+        };
+    }
+}
+
+
+/// Owner of a zero-copy [`Bytes`](bytes::Bytes) view into the mapping
+///
+/// 指向映射的零拷贝 [`Bytes`](bytes::Bytes) 视图的所有者
+///
+/// Holding a handle clone keeps the mapping alive for as long as the `Bytes` exists.
+///
+/// 持有句柄的克隆，使映射在 `Bytes` 存在期间保持存活。
+#[cfg(feature = "bytes")]
+struct MappedRegion {
+    file: MmapFileInner,
+    offset: usize,
+    len: usize,
+}
+
+#[cfg(feature = "bytes")]
+impl AsRef<[u8]> for MappedRegion {
+    fn as_ref(&self) -> &[u8] {
+        // Safety: bounds were checked in read_bytes; the caller of read_bytes guarantees
+        // the mapping is not replaced while the Bytes is alive
+        // Safety: 边界已在 read_bytes 中检查；read_bytes 的调用者保证在 Bytes 存活期间映射不会被替换
+        let mmap = unsafe { &*self.file.shared.mmap.get() };
+        &mmap[self.offset..self.offset + self.len]
+    }
+}
@@ -0,0 +1,74 @@
+//! Helpers for building `NonZeroU64` sizes
+//!
+//! 构造 `NonZeroU64` 大小的辅助函数
+
+use std::num::NonZeroU64;
+
+/// Build a `NonZeroU64`, panicking on zero
+///
+/// 构造 `NonZeroU64`，为零时 panic
+///
+/// Shorthand for `NonZeroU64::new(x).unwrap()` with a clearer panic message, for sizes that
+/// are known to be non-zero (constants, literals).
+///
+/// `NonZeroU64::new(x).unwrap()` 的简写，panic 信息更清晰，适用于已知非零的大小（常量、字面量）。
+///
+/// # Panics
+/// Panics if `x` is 0
+///
+/// # Panics
+/// 如果 `x` 为 0，则 panic
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{nz, allocator::ALIGNMENT};
+/// assert_eq!(nz(ALIGNMENT).get(), 4096);
+/// ```
+#[inline]
+#[track_caller]
+pub const fn nz(x: u64) -> NonZeroU64 {
+    match NonZeroU64::new(x) {
+        Some(x) => x,
+        None => panic!("size must be non-zero / 大小必须非零"),
+    }
+}
+
+/// Build a `NonZeroU64`, returning `None` on zero
+///
+/// 构造 `NonZeroU64`，为零时返回 `None`
+///
+/// Same as `NonZeroU64::new`, provided next to [`nz`] for sizes computed at runtime.
+///
+/// 与 `NonZeroU64::new` 相同，与 [`nz`] 一起提供，用于运行时计算的大小。
+#[inline]
+pub const fn try_nz(x: u64) -> Option<NonZeroU64> {
+    NonZeroU64::new(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nz() {
+        assert_eq!(nz(1).get(), 1);
+        assert_eq!(nz(u64::MAX).get(), u64::MAX);
+
+        // Usable in constants
+        const SIZE: NonZeroU64 = nz(4096);
+        assert_eq!(SIZE.get(), 4096);
+    }
+
+    #[test]
+    #[should_panic(expected = "size must be non-zero")]
+    fn test_nz_zero_panics() {
+        nz(0);
+    }
+
+    #[test]
+    fn test_try_nz() {
+        assert_eq!(try_nz(0), None);
+        assert_eq!(try_nz(7).map(NonZeroU64::get), Some(7));
+    }
+}
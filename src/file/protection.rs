@@ -0,0 +1,78 @@
+//! Memory protection flags for mappings
+//!
+//! 映射的内存保护标志
+
+/// Memory protection of a mapping
+///
+/// 映射的内存保护
+///
+/// Used by [`MmapFileInner::create_with_prot`](super::MmapFileInner::create_with_prot) and
+/// [`MmapFileInner::set_protection`](super::MmapFileInner::set_protection) to control how
+/// the mapped pages may be accessed.
+///
+/// 用于 [`MmapFileInner::create_with_prot`](super::MmapFileInner::create_with_prot) 和
+/// [`MmapFileInner::set_protection`](super::MmapFileInner::set_protection)，控制映射页面的访问方式。
+///
+/// # W^X
+///
+/// No variant is both writable and executable. To produce executable code, map the region
+/// as [`ReadWrite`](Protection::ReadWrite), write the code, then flip it to
+/// [`ReadExecute`](Protection::ReadExecute). Executable file mappings are platform-sensitive:
+/// they can be rejected by `noexec` mounts, SELinux policies or hardened runtimes.
+///
+/// # W^X
+///
+/// 没有任何变体同时可写且可执行。要生成可执行代码，先以 [`ReadWrite`](Protection::ReadWrite)
+/// 映射区域，写入代码后再切换为 [`ReadExecute`](Protection::ReadExecute)。
+/// 可执行的文件映射与平台相关：可能被 `noexec` 挂载、SELinux 策略或强化运行时拒绝。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Protection {
+    /// Pages can only be read
+    ///
+    /// 页面只能读取
+    ReadOnly,
+    /// Pages can be read and written (default)
+    ///
+    /// 页面可以读取和写入（默认）
+    #[default]
+    ReadWrite,
+    /// Pages can be read and executed, but not written
+    ///
+    /// 页面可以读取和执行，但不能写入
+    ReadExecute,
+}
+
+impl Protection {
+    /// Returns true if writes are permitted
+    ///
+    /// 如果允许写入则返回 true
+    #[inline]
+    pub fn is_writable(&self) -> bool {
+        matches!(self, Protection::ReadWrite)
+    }
+
+    /// Encode as a byte for atomic storage
+    ///
+    /// 编码为字节以便原子存储
+    #[inline]
+    pub(crate) fn to_u8(self) -> u8 {
+        match self {
+            Protection::ReadOnly => 0,
+            Protection::ReadWrite => 1,
+            Protection::ReadExecute => 2,
+        }
+    }
+
+    /// Decode from a byte produced by [`Protection::to_u8`]
+    ///
+    /// 从 [`Protection::to_u8`] 生成的字节解码
+    #[inline]
+    pub(crate) fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Protection::ReadOnly,
+            2 => Protection::ReadExecute,
+            _ => Protection::ReadWrite,
+        }
+    }
+}
@@ -2,8 +2,9 @@
 //! 
 //! 文件范围和写入凭据类型
 
+use std::num::NonZeroU64;
 use std::ops::Range;
-use super::allocator::{align_up, align_down};
+use super::allocator::{align_up, align_down, sequential, ALIGNMENT};
 
 /// Result of `split_at_align_up`
 /// 
@@ -258,6 +259,68 @@ impl AllocatedRange {
         Self { start, end }
     }
 
+    /// Construct a range from a standard range without any checks
+    ///
+    /// 从标准范围构造，不进行任何检查
+    ///
+    /// Available with the `unchecked-ranges` feature. Intended for integration tests and
+    /// advanced users who manage their own layouts outside of an allocator.
+    ///
+    /// 需要启用 `unchecked-ranges` 特性。适用于集成测试以及在分配器之外自行管理布局的高级用户。
+    ///
+    /// # Safety
+    ///
+    /// ⚠️ **Overlap hazard**: the whole point of `AllocatedRange` is that ranges coming from an
+    /// allocator never overlap, which is what makes [`MmapFile`](crate::MmapFile)'s
+    /// lock-free writes sound. By constructing ranges by hand, the caller takes over that
+    /// guarantee and must ensure:
+    /// - `range.start <= range.end`
+    /// - No two ranges used concurrently with the same file overlap; overlapping concurrent
+    ///   writes (or a write concurrent with a read) are a data race and undefined behavior
+    ///
+    /// Ranges outside the file bounds are still rejected or panic as usual, but overlap is
+    /// not detected at all.
+    ///
+    /// # Safety
+    ///
+    /// ⚠️ **重叠风险**：`AllocatedRange` 的核心保证是来自分配器的范围永不重叠，
+    /// 这正是 [`MmapFile`](crate::MmapFile) 无锁写入安全的前提。手动构造范围时，
+    /// 调用者需要自行承担该保证，并确保：
+    /// - `range.start <= range.end`
+    /// - 同一文件上并发使用的任意两个范围不重叠；重叠的并发写入（或与读取并发的写入）
+    ///   属于数据竞争，是未定义行为
+    ///
+    /// 超出文件边界的范围仍会像往常一样被拒绝或 panic，但重叠完全不会被检测。
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFile, AllocatedRange, Result};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("unchecked.bin");
+    /// use std::num::NonZeroU64;
+    ///
+    /// let (file, _allocator) = MmapFile::create_default(&path, NonZeroU64::new(8192).unwrap())?;
+    ///
+    /// // Safety: this is the only range used with the file
+    /// // Safety: 这是该文件唯一使用的范围
+    /// let range = unsafe { AllocatedRange::new_unchecked(100..105) };
+    /// file.write_range(range, b"hello");
+    ///
+    /// let mut buf = [0u8; 5];
+    /// file.read_range(range, &mut buf)?;
+    /// assert_eq!(&buf, b"hello");
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "unchecked-ranges")]
+    #[inline]
+    pub unsafe fn new_unchecked(range: Range<u64>) -> Self {
+        Self::from_range_unchecked(range.start, range.end)
+    }
+
     /// Get the start position
     /// 
     /// 获取起始位置
@@ -341,6 +404,33 @@ impl AllocatedRange {
     /// ```
     #[inline]
     pub fn split_at_align_up(&self, pos: u64) -> SplitUpResult {
+        self.split_at_align_up_to(pos, ALIGNMENT)
+    }
+
+    /// Split the range at the given relative position, aligning the split point up to `align`
+    /// 
+    /// 在给定相对位置拆分范围，分割点向上对齐到 `align`
+    /// 
+    /// Same as [`split_at_align_up`](Self::split_at_align_up) with a custom alignment instead of 4K.
+    /// 
+    /// 与 [`split_at_align_up`](Self::split_at_align_up) 相同，但使用自定义对齐而不是 4K。
+    /// 
+    /// # Parameters
+    /// - `pos`: Relative offset from the start of the range.
+    /// - `align`: Alignment of the split point, a power of two
+    /// 
+    /// # 参数
+    /// - `pos`: 从范围起始位置开始的相对偏移量。
+    /// - `align`: 分割点的对齐，必须是 2 的幂
+    /// 
+    /// # Panics
+    /// Panics if `align` is not a power of two
+    /// 
+    /// # Panics
+    /// 如果 `align` 不是 2 的幂，则 panic
+    #[inline]
+    pub fn split_at_align_up_to(&self, pos: u64, align: u64) -> SplitUpResult {
+        assert!(align.is_power_of_two(), "alignment {} must be a power of two", align);
         let start = self.start;
         let end = self.end;
         let len = self.len();
@@ -349,15 +439,13 @@ impl AllocatedRange {
             return SplitUpResult::OutOfBounds(*self);
         }
         
-        let split_point = align_up(start + pos);
-        
-        if split_point >= end {
-            SplitUpResult::Low(*self)
-        } else {
-            SplitUpResult::Split {
+        let mask = align - 1;
+        match (start + pos).checked_add(mask).map(|point| point & !mask) {
+            Some(split_point) if split_point < end => SplitUpResult::Split {
                 low: AllocatedRange::from_range_unchecked(start, split_point),
                 high: AllocatedRange::from_range_unchecked(split_point, end),
-            }
+            },
+            _ => SplitUpResult::Low(*self),
         }
     }
 
@@ -399,6 +487,33 @@ impl AllocatedRange {
     /// ```
     #[inline]
     pub fn split_at_align_down(&self, pos: u64) -> SplitDownResult {
+        self.split_at_align_down_to(pos, ALIGNMENT)
+    }
+
+    /// Split the range at the given relative position, aligning the split point down to `align`
+    /// 
+    /// 在给定相对位置拆分范围，分割点向下对齐到 `align`
+    /// 
+    /// Same as [`split_at_align_down`](Self::split_at_align_down) with a custom alignment instead of 4K.
+    /// 
+    /// 与 [`split_at_align_down`](Self::split_at_align_down) 相同，但使用自定义对齐而不是 4K。
+    /// 
+    /// # Parameters
+    /// - `pos`: Relative offset from the start of the range.
+    /// - `align`: Alignment of the split point, a power of two
+    /// 
+    /// # 参数
+    /// - `pos`: 从范围起始位置开始的相对偏移量。
+    /// - `align`: 分割点的对齐，必须是 2 的幂
+    /// 
+    /// # Panics
+    /// Panics if `align` is not a power of two
+    /// 
+    /// # Panics
+    /// 如果 `align` 不是 2 的幂，则 panic
+    #[inline]
+    pub fn split_at_align_down_to(&self, pos: u64, align: u64) -> SplitDownResult {
+        assert!(align.is_power_of_two(), "alignment {} must be a power of two", align);
         let start = self.start;
         let end = self.end;
         let len = self.len();
@@ -407,7 +522,7 @@ impl AllocatedRange {
             return SplitDownResult::OutOfBounds(*self);
         }
         
-        let split_point = align_down(start + pos);
+        let split_point = (start + pos) & !(align - 1);
         
         if split_point <= start {
             SplitDownResult::High(*self)
@@ -419,6 +534,106 @@ impl AllocatedRange {
         }
     }
 
+    /// Split the range exactly at the given relative position
+    /// 
+    /// 在给定相对位置精确拆分范围
+    /// 
+    /// Splits at `start + pos` without any alignment.
+    /// 
+    /// 在 `start + pos` 处拆分，不做任何对齐。
+    /// 
+    /// # Parameters
+    /// - `pos`: Relative offset from the start of the range.
+    /// 
+    /// # Returns
+    /// `(low, high)` covering `[start, start + pos)` and `[start + pos, end)`, or `None` if
+    /// either would be empty (`pos == 0` or `pos >= len`)
+    /// 
+    /// # 参数
+    /// - `pos`: 从范围起始位置开始的相对偏移量。
+    /// 
+    /// # 返回值
+    /// 覆盖 `[start, start + pos)` 和 `[start + pos, end)` 的 `(low, high)`；
+    /// 如果其中任一为空（`pos == 0` 或 `pos >= len`），返回 `None`
+    #[inline]
+    pub fn split_at(&self, pos: u64) -> Option<(AllocatedRange, AllocatedRange)> {
+        if pos == 0 || pos >= self.len() {
+            return None;
+        }
+
+        let split_point = self.start + pos;
+        Some((
+            AllocatedRange::from_range_unchecked(self.start, split_point),
+            AllocatedRange::from_range_unchecked(split_point, self.end),
+        ))
+    }
+
+    /// Iterate over the 4K pages the range covers
+    /// 
+    /// 遍历范围覆盖的 4K 页面
+    /// 
+    /// Yields the intersection of the range with each 4K-aligned page it touches, in order.
+    /// For a page-aligned range every item is a full page; otherwise the first and last items
+    /// may be partial. An empty range yields nothing.
+    /// 
+    /// 按顺序产生范围与其涉及的每个 4K 对齐页面的交集。对于页对齐的范围，每一项都是完整页面；
+    /// 否则第一项和最后一项可能是不完整的页面。空范围不产生任何项。
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 3).unwrap());
+    /// let range = allocator.allocate(NonZeroU64::new(ALIGNMENT * 3).unwrap()).unwrap();
+    /// assert_eq!(range.pages().count(), 3);
+    /// ```
+    #[inline]
+    pub fn pages(&self) -> impl Iterator<Item = AllocatedRange> + use<> {
+        let end = self.end;
+        let mut start = self.start;
+        std::iter::from_fn(move || {
+            if start >= end {
+                return None;
+            }
+            let page_end = align_down(start).saturating_add(ALIGNMENT).min(end);
+            let page = AllocatedRange::from_range_unchecked(start, page_end);
+            start = page_end;
+            Some(page)
+        })
+    }
+
+    /// Turn the range into an allocator over exactly its own space
+    /// 
+    /// 将范围转换为恰好覆盖其自身空间的分配器
+    /// 
+    /// The allocator hands out absolute file offsets within `[start, end)`, so the range can be
+    /// subdivided among sub-tasks and each piece split again in turn. Allocation sizes are
+    /// rounded up to 4K as usual, counted from `start`. An empty range yields an exhausted allocator.
+    /// 
+    /// 分配器在 `[start, end)` 内分配绝对文件偏移，因此范围可以在子任务之间再次划分，
+    /// 每一块又可以继续拆分。分配大小照常从 `start` 开始向上对齐到 4K。空范围得到一个已耗尽的分配器。
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// # use ranged_mmap::allocator::{sequential::Allocator, RangeAllocator, ALIGNMENT};
+    /// # use std::num::NonZeroU64;
+    /// let mut allocator = Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap());
+    /// let arena = allocator.allocate(NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+    ///
+    /// let mut sub = arena.into_allocator();
+    /// assert_eq!(sub.allocate(NonZeroU64::new(1).unwrap()).unwrap().start(), 0);
+    /// assert_eq!(sub.allocate(NonZeroU64::new(1).unwrap()).unwrap().end(), ALIGNMENT * 2);
+    /// assert!(sub.allocate(NonZeroU64::new(1).unwrap()).is_none());
+    /// ```
+    #[inline]
+    pub fn into_allocator(self) -> sequential::Allocator {
+        let total = NonZeroU64::new(self.end).unwrap_or(NonZeroU64::MIN);
+        let start = if self.is_empty() { total.get() } else { self.start };
+        sequential::Allocator::starting_at(total, start)
+    }
+
     /// Get the range as a tuple (start, end)
     /// 
     /// 获取范围的元组表示 (start, end)
@@ -442,6 +657,34 @@ impl AllocatedRange {
     pub fn as_range(&self) -> Range<u64> {
         self.start..self.end
     }
+
+    /// Get the 4K aligned span of pages the range touches
+    /// 
+    /// 获取范围所涉及页面的 4K 对齐区间
+    /// 
+    /// Returns `(align_down(start), align_up(end))`, the region a flush of this range
+    /// actually writes back, since `msync` works on whole pages. On platforms with pages
+    /// larger than [`ALIGNMENT`](super::allocator::ALIGNMENT) the kernel may flush more.
+    /// 
+    /// 返回 `(align_down(start), align_up(end))`，即刷新此范围时实际写回的区域，
+    /// 因为 `msync` 以整页为单位工作。在页面大于 [`ALIGNMENT`](super::allocator::ALIGNMENT)
+    /// 的平台上，内核可能会刷新更多内容。
+    #[inline]
+    pub fn page_span(&self) -> (u64, u64) {
+        (align_down(self.start), align_up(self.end))
+    }
+
+    /// Check whether `other` lies entirely within this range
+    /// 
+    /// 检查 `other` 是否完全位于此范围之内
+    /// 
+    /// An empty `other` is contained when its position falls within `[start, end]`.
+    /// 
+    /// 当空的 `other` 位于 `[start, end]` 之内时视为被包含。
+    #[inline]
+    pub fn contains_range(&self, other: &AllocatedRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
 }
 
 impl From<AllocatedRange> for Range<u64> {
@@ -549,12 +792,25 @@ impl WriteReceipt {
     pub fn is_empty(&self) -> bool {
         self.range.is_empty()
     }
+
+    /// Check whether this receipt covers another receipt's range
+    /// 
+    /// 检查此凭据是否覆盖另一个凭据的范围
+    /// 
+    /// A covered receipt is flushed by flushing this one, so flush schedulers can drop
+    /// nested receipts. Delegates to [`AllocatedRange::contains_range`].
+    /// 
+    /// 刷新此凭据即会刷新被覆盖的凭据，因此刷新调度器可以去除嵌套的凭据。
+    /// 委托给 [`AllocatedRange::contains_range`]。
+    #[inline]
+    pub fn covers(&self, other: &WriteReceipt) -> bool {
+        self.range.contains_range(&other.range)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use super::super::allocator::ALIGNMENT;
 
     // ========== split_at_align_up tests ==========
 
@@ -791,4 +1047,155 @@ mod tests {
         assert_eq!(result.low(), None);
         assert_eq!(result.high(), Some(range));
     }
+
+    // ========== page_span tests ==========
+
+    #[test]
+    fn test_page_span_unaligned() {
+        let range = AllocatedRange::from_range_unchecked(100, 5000);
+        assert_eq!(range.page_span(), (0, ALIGNMENT * 2));
+    }
+
+    #[test]
+    fn test_page_span_already_aligned() {
+        let range = AllocatedRange::from_range_unchecked(ALIGNMENT, ALIGNMENT * 3);
+        assert_eq!(range.page_span(), range.as_range_tuple());
+    }
+
+    // ========== split_at tests ==========
+
+    #[test]
+    fn test_split_at_exact() {
+        let range = AllocatedRange::from_range_unchecked(ALIGNMENT, ALIGNMENT * 3);
+        let (low, high) = range.split_at(100).unwrap();
+        assert_eq!(low.as_range_tuple(), (ALIGNMENT, ALIGNMENT + 100));
+        assert_eq!(high.as_range_tuple(), (ALIGNMENT + 100, ALIGNMENT * 3));
+    }
+
+    #[test]
+    fn test_split_at_empty_side() {
+        let range = AllocatedRange::from_range_unchecked(0, 8192);
+        assert!(range.split_at(0).is_none());
+        assert!(range.split_at(8192).is_none());
+        assert!(range.split_at(9000).is_none());
+        assert!(range.split_at(8191).is_some());
+    }
+
+    // ========== custom alignment split tests ==========
+
+    #[test]
+    fn test_split_at_align_up_to_custom() {
+        // Range [0, 2048), split at pos 100 with 512 alignment -> 512
+        let range = AllocatedRange::from_range_unchecked(0, 2048);
+        match range.split_at_align_up_to(100, 512) {
+            SplitUpResult::Split { low, high } => {
+                assert_eq!(low.as_range_tuple(), (0, 512));
+                assert_eq!(high.as_range_tuple(), (512, 2048));
+            }
+            _ => panic!("expected split"),
+        }
+
+        // Aligned up to the end
+        assert_eq!(range.split_at_align_up_to(1600, 1024), SplitUpResult::Low(range));
+        // Same as the 4K variant
+        assert_eq!(range.split_at_align_up_to(100, ALIGNMENT), range.split_at_align_up(100));
+    }
+
+    #[test]
+    fn test_split_at_align_down_to_custom() {
+        // Range [0, 2048), split at pos 700 with 512 alignment -> 512
+        let range = AllocatedRange::from_range_unchecked(0, 2048);
+        match range.split_at_align_down_to(700, 512) {
+            SplitDownResult::Split { low, high } => {
+                assert_eq!(low.as_range_tuple(), (0, 512));
+                assert_eq!(high.as_range_tuple(), (512, 2048));
+            }
+            _ => panic!("expected split"),
+        }
+
+        // Aligned down to the start
+        assert_eq!(range.split_at_align_down_to(300, 512), SplitDownResult::High(range));
+        assert_eq!(range.split_at_align_down_to(3000, 512), SplitDownResult::OutOfBounds(range));
+    }
+
+    #[test]
+    #[should_panic(expected = "power of two")]
+    fn test_split_at_align_rejects_non_power_of_two() {
+        let range = AllocatedRange::from_range_unchecked(0, 2048);
+        range.split_at_align_up_to(100, 3000);
+    }
+
+    // ========== into_allocator tests ==========
+
+    #[test]
+    fn test_into_allocator_within_bounds() {
+        let range = AllocatedRange::from_range_unchecked(4096, 12288);
+        let mut allocator = range.into_allocator();
+
+        let first = allocator.allocate(NonZeroU64::new(100).unwrap()).unwrap();
+        assert_eq!(first.as_range_tuple(), (4096, 8192));
+
+        // Last allocation is clamped to the end of the range
+        let second = allocator.allocate(NonZeroU64::new(ALIGNMENT * 4).unwrap()).unwrap();
+        assert_eq!(second.as_range_tuple(), (8192, 12288));
+        assert!(allocator.allocate(NonZeroU64::new(1).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_into_allocator_empty_range() {
+        for range in [AllocatedRange::from_range_unchecked(0, 0), AllocatedRange::from_range_unchecked(4096, 4096)] {
+            let mut allocator = range.into_allocator();
+            assert_eq!(allocator.remaining(), 0);
+            assert!(allocator.allocate(NonZeroU64::new(1).unwrap()).is_none());
+        }
+    }
+
+    // ========== pages tests ==========
+
+    #[test]
+    fn test_pages_aligned() {
+        let range = AllocatedRange::from_range_unchecked(0, 12288);
+        let pages: Vec<_> = range.pages().map(|page| page.as_range_tuple()).collect();
+        assert_eq!(pages, vec![(0, 4096), (4096, 8192), (8192, 12288)]);
+    }
+
+    #[test]
+    fn test_pages_partial_first_and_last() {
+        let range = AllocatedRange::from_range_unchecked(100, 5000);
+        let pages: Vec<_> = range.pages().map(|page| page.as_range_tuple()).collect();
+        assert_eq!(pages, vec![(100, 4096), (4096, 5000)]);
+
+        // Within a single page
+        let range = AllocatedRange::from_range_unchecked(100, 200);
+        assert_eq!(range.pages().collect::<Vec<_>>(), vec![range]);
+    }
+
+    #[test]
+    fn test_pages_empty() {
+        let range = AllocatedRange::from_range_unchecked(4096, 4096);
+        assert_eq!(range.pages().count(), 0);
+    }
+
+    // ========== containment tests ==========
+
+    #[test]
+    fn test_contains_range() {
+        let outer = AllocatedRange::from_range_unchecked(0, 8192);
+        assert!(outer.contains_range(&outer));
+        assert!(outer.contains_range(&AllocatedRange::from_range_unchecked(4096, 8192)));
+        assert!(!outer.contains_range(&AllocatedRange::from_range_unchecked(4096, 12288)));
+        assert!(!AllocatedRange::from_range_unchecked(4096, 8192).contains_range(&outer));
+    }
+
+    #[test]
+    fn test_receipt_covers() {
+        let big = WriteReceipt::new(AllocatedRange::from_range_unchecked(0, 8192));
+        let nested = WriteReceipt::new(AllocatedRange::from_range_unchecked(0, 4096));
+        let straddling = WriteReceipt::new(AllocatedRange::from_range_unchecked(4096, 12288));
+
+        assert!(big.covers(&nested));
+        assert!(!big.covers(&straddling));
+        assert!(!nested.covers(&big));
+    }
+
 }
@@ -0,0 +1,222 @@
+//! Pointer-only mapping backed by `memmap2::MmapRaw`
+//!
+//! 基于 `memmap2::MmapRaw` 的仅指针访问映射
+
+use super::error::{Error, Result};
+use super::mmap_file_inner::{check_platform_size, MmapFileInner};
+use memmap2::MmapRaw;
+use std::num::NonZeroU64;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Memory-mapped file exposing only raw pointers
+///
+/// 仅暴露裸指针的内存映射文件
+///
+/// Backed by [`memmap2::MmapRaw`], which never hands out `&[u8]` or `&mut [u8]` to the
+/// mapping, so no Rust reference can alias memory that other threads or processes modify.
+/// This is meant for users implementing their own synchronization on top, e.g. with atomics
+/// or a custom lock protocol.
+///
+/// 由 [`memmap2::MmapRaw`] 支持，它永远不会交出指向映射的 `&[u8]` 或 `&mut [u8]`，
+/// 因此不会有 Rust 引用与其他线程或进程修改的内存产生别名。
+/// 适用于在其上自行实现同步的用户，例如使用原子操作或自定义锁协议。
+///
+/// Compared to [`MmapFileInner`] this type offers fewer guarantees: there is no bounds
+/// checking, no write tracking (high watermark), no protection changes and no resizing.
+/// Every access goes through [`as_ptr`](Self::as_ptr) / [`as_mut_ptr`](Self::as_mut_ptr),
+/// and keeping those accesses in bounds and race-free is entirely up to the caller.
+/// Clones share the same mapping, which is unmapped when the last clone is dropped.
+///
+/// 与 [`MmapFileInner`] 相比，此类型提供的保证更少：没有边界检查、没有写入跟踪（高水位线）、
+/// 不能更改保护，也不能调整大小。所有访问都通过 [`as_ptr`](Self::as_ptr) / [`as_mut_ptr`](Self::as_mut_ptr)
+/// 进行，确保访问不越界且没有竞争完全由调用者负责。克隆共享同一映射，最后一个克隆被丢弃时解除映射。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{RawMmapFile, Result};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("raw.bin");
+/// # use std::num::NonZeroU64;
+/// let file = RawMmapFile::create(&path, NonZeroU64::new(4096).unwrap())?;
+///
+/// // Safety: offset 0 is in bounds and no other thread accesses it
+/// // Safety: 偏移 0 在边界内，且没有其他线程访问它
+/// unsafe {
+///     file.as_mut_ptr().write(42);
+///     assert_eq!(file.as_ptr().read(), 42);
+/// }
+/// file.flush()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RawMmapFile {
+    /// Shared raw mapping
+    ///
+    /// 共享的原始映射
+    mmap: Arc<MmapRaw>,
+
+    /// Mapped size in bytes
+    ///
+    /// 映射大小（字节）
+    size: NonZeroU64,
+}
+
+impl RawMmapFile {
+    /// Create a new file and map it as raw memory
+    ///
+    /// 创建新文件并以原始内存方式映射
+    ///
+    /// If the file already exists, it will be truncated.
+    ///
+    /// 如果文件已存在，会被截断。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    /// - `size`: File size in bytes
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    /// - `size`: 文件大小（字节）
+    ///
+    /// # Errors
+    /// - Returns `FileTooLargeForPlatform` error if size exceeds `usize::MAX`
+    /// - Returns corresponding I/O errors if file creation or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果 size 超过 `usize::MAX`，返回 `FileTooLargeForPlatform` 错误
+    /// - 如果无法创建文件或映射内存，返回相应的 I/O 错误
+    pub fn create(path: impl AsRef<Path>, size: NonZeroU64) -> Result<Self> {
+        let file = MmapFileInner::create_file(path.as_ref(), size, true)?;
+        Self::from_mmap_raw(MmapRaw::map_raw(&file)?)
+    }
+
+    /// Open an existing file and map it as raw memory
+    ///
+    /// 打开已存在的文件并以原始内存方式映射
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    ///
+    /// # Errors
+    /// - Returns `EmptyFile` error if the file is empty
+    /// - Returns corresponding I/O errors if the file cannot be opened or mapped
+    ///
+    /// # Errors
+    /// - 如果文件为空，返回 `EmptyFile` 错误
+    /// - 如果无法打开或映射文件，返回相应的 I/O 错误
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(path)?;
+        check_platform_size(file.metadata()?.len())?;
+        Self::from_mmap_raw(MmapRaw::map_raw(&file)?)
+    }
+
+    /// Wrap an existing raw mapping
+    ///
+    /// 包装已有的原始映射
+    ///
+    /// # Parameters
+    /// - `mmap`: Raw mapping created by the caller, e.g. with custom [`memmap2::MmapOptions`]
+    ///
+    /// # 参数
+    /// - `mmap`: 由调用者创建的原始映射，例如使用自定义的 [`memmap2::MmapOptions`]
+    ///
+    /// # Errors
+    /// Returns `EmptyFile` error if the mapping is empty
+    ///
+    /// # Errors
+    /// 如果映射为空，返回 `EmptyFile` 错误
+    pub fn from_mmap_raw(mmap: MmapRaw) -> Result<Self> {
+        let size = NonZeroU64::new(mmap.len() as u64).ok_or(Error::EmptyFile)?;
+        Ok(Self {
+            mmap: Arc::new(mmap),
+            size,
+        })
+    }
+
+    /// Get the mapped size
+    ///
+    /// 获取映射大小
+    #[inline]
+    pub fn size(&self) -> NonZeroU64 {
+        self.size
+    }
+
+    /// Get a read pointer to the start of the mapping
+    ///
+    /// 获取指向映射起始位置的读指针
+    ///
+    /// The pointer stays valid as long as any clone of this handle is alive.
+    ///
+    /// 只要此句柄的任一克隆存活，该指针就保持有效。
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.mmap.as_ptr()
+    }
+
+    /// Get a write pointer to the start of the mapping
+    ///
+    /// 获取指向映射起始位置的写指针
+    ///
+    /// The pointer stays valid as long as any clone of this handle is alive.
+    ///
+    /// 只要此句柄的任一克隆存活，该指针就保持有效。
+    #[inline]
+    pub fn as_mut_ptr(&self) -> *mut u8 {
+        self.mmap.as_mut_ptr()
+    }
+
+    /// Flush the whole mapping to disk synchronously
+    ///
+    /// 同步刷新整个映射到磁盘
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if the flush fails
+    ///
+    /// # Errors
+    /// 如果刷新失败，返回相应的 I/O 错误
+    #[inline]
+    pub fn flush(&self) -> Result<()> {
+        Ok(self.mmap.flush()?)
+    }
+
+    /// Flush a range of the mapping to disk synchronously
+    ///
+    /// 同步刷新映射的指定范围到磁盘
+    ///
+    /// # Parameters
+    /// - `offset`: Start of the range
+    /// - `len`: Length of the range
+    ///
+    /// # 参数
+    /// - `offset`: 范围起始位置
+    /// - `len`: 范围长度
+    ///
+    /// # Errors
+    /// - Returns `OutOfBounds` error if the range exceeds the mapping
+    /// - Returns corresponding I/O errors if the flush fails
+    ///
+    /// # Errors
+    /// - 如果范围超出映射，返回 `OutOfBounds` 错误
+    /// - 如果刷新失败，返回相应的 I/O 错误
+    pub fn flush_range(&self, offset: u64, len: usize) -> Result<()> {
+        match offset.checked_add(len as u64) {
+            Some(end) if end <= self.size.get() => Ok(self.mmap.flush_range(offset as usize, len)?),
+            _ => Err(Error::OutOfBounds {
+                offset,
+                len: len as u64,
+                size: self.size.get(),
+            }),
+        }
+    }
+}
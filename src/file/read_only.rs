@@ -0,0 +1,238 @@
+//! Read-only mapping for reader/writer separation
+//!
+//! 用于读写分离的只读映射
+
+use super::error::{Error, Result};
+use super::mmap_file_inner::{check_platform_size, MmapFileInner};
+use super::sys;
+use memmap2::Mmap;
+use std::fs::OpenOptions;
+use std::num::NonZeroU64;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Read-only memory-mapped file
+///
+/// 只读内存映射文件
+///
+/// The file is opened for reading only and mapped with read-only protection, so this handle
+/// has no write methods and any stray write through a raw pointer faults instead of silently
+/// corrupting data. Usually obtained from [`MmapFileInner::open_pair`] together with a
+/// writable mapping of the same file. Clones share the same mapping.
+///
+/// 文件仅以读方式打开并以只读保护映射，因此此句柄没有任何写入方法，
+/// 通过裸指针的意外写入会触发错误，而不会悄悄破坏数据。
+/// 通常与同一文件的可写映射一起由 [`MmapFileInner::open_pair`] 获得。克隆共享同一映射。
+#[derive(Clone)]
+pub struct ReadOnlyMmapFile {
+    /// Shared read-only mapping
+    ///
+    /// 共享的只读映射
+    mmap: Arc<Mmap>,
+
+    /// Mapped size in bytes
+    ///
+    /// 映射大小（字节）
+    size: NonZeroU64,
+}
+
+impl ReadOnlyMmapFile {
+    /// Open an existing file as a read-only mapping
+    ///
+    /// 以只读映射的方式打开已存在的文件
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    ///
+    /// # Errors
+    /// - Returns `EmptyFile` error if the file is empty
+    /// - Returns corresponding I/O errors if opening or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果文件为空，返回 `EmptyFile` 错误
+    /// - 如果无法打开文件或映射内存，返回相应的 I/O 错误
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let file = OpenOptions::new().read(true).open(path)?;
+
+        let size = NonZeroU64::new(file.metadata()?.len()).ok_or(Error::EmptyFile)?;
+        check_platform_size(size.get())?;
+
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        Ok(Self {
+            mmap: Arc::new(mmap),
+            size,
+        })
+    }
+
+    /// Get the mapped size
+    ///
+    /// 获取映射大小
+    #[inline]
+    pub fn size(&self) -> NonZeroU64 {
+        self.size
+    }
+
+    /// Get a read pointer to the start of the mapping
+    ///
+    /// 获取指向映射起始位置的读指针
+    #[inline]
+    pub fn as_ptr(&self) -> *const u8 {
+        self.mmap.as_ptr()
+    }
+
+    /// View the whole mapping as a byte slice
+    ///
+    /// 将整个映射视为字节切片
+    ///
+    /// This handle cannot write, but the mapping is shared with the file, so changes made
+    /// through another mapping (such as the writer from [`MmapFileInner::open_pair`]) show up
+    /// in the slice while it is borrowed. On a writable [`MmapFileInner`], use
+    /// [`as_slice`](MmapFileInner::as_slice) instead.
+    ///
+    /// 此句柄无法写入，但映射与文件共享，因此在借用切片期间，通过其他映射
+    /// （例如 [`MmapFileInner::open_pair`] 返回的写入者）所做的修改会出现在切片中。
+    /// 对于可写的 [`MmapFileInner`]，请改用 [`as_slice`](MmapFileInner::as_slice)。
+    ///
+    /// # Safety
+    ///
+    /// No writer may modify any part of the file while the returned slice is alive, through
+    /// this process or any other.
+    ///
+    /// # Safety
+    ///
+    /// 在返回的切片存活期间，任何写入者（无论在本进程还是其他进程）都不得修改文件的任何部分。
+    #[inline]
+    pub unsafe fn bytes(&self) -> &[u8] {
+        &self.mmap
+    }
+
+    /// Read data starting at the specified position
+    ///
+    /// 从指定位置开始读取数据
+    ///
+    /// # Safety
+    ///
+    /// No writer may modify `[offset, offset + buf.len())` during the read, through this
+    /// process or any other.
+    ///
+    /// # Safety
+    ///
+    /// 读取期间，任何写入者（无论在本进程还是其他进程）都不得修改 `[offset, offset + buf.len())`。
+    ///
+    /// # Parameters
+    /// - `offset`: Read position
+    /// - `buf`: Buffer to receive data
+    ///
+    /// # Returns
+    /// Number of bytes actually read
+    ///
+    /// # 参数
+    /// - `offset`: 读取位置
+    /// - `buf`: 接收数据的缓冲区
+    ///
+    /// # 返回值
+    /// 返回实际读取的字节数
+    pub unsafe fn read_at(&self, offset: u64, buf: &mut [u8]) -> Result<usize> {
+        if offset >= self.size.get() {
+            return Ok(0);
+        }
+
+        // The size fits in usize (checked on map), so the offset does too
+        // 大小在映射时已检查能放入 usize，因此偏移量也能
+        let offset = offset as usize;
+        let available = (self.size.get() as usize - offset).min(buf.len());
+        buf[..available].copy_from_slice(&self.mmap[offset..offset + available]);
+
+        Ok(available)
+    }
+
+    /// Make writes done through other mappings of the file visible to this one
+    ///
+    /// 使通过该文件其他映射完成的写入对本映射可见
+    ///
+    /// Calls `msync(MS_INVALIDATE)` on Unix. Shared mappings of the same file are coherent on
+    /// Linux and Windows, so this is a no-op there, but portable code should call it after the
+    /// writer has synced and before reading the new data.
+    ///
+    /// 在 Unix 上调用 `msync(MS_INVALIDATE)`。在 Linux 和 Windows 上同一文件的共享映射是一致的，
+    /// 因此这是空操作，但可移植代码应在写入者同步之后、读取新数据之前调用它。
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if the invalidation fails
+    ///
+    /// # Errors
+    /// 如果失效操作失败，返回相应的 I/O 错误
+    pub fn invalidate(&self) -> Result<()> {
+        // Safety: The mapping is page-aligned and lives as long as self
+        // Safety: 映射是页对齐的，且与 self 存活时间相同
+        unsafe { sys::invalidate(self.mmap.as_ptr(), self.mmap.len())? };
+        Ok(())
+    }
+}
+
+impl MmapFileInner {
+    /// Open an existing file as a read-only mapping and a read-write mapping
+    ///
+    /// 将已存在的文件同时打开为只读映射和读写映射
+    ///
+    /// The two mappings are independent: the reader cannot modify the file even by mistake,
+    /// while the writer behaves like [`open`](Self::open). After the writer syncs, call
+    /// [`ReadOnlyMmapFile::invalidate`] before reading the new data.
+    ///
+    /// 两个映射相互独立：读取者即使出错也无法修改文件，写入者的行为与 [`open`](Self::open) 相同。
+    /// 写入者同步之后，在读取新数据之前调用 [`ReadOnlyMmapFile::invalidate`]。
+    ///
+    /// # Parameters
+    /// - `path`: File path
+    ///
+    /// # Returns
+    /// `(reader, writer)`
+    ///
+    /// # 参数
+    /// - `path`: 文件路径
+    ///
+    /// # 返回值
+    /// `(读取者, 写入者)`
+    ///
+    /// # Errors
+    /// - Returns `EmptyFile` error if the file is empty
+    /// - Returns corresponding I/O errors if opening or memory mapping fails
+    ///
+    /// # Errors
+    /// - 如果文件为空，返回 `EmptyFile` 错误
+    /// - 如果无法打开文件或映射内存，返回相应的 I/O 错误
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use ranged_mmap::{MmapFileInner, Result};
+    /// # use tempfile::tempdir;
+    /// # fn main() -> Result<()> {
+    /// # let dir = tempdir()?;
+    /// # let path = dir.path().join("pair.bin");
+    /// # std::fs::write(&path, [0u8; 4096])?;
+    /// let (reader, writer) = MmapFileInner::open_pair(&path)?;
+    ///
+    /// unsafe {
+    ///     writer.write_all_at(0, b"hello");
+    ///     writer.sync_all()?;
+    /// }
+    /// reader.invalidate()?;
+    ///
+    /// let mut buf = [0u8; 5];
+    /// unsafe { reader.read_at(0, &mut buf)?; }
+    /// assert_eq!(&buf, b"hello");
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn open_pair(path: impl AsRef<Path>) -> Result<(ReadOnlyMmapFile, MmapFileInner)> {
+        let path = path.as_ref();
+        let writer = Self::open(path)?;
+        let reader = ReadOnlyMmapFile::open(path)?;
+        Ok((reader, writer))
+    }
+}
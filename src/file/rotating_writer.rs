@@ -0,0 +1,224 @@
+//! Append-only writer spilling across numbered files
+//!
+//! 跨多个编号文件滚动的追加写入器
+
+use super::allocator::sequential::Allocator;
+use super::error::{Error, Result};
+use super::mmap_file::MmapFile;
+use super::range::{AllocatedRange, WriteReceipt};
+use std::num::NonZeroU64;
+use std::path::{Path, PathBuf};
+
+/// Index of a file in a [`RotatingMmapWriter`] sequence
+///
+/// [`RotatingMmapWriter`] 文件序列中的文件编号
+pub type FileIndex = u64;
+
+/// Writer that appends to fixed-size files, opening the next one when the current is full
+///
+/// 向固定大小的文件追加写入的写入器，当前文件写满时打开下一个文件
+///
+/// Files are named `{base_name}.{index:06}` inside `dir`, starting at `base_name.000000`.
+/// Each file is an [`MmapFile`] filled by a [`sequential::Allocator`](Allocator), so every
+/// append occupies a 4K aligned slot. A record never straddles two files: if it does not
+/// fit in what is left of the current file, the current file is flushed and the record goes
+/// to the start of the next one. Existing files with the same names are truncated.
+///
+/// 文件在 `dir` 中命名为 `{base_name}.{index:06}`，从 `base_name.000000` 开始。
+/// 每个文件都是由 [`sequential::Allocator`](Allocator) 填充的 [`MmapFile`]，
+/// 因此每次追加占用一个 4K 对齐的槽位。记录永远不会跨越两个文件：如果当前文件的剩余空间放不下，
+/// 则刷新当前文件，并将记录写到下一个文件的开头。同名的已有文件会被截断。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{RotatingMmapWriter, Result, allocator::ALIGNMENT};
+/// # use tempfile::tempdir;
+/// # fn main() -> Result<()> {
+/// # let dir = tempdir()?;
+/// # use std::num::NonZeroU64;
+/// let mut writer = RotatingMmapWriter::new(dir.path(), "log", NonZeroU64::new(ALIGNMENT).unwrap())?;
+///
+/// let (first, _) = writer.append(b"hello")?;
+/// let (second, _) = writer.append(b"world")?;
+/// assert_eq!((first, second), (0, 1));
+/// assert!(dir.path().join("log.000001").exists());
+/// # Ok(())
+/// # }
+/// ```
+pub struct RotatingMmapWriter {
+    /// Directory holding the files
+    ///
+    /// 存放文件的目录
+    dir: PathBuf,
+
+    /// File name prefix
+    ///
+    /// 文件名前缀
+    base_name: String,
+
+    /// Size of every file
+    ///
+    /// 每个文件的大小
+    file_size: NonZeroU64,
+
+    /// Index of the current file
+    ///
+    /// 当前文件的编号
+    index: FileIndex,
+
+    /// Current file
+    ///
+    /// 当前文件
+    file: MmapFile,
+
+    /// Allocator of the current file
+    ///
+    /// 当前文件的分配器
+    allocator: Allocator,
+}
+
+impl RotatingMmapWriter {
+    /// Create a writer and its first file
+    ///
+    /// 创建写入器及其第一个文件
+    ///
+    /// # Parameters
+    /// - `dir`: Directory for the files, must already exist
+    /// - `base_name`: File name prefix
+    /// - `file_size`: Size of every file in bytes
+    ///
+    /// # 参数
+    /// - `dir`: 存放文件的目录，必须已存在
+    /// - `base_name`: 文件名前缀
+    /// - `file_size`: 每个文件的大小（字节）
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if the first file cannot be created
+    ///
+    /// # Errors
+    /// 如果无法创建第一个文件，返回相应的 I/O 错误
+    pub fn new(dir: impl AsRef<Path>, base_name: impl Into<String>, file_size: NonZeroU64) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        let base_name = base_name.into();
+        let (file, allocator) = MmapFile::create_default(file_path(&dir, &base_name, 0), file_size)?;
+
+        Ok(Self {
+            dir,
+            base_name,
+            file_size,
+            index: 0,
+            file,
+            allocator,
+        })
+    }
+
+    /// Append `data`, rolling over to the next file if the current one is full
+    ///
+    /// 追加 `data`，当前文件已满时滚动到下一个文件
+    ///
+    /// # Parameters
+    /// - `data`: Record to append, at most `file_size` bytes
+    ///
+    /// # 参数
+    /// - `data`: 要追加的记录，不超过 `file_size` 字节
+    ///
+    /// # Returns
+    /// The index of the file holding the record and a [`WriteReceipt`] for its bytes
+    /// in that file (empty if `data` is empty)
+    ///
+    /// # 返回值
+    /// 存放该记录的文件编号，以及覆盖其在该文件中字节的 [`WriteReceipt`]（`data` 为空时为空凭据）
+    ///
+    /// # Errors
+    /// - Returns `DataTooLarge` error if `data` is longer than `file_size`
+    /// - Returns corresponding I/O errors if flushing the full file or creating the next one fails
+    ///
+    /// # Errors
+    /// - 如果 `data` 比 `file_size` 长，返回 `DataTooLarge` 错误
+    /// - 如果刷新已满的文件或创建下一个文件失败，返回相应的 I/O 错误
+    pub fn append(&mut self, data: impl AsRef<[u8]>) -> Result<(FileIndex, WriteReceipt)> {
+        let data = data.as_ref();
+        let Some(size) = NonZeroU64::new(data.len() as u64) else {
+            let pos = self.allocator.next_pos();
+            return Ok((self.index, WriteReceipt::new(AllocatedRange::from_range_unchecked(pos, pos))));
+        };
+        if size > self.file_size {
+            return Err(Error::DataTooLarge {
+                data_len: data.len(),
+                range_len: self.file_size.get(),
+            });
+        }
+
+        let range = match self.allocator.peek(size) {
+            Some(range) if range.len() >= size.get() => range,
+            _ => {
+                self.rotate()?;
+                self.allocator.peek(size).expect("a new file fits any record up to file_size")
+            }
+        };
+        self.allocator.allocate(size);
+
+        let receipt = self.file.write_partial(range, data, false)?;
+        Ok((self.index, receipt))
+    }
+
+    /// Flush the current file and switch to the next one
+    ///
+    /// 刷新当前文件并切换到下一个文件
+    fn rotate(&mut self) -> Result<()> {
+        self.file.flush()?;
+
+        let index = self.index + 1;
+        let (file, allocator) = MmapFile::create_default(self.path(index), self.file_size)?;
+        self.file = file;
+        self.allocator = allocator;
+        self.index = index;
+        Ok(())
+    }
+
+    /// Get the index of the file currently written to
+    ///
+    /// 获取当前写入的文件编号
+    #[inline]
+    pub fn current_index(&self) -> FileIndex {
+        self.index
+    }
+
+    /// Get the file currently written to
+    ///
+    /// 获取当前写入的文件
+    #[inline]
+    pub fn current_file(&self) -> &MmapFile {
+        &self.file
+    }
+
+    /// Get the path of the file with the given index
+    ///
+    /// 获取指定编号文件的路径
+    #[inline]
+    pub fn path(&self, index: FileIndex) -> PathBuf {
+        file_path(&self.dir, &self.base_name, index)
+    }
+
+    /// Flush the current file to disk asynchronously
+    ///
+    /// 异步刷新当前文件到磁盘
+    ///
+    /// # Errors
+    /// Returns corresponding I/O errors if the flush fails
+    ///
+    /// # Errors
+    /// 如果刷新失败，返回相应的 I/O 错误
+    #[inline]
+    pub fn flush(&self) -> Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Build the path `{dir}/{base_name}.{index:06}`
+///
+/// 构建路径 `{dir}/{base_name}.{index:06}`
+fn file_path(dir: &Path, base_name: &str, index: FileIndex) -> PathBuf {
+    dir.join(format!("{}.{:06}", base_name, index))
+}
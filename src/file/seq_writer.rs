@@ -0,0 +1,144 @@
+//! `std::io::Write` sink that fills a file sequentially
+//!
+//! 顺序填充文件的 `std::io::Write` 写入端
+
+use super::mmap_file_inner::MmapFileInner;
+use super::range::{AllocatedRange, WriteReceipt};
+use std::io;
+
+/// Sequential writer over the unallocated tail of a file
+///
+/// 覆盖文件未分配尾部的顺序写入器
+///
+/// Created by [`MmapFile::sequential_writer`](super::MmapFile::sequential_writer), which hands
+/// the writer the rest of the allocator's space. Every `write` claims exactly `buf.len()`
+/// bytes right after the previous one, so written data is contiguous and not padded to 4K.
+/// `write` returns `Ok(0)` once the file is full, so `write_all` fails with
+/// [`WriteZero`](io::ErrorKind::WriteZero). `flush` synchronously flushes everything written
+/// so far.
+///
+/// 由 [`MmapFile::sequential_writer`](super::MmapFile::sequential_writer) 创建，
+/// 它将分配器的剩余空间交给写入器。每次 `write` 紧接上一次写入之后占用恰好 `buf.len()` 字节，
+/// 因此写入的数据是连续的，不会被填充到 4K。文件写满后 `write` 返回 `Ok(0)`，
+/// 因此 `write_all` 会以 [`WriteZero`](io::ErrorKind::WriteZero) 失败。
+/// `flush` 同步刷新迄今为止写入的所有数据。
+///
+/// # Examples
+///
+/// ```
+/// # use ranged_mmap::{MmapFile, allocator::ALIGNMENT};
+/// # use tempfile::tempdir;
+/// # use std::io::Write;
+/// # fn main() -> std::io::Result<()> {
+/// # let dir = tempdir()?;
+/// # let path = dir.path().join("seq.bin");
+/// # use std::num::NonZeroU64;
+/// let (file, allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap())?;
+///
+/// let mut writer = file.sequential_writer(allocator);
+/// write!(writer, "hello, {}", "world")?;
+/// writer.flush()?;
+///
+/// let receipts = writer.finish();
+/// assert_eq!(receipts.iter().map(|r| r.len()).sum::<u64>(), 12);
+/// # Ok(())
+/// # }
+/// ```
+pub struct SeqWriter {
+    /// Handle to the mapping
+    ///
+    /// 映射的句柄
+    file: MmapFileInner,
+
+    /// Start of the region owned by the writer
+    ///
+    /// 写入器拥有区域的起始位置
+    start: u64,
+
+    /// Next write position
+    ///
+    /// 下一个写入位置
+    pos: u64,
+
+    /// End of the region owned by the writer
+    ///
+    /// 写入器拥有区域的结束位置
+    end: u64,
+
+    /// Receipts of the writes so far, in order
+    ///
+    /// 迄今为止各次写入的凭据，按顺序排列
+    receipts: Vec<WriteReceipt>,
+}
+
+impl SeqWriter {
+    /// Create a writer owning `region`
+    ///
+    /// 创建拥有 `region` 的写入器
+    #[inline]
+    pub(crate) fn new(file: MmapFileInner, region: AllocatedRange) -> Self {
+        Self {
+            file,
+            start: region.start(),
+            pos: region.start(),
+            end: region.end(),
+            receipts: Vec::new(),
+        }
+    }
+
+    /// Get the position the next write lands at
+    ///
+    /// 获取下一次写入的位置
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    /// Get the number of bytes still available
+    ///
+    /// 获取仍可写入的字节数
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.end - self.pos
+    }
+
+    /// Consume the writer and get the receipts of all writes, in order
+    ///
+    /// 消耗写入器并按顺序获取所有写入的凭据
+    #[inline]
+    pub fn finish(self) -> Vec<WriteReceipt> {
+        self.receipts
+    }
+}
+
+impl io::Write for SeqWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let len = (buf.len() as u64).min(self.remaining());
+        if len == 0 {
+            return Ok(0);
+        }
+
+        // Safety: [pos, pos + len) lies in the region the writer took over from the allocator
+        // Safety: [pos, pos + len) 位于写入器从分配器接管的区域内
+        unsafe { self.file.write_at(self.pos, &buf[..len as usize]) };
+        self.file.mark_dirty(self.pos, len);
+        self.receipts.push(WriteReceipt::new(AllocatedRange::from_range_unchecked(
+            self.pos,
+            self.pos + len,
+        )));
+        self.pos += len;
+
+        Ok(len as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pos == self.start {
+            return Ok(());
+        }
+
+        // Safety: only this writer writes to its region, and it is not writing right now
+        // Safety: 只有此写入器写入其区域，且此刻没有在写入
+        unsafe { self.file.sync_range(self.start, (self.pos - self.start) as usize) }
+            .map_err(io::Error::from)
+    }
+}
@@ -0,0 +1,185 @@
+//! SIGBUS guard for writes to a mapping whose backing storage is full
+//!
+//! 针对后备存储已满的映射写入的 SIGBUS 保护
+//!
+//! When the filesystem cannot allocate a block for a page dirtied through the mapping, the
+//! kernel raises SIGBUS in the writing thread. Unwinding out of a signal handler is not
+//! possible in Rust, so instead the handler replaces the faulting page with an anonymous
+//! page and returns: the interrupted copy then completes harmlessly, and [`guarded`] maps
+//! the file back over the affected pages and reports the failure.
+//!
+//! 当文件系统无法为通过映射写脏的页面分配块时，内核会向写入线程发送 SIGBUS。
+//! Rust 无法从信号处理函数中展开栈，因此处理函数会将出错的页面替换为匿名页面后返回：
+//! 被中断的拷贝随后无害地完成，[`guarded`] 再将文件重新映射回受影响的页面并报告失败。
+
+use std::cell::Cell;
+use std::io;
+use std::os::unix::io::RawFd;
+use std::sync::{Once, OnceLock};
+
+/// Mapping currently being written by this thread
+///
+/// 当前线程正在写入的映射
+#[derive(Clone, Copy)]
+struct Guard {
+    base: usize,
+    len: usize,
+    page_size: usize,
+    /// Lowest and highest faulted page, if any
+    ///
+    /// 出错的最低和最高页面（如有）
+    faulted: Option<(usize, usize)>,
+}
+
+thread_local! {
+    static GUARD: Cell<Option<Guard>> = const { Cell::new(None) };
+}
+
+static INSTALL: Once = Once::new();
+static PREVIOUS: OnceLock<libc::sigaction> = OnceLock::new();
+
+/// Install the process-wide SIGBUS handler, chaining to any previous one
+///
+/// 安装进程级 SIGBUS 处理函数，并链式调用之前的处理函数
+fn install() {
+    INSTALL.call_once(|| unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle as *const () as libc::sighandler_t;
+        action.sa_flags = libc::SA_SIGINFO | libc::SA_NODEFER;
+        libc::sigemptyset(&mut action.sa_mask);
+
+        let mut previous: libc::sigaction = std::mem::zeroed();
+        if libc::sigaction(libc::SIGBUS, &action, &mut previous) == 0 {
+            let _ = PREVIOUS.set(previous);
+        }
+    });
+}
+
+extern "C" fn handle(sig: libc::c_int, info: *mut libc::siginfo_t, ctx: *mut libc::c_void) {
+    let addr = unsafe { (*info).si_addr() } as usize;
+
+    let handled = GUARD
+        .try_with(|cell| {
+            let Some(mut guard) = cell.get() else {
+                return false;
+            };
+            if addr < guard.base || addr >= guard.base + guard.len {
+                return false;
+            }
+
+            // Swap in a private anonymous page so the interrupted store can complete
+            // 换入私有匿名页面，使被中断的写入能够完成
+            let page = addr & !(guard.page_size - 1);
+            let mapped = unsafe {
+                libc::mmap(
+                    page as *mut libc::c_void,
+                    guard.page_size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_FIXED,
+                    -1,
+                    0,
+                )
+            };
+            if mapped == libc::MAP_FAILED {
+                return false;
+            }
+
+            guard.faulted = Some(match guard.faulted {
+                Some((lo, hi)) => (lo.min(page), hi.max(page)),
+                None => (page, page),
+            });
+            cell.set(Some(guard));
+            true
+        })
+        .unwrap_or(false);
+
+    if handled {
+        return;
+    }
+
+    // Not ours: defer to the previous handler, or restore the default so the fault is fatal
+    // 不属于我们：交给之前的处理函数，或恢复默认行为使该错误按原样终止进程
+    unsafe {
+        match PREVIOUS.get() {
+            Some(previous)
+                if previous.sa_sigaction != libc::SIG_DFL
+                    && previous.sa_sigaction != libc::SIG_IGN =>
+            {
+                if previous.sa_flags & libc::SA_SIGINFO != 0 {
+                    let f: extern "C" fn(libc::c_int, *mut libc::siginfo_t, *mut libc::c_void) =
+                        std::mem::transmute(previous.sa_sigaction);
+                    f(sig, info, ctx);
+                } else {
+                    let f: extern "C" fn(libc::c_int) = std::mem::transmute(previous.sa_sigaction);
+                    f(sig);
+                }
+            }
+            _ => {
+                libc::signal(libc::SIGBUS, libc::SIG_DFL);
+            }
+        }
+    }
+}
+
+/// Run `f`, which writes into the shared file mapping `[base, base + len)` of `fd`
+///
+/// 运行 `f`，它会写入 `fd` 的共享文件映射 `[base, base + len)`
+///
+/// Returns `Ok(true)` if a SIGBUS was raised inside the mapping while `f` ran. In that
+/// case the data written to the faulting pages is lost, and the file is mapped back over
+/// them before returning.
+///
+/// 如果 `f` 运行期间在映射内触发了 SIGBUS，返回 `Ok(true)`。
+/// 此时写入出错页面的数据会丢失，返回前文件会被重新映射回这些页面。
+///
+/// # Safety
+///
+/// `base` must be the start of a live `MAP_SHARED` read-write mapping of `fd` at file
+/// offset 0, at least `len` bytes long, and no other thread may access the faulting pages
+/// until this function returns.
+///
+/// # Safety
+///
+/// `base` 必须是 `fd` 从文件偏移 0 开始、至少 `len` 字节、有效的 `MAP_SHARED` 读写映射的起始地址，
+/// 并且在本函数返回之前，其他线程不得访问出错的页面。
+pub(crate) unsafe fn guarded(
+    base: *mut u8,
+    len: usize,
+    fd: RawFd,
+    f: impl FnOnce(),
+) -> io::Result<bool> {
+    install();
+
+    let page_size = super::sys::page_size();
+    GUARD.with(|cell| {
+        cell.set(Some(Guard {
+            base: base as usize,
+            len,
+            page_size,
+            faulted: None,
+        }))
+    });
+    f();
+    let guard = GUARD.with(|cell| cell.take());
+
+    let Some((lo, hi)) = guard.and_then(|guard| guard.faulted) else {
+        return Ok(false);
+    };
+
+    let offset = lo - base as usize;
+    let remapped = unsafe {
+        libc::mmap(
+            lo as *mut libc::c_void,
+            hi - lo + page_size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_FIXED,
+            fd,
+            offset as libc::off_t,
+        )
+    };
+    if remapped == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,467 @@
+//! Platform-specific helpers for operations memmap2 does not expose
+//!
+//! memmap2 未提供的平台相关操作
+
+use super::protection::Protection;
+use std::fs::File;
+use std::io;
+
+/// Change the protection of `[ptr, ptr + len)`
+///
+/// 修改 `[ptr, ptr + len)` 的内存保护
+///
+/// # Safety
+///
+/// `ptr` must be the page-aligned start of a live mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须是页对齐的、至少 `len` 字节的有效映射起始地址。
+#[cfg(unix)]
+pub(crate) unsafe fn protect(ptr: *mut u8, len: usize, prot: Protection) -> io::Result<()> {
+    let flags = match prot {
+        Protection::ReadOnly => libc::PROT_READ,
+        Protection::ReadWrite => libc::PROT_READ | libc::PROT_WRITE,
+        Protection::ReadExecute => libc::PROT_READ | libc::PROT_EXEC,
+    };
+
+    match unsafe { libc::mprotect(ptr.cast(), len, flags) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Change the protection of `[ptr, ptr + len)`
+///
+/// 修改 `[ptr, ptr + len)` 的内存保护
+///
+/// # Safety
+///
+/// `ptr` must be the page-aligned start of a live mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须是页对齐的、至少 `len` 字节的有效映射起始地址。
+#[cfg(windows)]
+pub(crate) unsafe fn protect(ptr: *mut u8, len: usize, prot: Protection) -> io::Result<()> {
+    use windows_sys::Win32::System::Memory::{
+        VirtualProtect, PAGE_EXECUTE_READ, PAGE_READONLY, PAGE_READWRITE,
+    };
+
+    let flags = match prot {
+        Protection::ReadOnly => PAGE_READONLY,
+        Protection::ReadWrite => PAGE_READWRITE,
+        Protection::ReadExecute => PAGE_EXECUTE_READ,
+    };
+
+    let mut old = 0;
+    match unsafe { VirtualProtect(ptr.cast(), len, flags, &mut old) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Lock `[ptr, ptr + len)` into physical memory
+///
+/// 将 `[ptr, ptr + len)` 锁定在物理内存中
+///
+/// # Safety
+///
+/// `ptr` must point to a live mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须指向至少 `len` 字节的有效映射。
+#[cfg(unix)]
+pub(crate) unsafe fn lock(ptr: *const u8, len: usize) -> io::Result<()> {
+    match unsafe { libc::mlock(ptr.cast(), len) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Lock `[ptr, ptr + len)` into physical memory
+///
+/// 将 `[ptr, ptr + len)` 锁定在物理内存中
+///
+/// # Safety
+///
+/// `ptr` must point to a live mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须指向至少 `len` 字节的有效映射。
+#[cfg(windows)]
+pub(crate) unsafe fn lock(ptr: *const u8, len: usize) -> io::Result<()> {
+    use windows_sys::Win32::System::Memory::VirtualLock;
+
+    match unsafe { VirtualLock(ptr.cast(), len) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Drop the pages of `[ptr, ptr + len)` from this process
+///
+/// 从本进程中释放 `[ptr, ptr + len)` 的页面
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live shared file mapping of at least `len`
+/// bytes, whose contents were written back to the file.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效共享文件映射，且其内容已写回文件。
+#[cfg(unix)]
+pub(crate) unsafe fn release(ptr: *mut u8, len: usize) -> io::Result<()> {
+    // Locked pages reject MADV_DONTNEED, so unlock them first as VirtualUnlock does on Windows
+    // 锁定的页面会拒绝 MADV_DONTNEED，因此先解锁，与 Windows 上的 VirtualUnlock 一致
+    if unsafe { libc::munlock(ptr.cast(), len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    match unsafe { libc::madvise(ptr.cast(), len, libc::MADV_DONTNEED) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Drop the pages of `[ptr, ptr + len)` from this process
+///
+/// 从本进程中释放 `[ptr, ptr + len)` 的页面
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live shared file mapping of at least `len`
+/// bytes, whose contents were written back to the file.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效共享文件映射，且其内容已写回文件。
+#[cfg(windows)]
+pub(crate) unsafe fn release(ptr: *mut u8, len: usize) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::{GetLastError, ERROR_NOT_LOCKED};
+    use windows_sys::Win32::System::Memory::VirtualUnlock;
+
+    // Unlocking pages that are not locked removes them from the working set
+    // 解锁未锁定的页面会将其从工作集中移除
+    match unsafe { VirtualUnlock(ptr.cast(), len) } {
+        0 if unsafe { GetLastError() } != ERROR_NOT_LOCKED => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Ask the kernel to read `[ptr, ptr + len)` ahead of access
+///
+/// 请求内核在访问之前预读 `[ptr, ptr + len)`
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live file mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效文件映射。
+#[cfg(unix)]
+pub(crate) unsafe fn will_need(ptr: *const u8, len: usize) -> io::Result<()> {
+    match unsafe { libc::madvise(ptr as *mut libc::c_void, len, libc::MADV_WILLNEED) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Ask the kernel to read `[ptr, ptr + len)` ahead of access
+///
+/// 请求内核在访问之前预读 `[ptr, ptr + len)`
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live file mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效文件映射。
+#[cfg(windows)]
+pub(crate) unsafe fn will_need(ptr: *const u8, len: usize) -> io::Result<()> {
+    use windows_sys::Win32::System::Memory::{PrefetchVirtualMemory, WIN32_MEMORY_RANGE_ENTRY};
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    let entry = WIN32_MEMORY_RANGE_ENTRY {
+        VirtualAddress: ptr as *mut _,
+        NumberOfBytes: len,
+    };
+    match unsafe { PrefetchVirtualMemory(GetCurrentProcess(), 1, &entry, 0) } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Discard cached copies of `[ptr, ptr + len)` so later reads see the file contents
+///
+/// 丢弃 `[ptr, ptr + len)` 的缓存副本，使之后的读取能看到文件内容
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live file mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效文件映射。
+#[cfg(unix)]
+pub(crate) unsafe fn invalidate(ptr: *const u8, len: usize) -> io::Result<()> {
+    match unsafe { libc::msync(ptr as *mut libc::c_void, len, libc::MS_INVALIDATE) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Discard cached copies of `[ptr, ptr + len)` so later reads see the file contents
+///
+/// 丢弃 `[ptr, ptr + len)` 的缓存副本，使之后的读取能看到文件内容
+///
+/// Views of the same file mapping are always coherent on Windows, so there is nothing to do.
+///
+/// Windows 上同一文件映射的视图始终是一致的，因此无需任何操作。
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point to a live file mapping of at least `len` bytes.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向至少 `len` 字节的有效文件映射。
+#[cfg(windows)]
+pub(crate) unsafe fn invalidate(_ptr: *const u8, _len: usize) -> io::Result<()> {
+    Ok(())
+}
+
+/// Get the OS page size in bytes
+///
+/// 获取操作系统页面大小（字节）
+#[cfg(unix)]
+pub(crate) fn page_size() -> usize {
+    unsafe { libc::sysconf(libc::_SC_PAGESIZE) as usize }
+}
+
+/// Get the OS page size in bytes
+///
+/// 获取操作系统页面大小（字节）
+#[cfg(windows)]
+pub(crate) fn page_size() -> usize {
+    use windows_sys::Win32::System::SystemInformation::{GetSystemInfo, SYSTEM_INFO};
+
+    let mut info: SYSTEM_INFO = unsafe { std::mem::zeroed() };
+    unsafe { GetSystemInfo(&mut info) };
+    info.dwPageSize as usize
+}
+
+/// Query which pages of `[ptr, ptr + pages * page_size())` are resident
+///
+/// 查询 `[ptr, ptr + pages * page_size())` 中哪些页面驻留在内存中
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point into a live mapping covering all `pages` pages.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向覆盖全部 `pages` 个页面的有效映射。
+#[cfg(unix)]
+pub(crate) unsafe fn residency(ptr: *const u8, pages: usize) -> io::Result<Vec<bool>> {
+    let mut vec = vec![0u8; pages];
+    let len = pages * page_size();
+
+    match unsafe { libc::mincore(ptr as *mut _, len, vec.as_mut_ptr().cast()) } {
+        0 => Ok(vec.into_iter().map(|v| v & 1 == 1).collect()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+/// Query which pages of `[ptr, ptr + pages * page_size())` are resident
+///
+/// 查询 `[ptr, ptr + pages * page_size())` 中哪些页面驻留在内存中
+///
+/// # Safety
+///
+/// `ptr` must be page-aligned and point into a live mapping covering all `pages` pages.
+///
+/// # Safety
+///
+/// `ptr` 必须页对齐，并指向覆盖全部 `pages` 个页面的有效映射。
+#[cfg(windows)]
+pub(crate) unsafe fn residency(ptr: *const u8, pages: usize) -> io::Result<Vec<bool>> {
+    use windows_sys::Win32::System::ProcessStatus::{
+        K32QueryWorkingSetEx, PSAPI_WORKING_SET_EX_INFORMATION,
+    };
+    use windows_sys::Win32::System::Threading::GetCurrentProcess;
+
+    let page_size = page_size();
+    let mut infos: Vec<PSAPI_WORKING_SET_EX_INFORMATION> = (0..pages)
+        .map(|i| PSAPI_WORKING_SET_EX_INFORMATION {
+            VirtualAddress: ptr.wrapping_add(i * page_size) as *mut _,
+            ..Default::default()
+        })
+        .collect();
+
+    // The buffer size is a DWORD; refuse instead of silently truncating it for huge mappings
+    // 缓冲区大小为 DWORD；对于超大映射直接拒绝，而不是静默截断
+    let cb = u32::try_from(pages * std::mem::size_of::<PSAPI_WORKING_SET_EX_INFORMATION>())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many pages to query at once"))?;
+    match unsafe { K32QueryWorkingSetEx(GetCurrentProcess(), infos.as_mut_ptr().cast(), cb) } {
+        // Bit 0 of the flags is `Valid`: the page is in the working set
+        // 标志位第 0 位为 `Valid`：页面位于工作集中
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(infos
+            .iter()
+            .map(|info| unsafe { info.VirtualAttributes.Flags } & 1 == 1)
+            .collect()),
+    }
+}
+
+/// Reserve disk blocks for the first `len` bytes of `file`
+///
+/// 为 `file` 的前 `len` 字节预留磁盘块
+#[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+pub(crate) fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    // posix_fallocate returns the error number instead of setting errno
+    // posix_fallocate 直接返回错误码，而不是设置 errno
+    match unsafe { libc::posix_fallocate(file.as_raw_fd(), 0, len as libc::off_t) } {
+        0 => Ok(()),
+        err => Err(io::Error::from_raw_os_error(err)),
+    }
+}
+
+/// Reserve disk blocks for the first `len` bytes of `file`
+///
+/// 为 `file` 的前 `len` 字节预留磁盘块
+#[cfg(any(target_os = "macos", target_os = "ios"))]
+pub(crate) fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut store = libc::fstore_t {
+        fst_flags: libc::F_ALLOCATECONTIG,
+        fst_posmode: libc::F_PEOFPOSMODE,
+        fst_offset: 0,
+        fst_length: len as libc::off_t,
+        fst_bytesalloc: 0,
+    };
+
+    // Fall back to a non-contiguous allocation if no contiguous space is available
+    // 如果没有连续空间，则退回到非连续分配
+    if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store as *const libc::fstore_t) } == -1 {
+        store.fst_flags = libc::F_ALLOCATEALL;
+        if unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store as *const libc::fstore_t) } == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+
+    Ok(())
+}
+
+/// Reserve disk blocks for the first `len` bytes of `file`
+///
+/// 为 `file` 的前 `len` 字节预留磁盘块
+#[cfg(all(
+    unix,
+    not(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "freebsd",
+        target_os = "macos",
+        target_os = "ios"
+    ))
+))]
+pub(crate) fn preallocate(_file: &File, _len: u64) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "preallocation is not supported on this platform / 此平台不支持预分配",
+    ))
+}
+
+/// Reserve disk blocks for the first `len` bytes of `file`
+///
+/// 为 `file` 的前 `len` 字节预留磁盘块
+#[cfg(windows)]
+pub(crate) fn preallocate(file: &File, len: u64) -> io::Result<()> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FileAllocationInfo, SetFileInformationByHandle, FILE_ALLOCATION_INFO,
+    };
+
+    let info = FILE_ALLOCATION_INFO {
+        AllocationSize: len as i64,
+    };
+
+    match unsafe {
+        SetFileInformationByHandle(
+            file.as_raw_handle(),
+            FileAllocationInfo,
+            (&info as *const FILE_ALLOCATION_INFO).cast(),
+            std::mem::size_of::<FILE_ALLOCATION_INFO>() as u32,
+        )
+    } {
+        0 => Err(io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+/// Copy the first `len` bytes of `src` into `dst` inside the kernel
+///
+/// 在内核中将 `src` 的前 `len` 字节复制到 `dst`
+///
+/// Returns `Ok(false)` if the filesystems don't support an in-kernel copy, in which case
+/// the caller should fall back to copying through memory.
+///
+/// 如果文件系统不支持内核内复制，返回 `Ok(false)`，此时调用者应退回到通过内存复制。
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub(crate) fn copy_file(src: &File, dst: &File, len: u64) -> io::Result<bool> {
+    use std::os::unix::io::AsRawFd;
+
+    let mut src_off: libc::loff_t = 0;
+    let mut dst_off: libc::loff_t = 0;
+    let mut remaining = len;
+
+    while remaining > 0 {
+        let chunk = remaining.min(1 << 30) as usize;
+        let copied = unsafe {
+            libc::copy_file_range(
+                src.as_raw_fd(),
+                &mut src_off,
+                dst.as_raw_fd(),
+                &mut dst_off,
+                chunk,
+                0,
+            )
+        };
+
+        match copied {
+            // Source shorter than expected: let the caller copy the rest from memory
+            // 源文件比预期短：由调用者从内存中复制剩余部分
+            0 => return Ok(false),
+            n if n > 0 => remaining -= n as u64,
+            _ => {
+                let err = io::Error::last_os_error();
+                return match err.raw_os_error() {
+                    Some(libc::EXDEV | libc::ENOSYS | libc::EOPNOTSUPP | libc::EINVAL) => Ok(false),
+                    _ => Err(err),
+                };
+            }
+        }
+    }
+
+    Ok(true)
+}
+
+/// Copy the first `len` bytes of `src` into `dst` inside the kernel
+///
+/// 在内核中将 `src` 的前 `len` 字节复制到 `dst`
+///
+/// Not available on this platform; always returns `Ok(false)`.
+///
+/// 此平台不可用；总是返回 `Ok(false)`。
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+pub(crate) fn copy_file(_src: &File, _dst: &File, _len: u64) -> io::Result<bool> {
+    Ok(false)
+}
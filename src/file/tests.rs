@@ -1,5 +1,8 @@
 //! 测试模块
 
+// 旧测试以 `&vec![..]` 传参，写入接口改为 `AsRef<[u8]>` 后保持原样
+#![allow(clippy::needless_borrows_for_generic_args)]
+
 use super::*;
 use tempfile::tempdir;
 
@@ -296,6 +299,88 @@ mod mmap_file_inner_tests {
         }
     }
 
+    #[test]
+    fn test_read_slice_length_too_large() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_read_slice_huge.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(100).unwrap()).unwrap();
+
+        // 远大于文件的长度在分配之前就被拒绝
+        match unsafe { file.read_slice(0, usize::MAX) } {
+            Err(Error::ReadLengthTooLarge { len, size }) => {
+                assert_eq!(len, usize::MAX);
+                assert_eq!(size, 100);
+            }
+            other => panic!("expected ReadLengthTooLarge, got {:?}", other),
+        }
+
+        // 超出文件末尾但不超过文件大小的长度会被截断
+        let slice = unsafe { file.read_slice(90, 50) }.unwrap();
+        assert_eq!(slice.len(), 10);
+        assert!(unsafe { file.read_slice(200, 50) }.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_raw_mmap_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_raw.bin");
+
+        let file = RawMmapFile::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        assert_eq!(file.size().get(), 4096);
+
+        // 通过指针从另一个克隆写入，并从原句柄读取
+        let writer = file.clone();
+        std::thread::spawn(move || unsafe {
+            std::ptr::copy_nonoverlapping(b"raw".as_ptr(), writer.as_mut_ptr().add(100), 3);
+        })
+        .join()
+        .unwrap();
+
+        let mut buf = [0u8; 3];
+        unsafe { std::ptr::copy_nonoverlapping(file.as_ptr().add(100), buf.as_mut_ptr(), 3) };
+        assert_eq!(&buf, b"raw");
+
+        file.flush_range(100, 3).unwrap();
+        assert!(matches!(file.flush_range(4000, 100), Err(Error::OutOfBounds { .. })));
+        drop(file);
+
+        // 重新打开后数据仍在
+        let reopened = RawMmapFile::open(&path).unwrap();
+        assert_eq!(unsafe { reopened.as_ptr().add(100).read() }, b'r');
+        assert_eq!(&std::fs::read(&path).unwrap()[100..103], b"raw");
+    }
+
+    #[test]
+    fn test_check_size_limit() {
+        // 用较小的上限模拟 32 位平台
+        let max = u32::MAX as usize;
+        assert!(mmap_file_inner::check_size_limit(max as u64, max).is_ok());
+
+        match mmap_file_inner::check_size_limit(max as u64 + 1, max) {
+            Err(Error::FileTooLargeForPlatform { size, max: limit }) => {
+                assert_eq!(size, max as u64 + 1);
+                assert_eq!(limit, max);
+            }
+            other => panic!("expected FileTooLargeForPlatform, got {:?}", other),
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "32")]
+    fn test_create_too_large_for_platform() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_too_large.bin");
+
+        // 超出地址空间的文件在创建之前就被拒绝
+        let size = NonZeroU64::new(usize::MAX as u64 + 1).unwrap();
+        assert!(matches!(
+            MmapFileInner::create(&path, size),
+            Err(Error::FileTooLargeForPlatform { .. })
+        ));
+        assert!(!path.exists());
+    }
+
     #[test]
     fn test_flush_range() {
         let dir = tempdir().unwrap();
@@ -381,278 +466,1937 @@ mod mmap_file_inner_tests {
             assert_eq!(&buf, b"hello");
         }
     }
-}
-
-/// MmapFile 测试（Safe 版本）
-#[cfg(test)]
-mod mmap_file_tests {
-    use super::*;
-    use crate::allocator::{self, RangeAllocator, ALIGNMENT};
-    use std::num::NonZeroU64;
 
+    #[cfg(unix)]
     #[test]
-    fn test_create_with_allocator() {
+    fn test_set_protection_read_only_faults_on_write() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_create.bin");
+        let path = dir.path().join("inner_protection.bin");
 
-        let (file, allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 10).unwrap()).unwrap();
-        assert_eq!(file.size(), NonZeroU64::new(ALIGNMENT * 10).unwrap());
-        assert_eq!(allocator.total_size(), NonZeroU64::new(ALIGNMENT * 10).unwrap());
-        assert_eq!(allocator.next_pos(), 0);
-    }
+        let file = MmapFileInner::create_with_prot(&path, NonZeroU64::new(4096).unwrap(), Protection::ReadWrite).unwrap();
 
-    #[test]
-    fn test_open_existing_file() {
-        let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_open.bin");
+        unsafe {
+            file.write_all_at(0, &[0x42]);
+            file.set_protection(Protection::ReadOnly).unwrap();
+        }
 
-        // 先创建 (4K aligned)
-        let (file1, mut allocator1) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let range = allocator1.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let mut data = vec![0u8; ALIGNMENT as usize];
-        data[..10].copy_from_slice(b"testdata!!");
-        file1.write_range(range, &data);
-        unsafe { file1.sync_all().unwrap(); }
-        drop(file1);
-        drop(allocator1);
+        // 只读后仍然可以读取
+        let mut buf = [0u8; 1];
+        unsafe { file.read_at(0, &mut buf).unwrap(); }
+        assert_eq!(buf, [0x42]);
 
-        // 重新打开
-        let (file2, mut allocator2) = MmapFile::open_default(&path).unwrap();
-        assert_eq!(file2.size(), NonZeroU64::new(ALIGNMENT).unwrap());
-        assert_eq!(allocator2.total_size(), NonZeroU64::new(ALIGNMENT).unwrap());
+        // 在子进程中写入，确认会触发内存错误
+        let ptr = unsafe { file.as_mut_ptr() };
+        let pid = unsafe { libc::fork() };
+        assert!(pid >= 0);
+        if pid == 0 {
+            unsafe {
+                std::ptr::write_volatile(ptr, 0x43);
+                libc::_exit(0);
+            }
+        }
 
-        let range = allocator2.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let mut buf = vec![0u8; ALIGNMENT as usize];
-        file2.read_range(range, &mut buf).unwrap();
-        assert_eq!(&buf[..10], b"testdata!!");
+        let mut status = 0;
+        unsafe { libc::waitpid(pid, &mut status, 0); }
+        assert!(libc::WIFSIGNALED(status));
+        let signal = libc::WTERMSIG(status);
+        assert!(signal == libc::SIGSEGV || signal == libc::SIGBUS);
+
+        // 恢复可写后可以再次写入
+        unsafe {
+            file.set_protection(Protection::ReadWrite).unwrap();
+            file.write_all_at(0, &[0x43]);
+            file.read_at(0, &mut buf).unwrap();
+        }
+        assert_eq!(buf, [0x43]);
     }
 
     #[test]
-    fn test_basic_write_and_read() {
+    fn test_fill_rejects_read_only_mapping() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_basic.bin");
+        let path = dir.path().join("inner_fill_read_only.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let file = MmapFileInner::create_with_prot(&path, NonZeroU64::new(4096).unwrap(), Protection::ReadOnly).unwrap();
 
-        // 分配并写入 (4K aligned)
-        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        // 只读映射上的 fill / zero 返回错误而不是触发内存错误
+        unsafe {
+            assert!(matches!(file.fill(0xFF), Err(Error::ReadOnlyMapping)));
+            assert!(matches!(file.zero(), Err(Error::ReadOnlyMapping)));
 
-        let data1 = vec![1u8; ALIGNMENT as usize];
-        let data2 = vec![2u8; ALIGNMENT as usize];
+            // 切换为可写再切回只读后同样拒绝
+            file.set_protection(Protection::ReadWrite).unwrap();
+            file.set_protection(Protection::ReadOnly).unwrap();
+            assert!(matches!(file.fill(0xFF), Err(Error::ReadOnlyMapping)));
 
-        let receipt1 = file.write_range(range1, &data1);
-        let receipt2 = file.write_range(range2, &data2);
+            // 恢复可写后 fill 成功
+            file.set_protection(Protection::ReadWrite).unwrap();
+            file.fill(0xFF).unwrap();
+        }
 
-        assert_eq!(receipt1.len(), ALIGNMENT);
-        assert_eq!(receipt2.len(), ALIGNMENT);
+        let mut buf = vec![0u8; 4096];
+        unsafe { file.read_at(0, &mut buf).unwrap(); }
+        assert!(buf.iter().all(|&b| b == 0xFF));
+    }
 
-        unsafe { file.sync_all().unwrap(); }
+    #[test]
+    fn test_high_watermark_out_of_order() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_watermark.bin");
 
-        // 验证读取
-        let mut buf1 = vec![0u8; ALIGNMENT as usize];
-        let mut buf2 = vec![0u8; ALIGNMENT as usize];
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1_000_000).unwrap()).unwrap();
+        assert_eq!(file.high_watermark(), 0);
 
-        file.read_range(range1, &mut buf1).unwrap();
-        file.read_range(range2, &mut buf2).unwrap();
+        // 先写高偏移，再写低偏移，水位线应为较大的结束位置
+        unsafe {
+            file.write_all_at(900_000, b"tail");
+            file.write_all_at(0, b"head");
+        }
+        assert_eq!(file.high_watermark(), 900_004);
 
-        assert_eq!(buf1, data1);
-        assert_eq!(buf2, data2);
+        // 克隆共享水位线
+        let clone = file.clone();
+        unsafe { clone.write_all_at(950_000, b"x"); }
+        assert_eq!(file.high_watermark(), 950_001);
+
+        unsafe { file.sync_to_watermark().unwrap(); }
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_write_range_all() {
+    fn test_create_eager_resident() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_write_all.bin");
+        let path = dir.path().join("inner_eager.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let size = 16 * 1024;
+        let file = MmapFileInner::create_eager(&path, NonZeroU64::new(size).unwrap()).unwrap();
 
-        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let data = vec![0u8; ALIGNMENT as usize];
-        let receipt = file.write_range_all(range, &data);
+        unsafe {
+            file.write_all_at(0, &vec![0x5A; size as usize]);
+        }
 
-        assert_eq!(receipt.len(), ALIGNMENT);
-        assert_eq!(receipt.start(), 0);
-        assert_eq!(receipt.end(), ALIGNMENT);
+        // 锁定后的所有页面都应驻留在内存中
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let pages = (size as usize).div_ceil(page_size);
+        let mut residency = vec![0u8; pages];
+        let ret = unsafe { libc::mincore(file.as_ptr() as *mut _, size as usize, residency.as_mut_ptr().cast()) };
+        assert_eq!(ret, 0);
+        assert!(residency.iter().all(|&r| r & 1 == 1));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_concurrent_writes_with_allocated_ranges() {
+    fn test_resident_pages() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_concurrent.bin");
+        let path = dir.path().join("inner_resident.bin");
 
-        // 10 * 4K = 40K
-        let num_ranges = 10;
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * num_ranges).unwrap()).unwrap();
+        let size = 16 * 1024 * 1024;
+        let file = MmapFileInner::create(&path, NonZeroU64::new(size).unwrap()).unwrap();
 
-        // 预先分配所有范围
-        let mut ranges = Vec::new();
-        for _ in 0..num_ranges {
-            ranges.push(allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap());
-        }
+        // 触碰第一页
+        unsafe { file.write_all_at(0, b"touched"); }
 
-        // 并发写入（编译期安全！）
-        std::thread::scope(|s| {
-            for (i, range) in ranges.into_iter().enumerate() {
-                let f = file.clone();
-                s.spawn(move || {
-                    let data = vec![i as u8; ALIGNMENT as usize];
-                    let _receipt = f.write_range(range, &data);
-                });
-            }
-        });
+        let resident = file.resident_pages(0, 1).unwrap();
+        assert_eq!(resident, vec![true]);
 
-        unsafe { file.sync_all().unwrap(); }
+        // 远处未触碰的页面（受预读影响，尽力而为）
+        let far = file.resident_pages(size - 1, 1).unwrap();
+        assert_eq!(far, vec![false]);
 
-        // 验证
-        let mut allocator2 = allocator::sequential::Allocator::new(NonZeroU64::new(ALIGNMENT * num_ranges).unwrap());
-        for i in 0..num_ranges as usize {
-            let range = allocator2.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-            let mut buf = vec![0u8; ALIGNMENT as usize];
-            file.read_range(range, &mut buf).unwrap();
-            assert_eq!(buf, vec![i as u8; ALIGNMENT as usize]);
-        }
+        // 跨页区域每页一个结果
+        let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        let pages = file.resident_pages(1, page_size * 2).unwrap();
+        assert_eq!(pages.len(), 3);
+
+        // 越界
+        assert!(matches!(file.resident_pages(size, 1), Err(Error::OutOfBounds { .. })));
     }
 
     #[test]
-    fn test_high_concurrency() {
+    fn test_resize_zero_fills_grown_region() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_high_concurrency.bin");
-
-        // 100个并发写入, 每个4K
-        let num_threads = 100u64;
-        let chunk_size = ALIGNMENT;
-        let file_size = num_threads * chunk_size;
+        let path = dir.path().join("inner_resize.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(file_size).unwrap()).unwrap();
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        unsafe { file.fill(0xFF).unwrap(); }
 
-        // 预先分配所有范围
-        let mut ranges = Vec::new();
-        for _ in 0..num_threads {
-            ranges.push(allocator.allocate(NonZeroU64::new(chunk_size).unwrap()).unwrap());
+        // 先缩小再增长，确保增长区域曾经写过 0xFF
+        unsafe {
+            file.resize(NonZeroU64::new(2048).unwrap()).unwrap();
+            file.resize(NonZeroU64::new(8192).unwrap()).unwrap();
         }
+        assert_eq!(file.size().get(), 8192);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 8192);
 
-        // 并发写入
-        std::thread::scope(|s| {
-            for (i, range) in ranges.into_iter().enumerate() {
-                let f = file.clone();
-                s.spawn(move || {
-                    let data = vec![i as u8; chunk_size as usize];
-                    let _receipt = f.write_range(range, &data);
-                });
-            }
-        });
+        // 保留区域内容不变，增长区域全部为 0
+        let data = unsafe { file.read_slice(0, 8192).unwrap() };
+        assert!(data[..2048].iter().all(|&b| b == 0xFF));
+        assert!(data[2048..].iter().all(|&b| b == 0x00));
 
-        unsafe { file.sync_all().unwrap(); }
+        // 缩小时高水位线被截断
+        assert_eq!(file.high_watermark(), 2048);
+    }
 
-        // 验证
-        let mut allocator2 = allocator::sequential::Allocator::new(NonZeroU64::new(file_size).unwrap());
-        for i in 0..num_threads as usize {
-            let range = allocator2.allocate(NonZeroU64::new(chunk_size).unwrap()).unwrap();
-            let mut buf = vec![0u8; chunk_size as usize];
-            file.read_range(range, &mut buf).unwrap();
-            assert_eq!(buf, vec![i as u8; chunk_size as usize]);
-        }
+    #[test]
+    fn test_failed_resize_keeps_mapping() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_resize_failed.bin");
+        std::fs::write(&path, [1u8; 4096]).unwrap();
+
+        // 写时复制句柄以只读方式打开文件，因此调整大小会失败
+        let file = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe { file.write_byte(10, 0xEE).unwrap(); }
+        assert!(unsafe { file.resize(NonZeroU64::new(8192).unwrap()) }.is_err());
+
+        // 原映射及其私有修改保持不变
+        assert_eq!(file.size().get(), 4096);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 4096);
+        let data = unsafe { file.read_slice(0, 4096).unwrap() };
+        assert_eq!(data[10], 0xEE);
+        assert_eq!(data[11], 1);
     }
 
     #[test]
-    fn test_data_length_match() {
+    fn test_refresh_after_external_grow() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_length_match.bin");
+        let path = dir.path().join("inner_refresh.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let producer = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        let consumer = MmapFileInner::open(&path).unwrap();
 
-        // Allocate 4K
-        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        // 大小未变
+        assert!(!unsafe { consumer.refresh().unwrap() });
 
-        // 正确的长度
-        let receipt = file.write_range(range, &vec![0u8; ALIGNMENT as usize]);
-        assert_eq!(receipt.len(), ALIGNMENT);
+        // 生产者扩展文件，消费者感知并重新映射
+        unsafe { producer.resize(NonZeroU64::new(8192).unwrap()).unwrap(); }
+        assert!(unsafe { consumer.refresh().unwrap() });
+        assert_eq!(consumer.size().get(), 8192);
 
-        // 数据长度不匹配的情况现在用 debug_assert 检查
-        // 在 release 模式下不会 panic，但在 debug 模式下会 panic
+        // 消费者可以写入新区域，生产者可以读到
+        unsafe { consumer.write_all_at(6000, b"grown"); }
+        let mut buf = [0u8; 5];
+        unsafe { producer.read_at(6000, &mut buf).unwrap(); }
+        assert_eq!(&buf, b"grown");
+
+        assert!(!unsafe { consumer.refresh().unwrap() });
     }
 
     #[test]
-    fn test_read_range_buffer_too_small() {
+    fn test_open_copy_on_write() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_read_buf.bin");
+        let path = dir.path().join("inner_cow.bin");
+        std::fs::write(&path, [1u8; 4096]).unwrap();
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let file = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe {
+            file.write_byte(10, 0xEE).unwrap();
+            file.flush().unwrap();
+            file.sync_all().unwrap();
 
-        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let data = vec![0x42u8; ALIGNMENT as usize];
-        file.write_range(range, &data);
+            // 映射能看到修改
+            assert_eq!(file.read_byte(10).unwrap(), 0xEE);
+            assert_eq!(file.read_byte(11).unwrap(), 1);
+        }
 
-        // buffer 太小
-        let mut buf = vec![0u8; 100];
-        let result = file.read_range(range, &mut buf);
-        assert!(result.is_err());
+        // 磁盘上的文件保持不变
+        assert!(std::fs::read(&path).unwrap().iter().all(|&b| b == 1));
 
-        // buffer 大小正确
-        let mut buf = vec![0u8; ALIGNMENT as usize];
-        let result = file.read_range(range, &mut buf);
-        assert!(result.is_ok());
-        assert_eq!(buf, data);
+        // 其他映射也看不到私有修改
+        let shared = MmapFileInner::open(&path).unwrap();
+        assert_eq!(unsafe { shared.read_byte(10).unwrap() }, 1);
+    }
 
-        // buffer 更大也可以
-        let mut buf = vec![0u8; ALIGNMENT as usize * 2];
-        let result = file.read_range(range, &mut buf);
-        assert!(result.is_ok());
-        assert_eq!(&buf[..ALIGNMENT as usize], &data[..]);
+    #[test]
+    fn test_copy_on_write_copy_to_and_persist() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_cow_copy.bin");
+        std::fs::write(&path, [1u8; 4096]).unwrap();
+
+        let file = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe { file.write_byte(10, 0xEE).unwrap() };
+
+        // 复制保留私有写入，而不是复制未修改的底层文件
+        let copy_path = dir.path().join("inner_cow_copy_dest.bin");
+        let copy = unsafe { file.copy_to(&copy_path).unwrap() };
+        assert_eq!(unsafe { copy.read_byte(10).unwrap() }, 0xEE);
+        unsafe { copy.sync_all().unwrap() };
+        drop(copy);
+        assert_eq!(std::fs::read(&copy_path).unwrap()[10], 0xEE);
+
+        // persist 被拒绝，底层文件保持原位且不变
+        let final_path = dir.path().join("inner_cow_final.bin");
+        assert!(matches!(file.persist(&final_path), Err(Error::CopyOnWriteMapping)));
+        assert!(!final_path.exists());
+        assert!(std::fs::read(&path).unwrap().iter().all(|&b| b == 1));
     }
 
     #[test]
-    fn test_flush_operations() {
+    fn test_write_at_exclusive() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_flush.bin");
+        let path = dir.path().join("inner_exclusive.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let mut file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        assert_eq!(file.write_at_exclusive(10, b"only").unwrap(), 4);
+        assert_eq!(unsafe { file.read_slice(10, 4).unwrap() }, b"only");
 
-        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        // 存在克隆时拒绝写入
+        let clone = file.clone();
+        assert!(matches!(file.write_at_exclusive(0, b"x"), Err(Error::MappingInUse { clones: 1 })));
 
-        let receipt1 = file.write_range(range1, &vec![1u8; ALIGNMENT as usize]);
-        let receipt2 = file.write_range(range2, &vec![2u8; ALIGNMENT as usize]);
+        // 克隆丢弃后恢复可写
+        drop(clone);
+        assert_eq!(file.write_at_exclusive(0, b"x").unwrap(), 1);
 
-        // 测试异步刷新
-        file.flush().unwrap();
+        // 越界写入仍然报错
+        assert!(matches!(file.write_at_exclusive(4095, b"ab"), Err(Error::OutOfBounds { .. })));
+    }
 
-        // 刷新特定范围
-        file.flush_range(receipt1).unwrap();
-        file.flush_range(receipt2).unwrap();
+    #[test]
+    fn test_byte_histogram() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_histogram.bin");
 
-        // 全局同步
-        unsafe { file.sync_all().unwrap(); }
+        // 跨越多个 64K 块
+        let size = 200 * 1024;
+        let file = MmapFileInner::create(&path, NonZeroU64::new(size).unwrap()).unwrap();
+        unsafe {
+            assert_eq!(file.zero_ratio(), 1.0);
+
+            file.fill(0xAB).unwrap();
+            let histogram = file.byte_histogram();
+            assert_eq!(histogram[0xAB], size);
+            assert_eq!(histogram.iter().sum::<u64>(), size);
+            assert_eq!(file.zero_ratio(), 0.0);
+
+            // 一半清零
+            file.write_all_at(0, &vec![0u8; size as usize / 2]);
+            assert_eq!(file.byte_histogram()[0], size / 2);
+            assert_eq!(file.zero_ratio(), 0.5);
+        }
     }
 
     #[test]
-    fn test_write_receipt_properties() {
+    fn test_flush_range_counted() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_receipt.bin");
+        let path = dir.path().join("inner_flush_counted.bin");
 
-        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let file = MmapFileInner::create(&path, NonZeroU64::new(64 * 1024).unwrap()).unwrap();
+        let page_size = crate::file::sys::page_size();
+        unsafe {
+            file.write_all_at(100, &[1u8; 4900]);
 
-        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let receipt = file.write_range(range, &vec![0u8; ALIGNMENT as usize]);
+            // [100, 5000) 跨越页面 0 和 1（4K 页面）
+            let pages = file.flush_range_counted(100, 4900).unwrap();
+            assert_eq!(pages, 5000usize.div_ceil(page_size));
+            if page_size == 4096 {
+                assert_eq!(pages, 2);
+            }
 
-        assert_eq!(receipt.start(), 0);
-        assert_eq!(receipt.end(), ALIGNMENT);
-        assert_eq!(receipt.len(), ALIGNMENT);
-        assert!(!receipt.is_empty());
-        assert_eq!(receipt.range(), range);
+            assert_eq!(file.flush_range_counted(0, 0).unwrap(), 0);
+            assert_eq!(file.flush_range_counted(page_size as u64, 1).unwrap(), 1);
+        }
     }
-    
+
     #[test]
-    fn test_clone_and_shared_access() {
+    fn test_hexdump() {
         let dir = tempdir().unwrap();
-        let path = dir.path().join("safe_clone.bin");
+        let path = dir.path().join("inner_hexdump.bin");
 
-        let (file1, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
-        let file2 = file1.clone();
+        let file = MmapFileInner::create(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+        unsafe {
+            file.write_all_at(4096, b"Hello, World!\x00\x01\x7f");
+
+            assert_eq!(
+                file.hexdump(4096, 16).unwrap(),
+                "00001000: 4865 6c6c 6f2c 2057 6f72 6c64 2100 017f  Hello, World!...\n"
+            );
+
+            // 不满一行时补齐十六进制列
+            assert_eq!(
+                file.hexdump(4096, 21).unwrap(),
+                "00001000: 4865 6c6c 6f2c 2057 6f72 6c64 2100 017f  Hello, World!...\n\
+                 00001010: 0000 0000 00                             .....\n"
+            );
+
+            assert_eq!(file.hexdump(0, 0).unwrap(), "");
+            assert!(matches!(file.hexdump(8190, 16), Err(Error::OutOfBounds { .. })));
+        }
+    }
 
-        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+    #[test]
+    fn test_len() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_len.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(12345).unwrap()).unwrap();
+        assert_eq!(file.len(), file.size().get() as usize);
+        assert_eq!(file.len(), 12345);
+
+        // MmapFile 返回相同的值
+        let (file, _) = MmapFile::create_default(dir.path().join("safe_len.bin"), NonZeroU64::new(12345).unwrap()).unwrap();
+        assert_eq!(file.len(), 12345);
+    }
+
+    #[test]
+    fn test_read_only_bytes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_ro_bytes.bin");
+        let data: Vec<u8> = (0..4096u32).map(|i| (i % 256) as u8).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let reader = ReadOnlyMmapFile::open(&path).unwrap();
+        let bytes = unsafe { reader.bytes() };
+        assert_eq!(bytes.len(), 4096);
+        for (i, &byte) in bytes.iter().enumerate() {
+            assert_eq!(byte, (i % 256) as u8);
+        }
+
+        // 可写映射通过 unsafe 的 as_slice 得到相同内容
+        let writer = MmapFileInner::open(&path).unwrap();
+        assert_eq!(unsafe { writer.as_slice() }, bytes);
+    }
+
+    #[test]
+    fn test_open_pair() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_pair.bin");
+        std::fs::write(&path, [0u8; 4096]).unwrap();
+
+        let (reader, writer) = MmapFileInner::open_pair(&path).unwrap();
+        assert_eq!(reader.size(), writer.size());
+
+        unsafe {
+            writer.write_all_at(100, b"update");
+            writer.sync_all().unwrap();
+        }
+        reader.invalidate().unwrap();
+
+        // 读取者能看到写入者的更新
+        let mut buf = [0u8; 6];
+        assert_eq!(unsafe { reader.read_at(100, &mut buf).unwrap() }, 6);
+        assert_eq!(&buf, b"update");
+
+        // 越界读取返回 0
+        assert_eq!(unsafe { reader.read_at(4096, &mut buf).unwrap() }, 0);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[ignore = "maps at a fixed address, which can clobber other mappings"]
+    fn test_create_at_address() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_fixed.bin");
+        let size = 8192;
+
+        // 先用匿名映射预留地址区间，再放弃它，交给 MAP_FIXED 替换
+        let mut reserved = memmap2::MmapMut::map_anon(size).unwrap();
+        let addr = reserved.as_mut_ptr();
+        std::mem::forget(reserved);
+
+        let file = unsafe {
+            MmapFileInner::create_at_address(&path, NonZeroU64::new(size as u64).unwrap(), addr).unwrap()
+        };
+        assert_eq!(file.as_ptr(), addr as *const u8);
+
+        unsafe {
+            file.write_all_at(4000, b"fixed");
+            file.sync_all().unwrap();
+        }
+        assert_eq!(&std::fs::read(&path).unwrap()[4000..4005], b"fixed");
+    }
+
+    #[test]
+    fn test_sync_and_release() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_sync_release.bin");
+
+        let size = 64 * 1024;
+        let file = MmapFileInner::create(&path, NonZeroU64::new(size).unwrap()).unwrap();
+        let data: Vec<u8> = (0..size).map(|i| (i % 251) as u8).collect();
+
+        unsafe {
+            file.write_all_at(0, &data);
+            file.sync_and_release().unwrap();
+
+            // 页面从磁盘重新读入，内容不变
+            assert_eq!(file.read_slice(0, size as usize).unwrap(), data);
+        }
+        assert_eq!(std::fs::read(&path).unwrap(), data);
+    }
+
+    #[test]
+    fn test_sync_and_release_locked_and_copy_on_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_sync_release_locked.bin");
+
+        // 锁定的页面会先被解锁，因此释放成功
+        let file = MmapFileInner::create_eager(&path, NonZeroU64::new(16 * 1024).unwrap()).unwrap();
+        unsafe {
+            file.write_all_at(0, b"locked");
+            file.sync_and_release().unwrap();
+            assert_eq!(file.read_slice(0, 6).unwrap(), b"locked");
+        }
+        drop(file);
+
+        // 释放会丢弃写时复制映射的私有修改，因此被拒绝
+        let cow = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe {
+            cow.write_byte(0, b'L').unwrap();
+            assert!(matches!(cow.sync_and_release(), Err(Error::CopyOnWriteMapping)));
+            assert_eq!(cow.read_byte(0).unwrap(), b'L');
+        }
+    }
+
+    #[test]
+    fn test_read_into_uninit() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_read_uninit.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+        unsafe {
+            file.write_all_at(8000, &[7u8; 192]);
+
+            // 读入 Vec 的未初始化容量
+            let mut buf: Vec<u8> = Vec::with_capacity(100);
+            let read = file.read_into_uninit(8000, buf.spare_capacity_mut()).unwrap().len();
+            buf.set_len(read);
+            assert_eq!(buf, vec![7u8; 100]);
+
+            // 读取到文件末尾时返回较短的前缀
+            let mut tail: Vec<u8> = Vec::with_capacity(1024);
+            let read = file.read_into_uninit(8000, tail.spare_capacity_mut()).unwrap();
+            assert_eq!(read, &[7u8; 192][..]);
+
+            // 超出文件末尾时读取为空
+            assert!(file.read_into_uninit(9000, tail.spare_capacity_mut()).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn test_protection() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_protection_query.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        let clone = file.clone();
+        assert_eq!(file.protection(), Protection::ReadWrite);
+
+        // 修改保护后，所有克隆都能读到新状态
+        unsafe { file.set_protection(Protection::ReadOnly).unwrap() };
+        assert_eq!(file.protection(), Protection::ReadOnly);
+        assert_eq!(clone.protection(), Protection::ReadOnly);
+
+        unsafe { file.set_protection(Protection::ReadWrite).unwrap() };
+        assert_eq!(clone.protection(), Protection::ReadWrite);
+    }
+
+    #[test]
+    fn test_fill_pattern() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_fill_pattern.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(64).unwrap()).unwrap();
+        unsafe {
+            // 最后一次重复被截断
+            file.fill_pattern(5, 10, b"ABC").unwrap();
+            assert_eq!(file.read_slice(5, 10).unwrap(), b"ABCABCABCA");
+            assert_eq!(file.read_slice(0, 5).unwrap(), [0u8; 5]);
+            assert_eq!(file.read_slice(15, 1).unwrap(), [0u8]);
+            assert_eq!(file.high_watermark(), 15);
+
+            // 模式比区域长
+            file.fill_pattern(20, 2, b"XYZ").unwrap();
+            assert_eq!(file.read_slice(20, 3).unwrap(), b"XY\0");
+
+            // 越界
+            assert!(matches!(file.fill_pattern(60, 10, b"A"), Err(Error::OutOfBounds { .. })));
+        }
+    }
+
+    /// 创建 `size` 字节的稀疏文件，使超过 4 GiB 的测试不占用实际磁盘空间
+    #[cfg(windows)]
+    fn create_sparse(path: &std::path::Path, size: u64) {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::System::IO::DeviceIoControl;
+        use windows_sys::Win32::System::Ioctl::FSCTL_SET_SPARSE;
+
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+            .unwrap();
+        let mut returned = 0u32;
+        let ok = unsafe {
+            DeviceIoControl(
+                file.as_raw_handle(),
+                FSCTL_SET_SPARSE,
+                std::ptr::null(),
+                0,
+                std::ptr::null_mut(),
+                0,
+                &mut returned,
+                std::ptr::null_mut(),
+            )
+        };
+        assert_ne!(ok, 0, "FSCTL_SET_SPARSE failed: {}", std::io::Error::last_os_error());
+        file.set_len(size).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_offsets_beyond_4gib() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_beyond_4gib.bin");
+
+        // Windows 上需显式标记为稀疏文件；unix 上 ftruncate 本身就是稀疏的
+        #[cfg(windows)]
+        let file = {
+            create_sparse(&path, 6 * GIB);
+            MmapFileInner::open(&path).unwrap()
+        };
+        #[cfg(not(windows))]
+        let file = MmapFileInner::create(&path, NonZeroU64::new(6 * GIB).unwrap()).unwrap();
+        assert_eq!(file.size().get(), 6 * GIB);
+
+        unsafe {
+            // 跨越 4 GiB 边界以及位于 5 GiB 处的写入
+            file.write_all_at(4 * GIB - 3, b"straddle");
+            file.write_all_at(5 * GIB, b"beyond 4 GiB");
+            assert_eq!(file.read_slice(4 * GIB - 3, 8).unwrap(), b"straddle");
+            assert_eq!(file.read_slice(5 * GIB, 12).unwrap(), b"beyond 4 GiB");
+
+            file.flush_range(5 * GIB, 12).unwrap();
+            file.sync_range(4 * GIB - 3, 8).unwrap();
+        }
+        drop(file);
+
+        // 通过普通文件 I/O 确认数据落在正确的偏移处
+        use std::io::{Read, Seek, SeekFrom};
+        let mut raw = std::fs::File::open(&path).unwrap();
+        let mut buf = [0u8; 12];
+        raw.seek(SeekFrom::Start(5 * GIB)).unwrap();
+        raw.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"beyond 4 GiB");
+        raw.seek(SeekFrom::Start(4 * GIB - 3)).unwrap();
+        raw.read_exact(&mut buf[..8]).unwrap();
+        assert_eq!(&buf[..8], b"straddle");
+    }
+
+    #[test]
+    fn test_create_or_open() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_create_or_open.bin");
+
+        // 文件不存在时创建
+        let file = MmapFileInner::create_or_open(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        unsafe { file.write_all_at(100, b"persisted") };
+        drop(file);
+
+        // 大小相同时保留数据
+        let file = MmapFileInner::create_or_open(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        unsafe { assert_eq!(file.read_slice(100, 9).unwrap(), b"persisted") };
+        drop(file);
+
+        // 大小不同时截断并调整大小
+        let file = MmapFileInner::create_or_open(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+        assert_eq!(file.size().get(), 8192);
+        unsafe { assert!(file.read_slice(0, 8192).unwrap().iter().all(|&b| b == 0)) };
+        drop(file);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 8192);
+
+        // 缩小同样会截断
+        let file = MmapFileInner::create_or_open(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        assert_eq!(file.size().get(), 1024);
+        drop(file);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 1024);
+    }
+
+    #[test]
+    fn test_create_with_truncate() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_create_with_truncate.bin");
+        std::fs::write(&path, [7u8; 4096]).unwrap();
+
+        // 不截断：保留已有内容，增长部分为零
+        let file = MmapFileInner::create_with_truncate(&path, NonZeroU64::new(8192).unwrap(), false).unwrap();
+        unsafe {
+            assert!(file.read_slice(0, 4096).unwrap().iter().all(|&b| b == 7));
+            assert!(file.read_slice(4096, 4096).unwrap().iter().all(|&b| b == 0));
+        }
+        drop(file);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 8192);
+
+        // 截断：与 create 相同，内容被清空
+        let file = MmapFileInner::create_with_truncate(&path, NonZeroU64::new(4096).unwrap(), true).unwrap();
+        unsafe { assert!(file.read_slice(0, 4096).unwrap().iter().all(|&b| b == 0)) };
+        drop(file);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 4096);
+    }
+
+    #[test]
+    fn test_checkpoint() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_checkpoint.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+        unsafe {
+            file.write_all_at(0, b"data only");
+            file.checkpoint(true).unwrap();
+            file.write_all_at(4096, b"full sync");
+            file.checkpoint(false).unwrap();
+        }
+        drop(file);
+
+        // 重新打开后数据已持久化
+        let reopened = MmapFileInner::open(&path).unwrap();
+        unsafe {
+            assert_eq!(reopened.read_slice(0, 9).unwrap(), b"data only");
+            assert_eq!(reopened.read_slice(4096, 9).unwrap(), b"full sync");
+        }
+    }
+
+    #[test]
+    fn test_remap_keeps_data() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_remap.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+        unsafe {
+            file.write_all_at(0, b"before");
+            file.write_all_at(8186, b"remap!");
+            file.set_protection(Protection::ReadOnly).unwrap();
+
+            // 重新映射后数据仍在，保护也被重新应用
+            file.remap().unwrap();
+            assert_eq!(file.size().get(), 8192);
+            assert_eq!(file.read_slice(0, 6).unwrap(), b"before");
+            assert_eq!(file.read_slice(8186, 6).unwrap(), b"remap!");
+            assert!(matches!(file.try_write_at(0, b"x"), Err(Error::ReadOnlyMapping)));
+        }
+
+        // 刷新后的数据已写入磁盘
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..6], b"before");
+    }
+
+    #[test]
+    fn test_remap_rejects_copy_on_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_remap_cow.bin");
+        std::fs::write(&path, [1u8; 4096]).unwrap();
+
+        // 重新映射会丢弃私有修改，因此被拒绝，原映射保持不变
+        let file = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe {
+            file.write_byte(10, 0xEE).unwrap();
+            assert!(matches!(file.remap(), Err(Error::CopyOnWriteMapping)));
+            assert_eq!(file.read_byte(10).unwrap(), 0xEE);
+        }
+    }
+
+    #[test]
+    fn test_refresh_rejects_copy_on_write() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_refresh_cow.bin");
+        std::fs::write(&path, [1u8; 4096]).unwrap();
+
+        let file = MmapFileInner::open_copy_on_write(&path).unwrap();
+        unsafe { file.write_byte(10, 0xEE).unwrap() };
+
+        // 文件被外部扩展后，refresh 也不会把私有映射变成共享映射
+        std::fs::OpenOptions::new().write(true).open(&path).unwrap().set_len(8192).unwrap();
+        assert!(matches!(unsafe { file.refresh() }, Err(Error::CopyOnWriteMapping)));
+        assert_eq!(file.size().get(), 4096);
+        assert_eq!(unsafe { file.read_byte(10).unwrap() }, 0xEE);
+    }
+
+    #[test]
+    fn test_endian_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_endian.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(64).unwrap()).unwrap();
+        let value = 0x0102_0304_0506_0708u64;
+
+        unsafe {
+            file.write_u64_le_at(0, value).unwrap();
+            file.write_u64_be_at(8, value).unwrap();
+            file.write_u32_le_at(16, 0x0A0B_0C0D).unwrap();
+            file.write_u32_be_at(20, 0x0A0B_0C0D).unwrap();
+
+            assert_eq!(file.read_u64_le_at(0).unwrap(), value);
+            assert_eq!(file.read_u64_be_at(8).unwrap(), value);
+            assert_eq!(file.read_u32_le_at(16).unwrap(), 0x0A0B_0C0D);
+            assert_eq!(file.read_u32_be_at(20).unwrap(), 0x0A0B_0C0D);
+            file.flush().unwrap();
+        }
+
+        // 验证磁盘上的字节序
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[0..8], &[8, 7, 6, 5, 4, 3, 2, 1]);
+        assert_eq!(&bytes[8..16], &[1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(&bytes[16..20], &[0x0D, 0x0C, 0x0B, 0x0A]);
+        assert_eq!(&bytes[20..24], &[0x0A, 0x0B, 0x0C, 0x0D]);
+
+        // 越界
+        unsafe {
+            assert!(matches!(file.write_u64_le_at(60, value), Err(Error::OutOfBounds { .. })));
+            assert!(matches!(file.read_u32_be_at(61), Err(Error::OutOfBounds { .. })));
+        }
+    }
+
+    #[test]
+    fn test_read_write_byte() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_byte.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(100).unwrap()).unwrap();
+
+        unsafe {
+            // 包括第一个和最后一个有效偏移
+            for offset in [0u64, 1, 50, 99] {
+                file.write_byte(offset, offset as u8 + 1).unwrap();
+            }
+            for offset in [0u64, 1, 50, 99] {
+                assert_eq!(file.read_byte(offset).unwrap(), offset as u8 + 1);
+            }
+            assert_eq!(file.read_byte(2).unwrap(), 0);
+
+            // 越界
+            assert!(matches!(file.write_byte(100, 1), Err(Error::OutOfBounds { offset: 100, .. })));
+            assert!(matches!(file.read_byte(u64::MAX), Err(Error::OutOfBounds { .. })));
+        }
+
+        assert_eq!(file.high_watermark(), 100);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_as_raw_fd() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_raw_fd.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(8192).unwrap()).unwrap();
+
+        // fstat 应成功，且报告的大小与文件一致
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        let ret = unsafe { libc::fstat(file.as_raw_fd(), &mut stat) };
+        assert_eq!(ret, 0);
+        assert_eq!(stat.st_size, 8192);
+    }
+
+    #[test]
+    fn test_sync_on_drop_error_handler() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_drop_flush.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        let calls = Arc::new(AtomicUsize::new(0));
+        let handler_calls = Arc::clone(&calls);
+
+        file.set_sync_on_drop(true);
+        file.set_drop_error_handler(Box::new(move |err| {
+            assert!(matches!(err, Error::Io(_)));
+            handler_calls.fetch_add(1, Ordering::Relaxed);
+        }));
+        file.inject_drop_flush_failure();
+
+        // 丢弃克隆不会触发刷新
+        let clone = file.clone();
+        drop(clone);
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+
+        // 丢弃最后一个句柄时刷新失败，不会 panic，且处理器被调用
+        drop(file);
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_close() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_close.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        unsafe { file.write_all_at(0, b"closed") };
+
+        // 非最后一个句柄：只刷新
+        let clone = file.clone();
+        clone.close().unwrap();
+        assert_eq!(&std::fs::read(&path).unwrap()[..6], b"closed");
+
+        // 最后一个句柄：刷新失败时返回错误
+        file.inject_drop_flush_failure();
+        assert!(matches!(file.close(), Err(Error::Io(_))));
+
+        // 成功关闭最后一个句柄
+        let file = MmapFileInner::open(&path).unwrap();
+        file.close().unwrap();
+    }
+
+    #[test]
+    fn test_sync_on_drop_persists() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_drop_sync.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        file.set_sync_on_drop(true);
+        unsafe { file.write_all_at(0, b"synced"); }
+        drop(file);
+
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..6], b"synced");
+    }
+
+    #[test]
+    fn test_commit_range() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_commit_range.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(64 * 1024).unwrap()).unwrap();
+        unsafe { file.write_all_at(5000, b"keep"); }
+
+        unsafe { file.commit_range(100, 32 * 1024).unwrap(); }
+
+        // 已有内容不变
+        let data = unsafe { file.read_slice(5000, 4).unwrap() };
+        assert_eq!(data, b"keep");
+
+        // 越界
+        let result = unsafe { file.commit_range(60 * 1024, 8 * 1024) };
+        assert!(matches!(result, Err(Error::OutOfBounds { .. })));
+
+        // 只读映射
+        unsafe { file.set_protection(Protection::ReadOnly).unwrap(); }
+        let result = unsafe { file.commit_range(0, 4096) };
+        assert!(matches!(result, Err(Error::ReadOnlyMapping)));
+    }
+
+    #[test]
+    fn test_path() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_path.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        assert_eq!(file.path(), Some(path.as_path()));
+        assert_eq!(file.clone().path(), Some(path.as_path()));
+
+        let opened = MmapFileInner::open(&path).unwrap();
+        assert_eq!(opened.path(), Some(path.as_path()));
+
+        // Debug 输出包含路径
+        assert!(format!("{:?}", file).contains("inner_path.bin"));
+    }
+
+    #[test]
+    fn test_generation_counter() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_generation.bin");
+
+        let writer = MmapFileInner::create_with_generation(&path, NonZeroU64::new(4096).unwrap()).unwrap();
+        let reader = MmapFileInner::open_with_generation(&path).unwrap();
+        assert_eq!(reader.generation(), 0);
+
+        // 一个句柄同步，另一个句柄读到新的代数
+        unsafe {
+            writer.write_all_at(MmapFileInner::GENERATION_HEADER_LEN, b"payload");
+            writer.sync_all().unwrap();
+        }
+        assert_eq!(reader.generation(), 1);
+
+        unsafe { writer.sync_all().unwrap(); }
+        assert_eq!(reader.generation(), 2);
+        assert_eq!(writer.generation(), 2);
+
+        // 重新打开后计数器保留
+        drop(writer);
+        drop(reader);
+        let reopened = MmapFileInner::open_with_generation(&path).unwrap();
+        assert_eq!(reopened.generation(), 2);
+
+        // 没有头部的映射返回 0
+        let plain = MmapFileInner::open(&path).unwrap();
+        assert_eq!(plain.generation(), 0);
+    }
+
+    #[test]
+    fn test_batched_writer_matches_unbatched() {
+        let dir = tempdir().unwrap();
+        let batched_path = dir.path().join("inner_batched.bin");
+        let plain_path = dir.path().join("inner_unbatched.bin");
+
+        let size = NonZeroU64::new(4096).unwrap();
+        let batched = MmapFileInner::create(&batched_path, size).unwrap();
+        let plain = MmapFileInner::create(&plain_path, size).unwrap();
+
+        // 乱序的细粒度写入，包含相邻、重叠和独立的写入
+        let writes: Vec<(u64, Vec<u8>)> = vec![
+            (16, vec![2; 8]),
+            (0, vec![1; 8]),
+            (8, vec![3; 8]),
+            (12, vec![4; 8]),
+            (1000, vec![5; 8]),
+            (3000, vec![6; 4]),
+            (1004, vec![7; 2]),
+        ];
+
+        let mut batch = BatchedWriter::new(&batched);
+        for (offset, data) in &writes {
+            batch.write(*offset, data.clone());
+            unsafe { plain.write_all_at(*offset, data); }
+        }
+        assert_eq!(batch.len(), writes.len());
+
+        let receipts = unsafe { batch.commit().unwrap() };
+        assert!(batch.is_empty());
+
+        // 合并为三个区间
+        let spans: Vec<_> = receipts.iter().map(|r| (r.start(), r.end())).collect();
+        assert_eq!(spans, vec![(0, 24), (1000, 1008), (3000, 3004)]);
+
+        // 内容与逐个写入完全相同
+        let a = unsafe { batched.read_slice(0, 4096).unwrap() };
+        let b = unsafe { plain.read_slice(0, 4096).unwrap() };
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_batched_writer_out_of_bounds() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_batched_oob.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(64).unwrap()).unwrap();
+        let mut batch = BatchedWriter::new(&file);
+        batch.write(0, b"ok".as_slice());
+        batch.write(60, b"too long".as_slice());
+
+        let result = unsafe { batch.commit() };
+        assert!(matches!(result, Err(Error::OutOfBounds { offset: 60, .. })));
+
+        // 不写入任何数据
+        let data = unsafe { file.read_slice(0, 2).unwrap() };
+        assert_eq!(data, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_as_atomic_bytes_across_threads() {
+        use std::sync::atomic::Ordering;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_atomic_bytes.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        let flags = file.as_atomic_bytes().unwrap();
+        assert_eq!(flags.len(), 1024);
+
+        // 每个线程设置互不相同的字节（偶数位置）
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                s.spawn(move || {
+                    for i in (t * 2..1024).step_by(8) {
+                        flags[i].store(1, Ordering::Relaxed);
+                    }
+                });
+            }
+        });
+
+        for (i, flag) in flags.iter().enumerate() {
+            assert_eq!(flag.load(Ordering::Relaxed), (i % 2 == 0) as u8, "byte {}", i);
+        }
+
+        // 原子写入对普通读取同样可见
+        let data = unsafe { file.read_slice(0, 4) }.unwrap();
+        assert_eq!(data, [1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_as_atomic_bytes_rejects_read_only_mapping() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_atomic_bytes_ro.bin");
+
+        // 只读映射上的原子存储会触发内存错误，因此必须拒绝
+        let file = MmapFileInner::create_with_prot(&path, NonZeroU64::new(1024).unwrap(), Protection::ReadOnly).unwrap();
+        assert!(matches!(file.as_atomic_bytes(), Err(Error::ReadOnlyMapping)));
+    }
+
+    #[test]
+    fn test_split_mut_across_threads() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_split_mut.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(4 * 4096).unwrap()).unwrap();
+
+        // 拆分为 4 个可变切片，每个线程填充一个
+        let chunks = unsafe { file.split_mut(4096) };
+        assert_eq!(chunks.len(), 4);
+        std::thread::scope(|s| {
+            for (i, chunk) in chunks.into_iter().enumerate() {
+                s.spawn(move || chunk.fill(i as u8 + 1));
+            }
+        });
+
+        let data = unsafe { file.read_slice(0, 4 * 4096).unwrap() };
+        for (i, chunk) in data.chunks(4096).enumerate() {
+            assert!(chunk.iter().all(|&b| b == i as u8 + 1));
+        }
+
+        // 最后一个切片可以更短
+        let chunks = unsafe { file.split_mut(5000) };
+        let lens: Vec<_> = chunks.iter().map(|c| c.len()).collect();
+        assert_eq!(lens, vec![5000, 5000, 5000, 1384]);
+    }
+
+    #[test]
+    fn test_create_page_aligned() {
+        let dir = tempdir().unwrap();
+        let page_size = sys::page_size() as u64;
+
+        // 100 字节向上对齐到一页
+        let file = MmapFileInner::create_page_aligned(dir.path().join("inner_aligned_100.bin"), 100).unwrap();
+        assert_eq!(file.size().get(), page_size);
+
+        // 0 字节至少映射一页，已对齐的大小保持不变
+        let file = MmapFileInner::create_page_aligned(dir.path().join("inner_aligned_0.bin"), 0).unwrap();
+        assert_eq!(file.size().get(), page_size);
+        let file = MmapFileInner::create_page_aligned(dir.path().join("inner_aligned_2.bin"), page_size * 2).unwrap();
+        assert_eq!(file.size().get(), page_size * 2);
+
+        // 对齐时溢出
+        assert!(matches!(
+            MmapFileInner::create_page_aligned(dir.path().join("inner_aligned_max.bin"), u64::MAX),
+            Err(Error::SizeOverflow)
+        ));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_create_preallocated() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_preallocated.bin");
+
+        let size = 1024 * 1024;
+        let file = MmapFileInner::create_preallocated(&path, NonZeroU64::new(size).unwrap()).unwrap();
+        assert_eq!(file.size().get(), size);
+
+        // 磁盘块已被实际分配（不是稀疏文件）
+        let mut stat: libc::stat = unsafe { std::mem::zeroed() };
+        assert_eq!(unsafe { libc::fstat(file.as_raw_fd(), &mut stat) }, 0);
+        assert!(stat.st_blocks as u64 * 512 >= size);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_create_preallocated_no_space() {
+        // 尽力而为：请求超过文件系统可用空间的大小
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_preallocated_enospc.bin");
+
+        let mut vfs: libc::statvfs = unsafe { std::mem::zeroed() };
+        let c_dir = std::ffi::CString::new(dir.path().to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { libc::statvfs(c_dir.as_ptr(), &mut vfs) }, 0);
+        let available = vfs.f_bavail as u64 * vfs.f_frsize as u64;
+        let size = available + 1024 * 1024 * 1024;
+
+        match MmapFileInner::create_preallocated(&path, NonZeroU64::new(size).unwrap()) {
+            Err(Error::Io(err)) => match err.raw_os_error() {
+                Some(libc::ENOSPC) | Some(libc::EFBIG) => {}
+                // 文件系统不支持 fallocate，无法验证
+                Some(libc::EOPNOTSUPP) => {}
+                other => panic!("unexpected error: {:?} ({:?})", err, other),
+            },
+            other => panic!("expected ENOSPC, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_try_write_at() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_try_write.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        assert_eq!(unsafe { file.try_write_at(10, b"hello") }.unwrap(), 5);
+
+        let mut buf = [0u8; 5];
+        unsafe { file.read_at(10, &mut buf).unwrap() };
+        assert_eq!(&buf, b"hello");
+
+        // 越界写入返回错误而不是 panic
+        assert!(matches!(
+            unsafe { file.try_write_at(1020, b"hello") },
+            Err(Error::OutOfBounds { .. })
+        ));
+    }
+
+    /// 需要一个很小的 tmpfs，例如：
+    /// `mount -t tmpfs -o size=1m tmpfs /tmp/smallfs`
+    /// 然后 `RANGED_MMAP_SMALL_FS=/tmp/smallfs cargo test --features sigbus-guard -- --ignored`
+    #[cfg(all(unix, feature = "sigbus-guard"))]
+    #[test]
+    #[ignore]
+    fn test_try_write_at_storage_full() {
+        let dir = std::env::var("RANGED_MMAP_SMALL_FS")
+            .expect("RANGED_MMAP_SMALL_FS must point at a small tmpfs or loopback mount");
+        let path = std::path::Path::new(&dir).join("inner_storage_full.bin");
+
+        // 稀疏文件远大于文件系统容量
+        let size = 64 * 1024 * 1024;
+        let file = MmapFileInner::create(&path, NonZeroU64::new(size).unwrap()).unwrap();
+
+        let chunk = vec![0xAB; 64 * 1024];
+        let mut offset = 0;
+        let result = loop {
+            assert!(offset < size, "filesystem never filled up");
+            match unsafe { file.try_write_at(offset, &chunk) } {
+                Ok(_) => offset += chunk.len() as u64,
+                Err(err) => break err,
+            };
+        };
+        assert!(matches!(result, Error::StorageFull { .. }));
+
+        // 映射仍然可用：已写入的数据可以读回
+        let mut buf = [0u8; 4];
+        unsafe { file.read_at(0, &mut buf).unwrap() };
+        assert_eq!(buf, [0xAB; 4]);
+
+        drop(file);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_persist() {
+        let dir = tempdir().unwrap();
+        let temp_path = dir.path().join("inner_persist.tmp");
+        let final_path = dir.path().join("inner_persist.bin");
+        std::fs::write(&final_path, b"old contents").unwrap();
+
+        let file = MmapFileInner::create(&temp_path, NonZeroU64::new(1024).unwrap()).unwrap();
+        unsafe { file.write_at(0, b"published") };
+
+        // 仍有其他克隆存活时拒绝发布
+        let clone = file.clone();
+        assert!(matches!(clone.persist(&final_path), Err(Error::MappingInUse { clones: 1 })));
+        assert!(temp_path.exists());
+
+        file.persist(&final_path).unwrap();
+
+        // 临时文件消失，目标路径被原子替换为新数据
+        assert!(!temp_path.exists());
+        let data = std::fs::read(&final_path).unwrap();
+        assert_eq!(data.len(), 1024);
+        assert_eq!(&data[..9], b"published");
+    }
+
+    #[test]
+    fn test_copy_to() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_copy_src.bin");
+        let dest = dir.path().join("inner_copy_dst.bin");
+
+        let size = 3 * 1024 * 1024 + 100;
+        let file = MmapFileInner::create(&path, NonZeroU64::new(size).unwrap()).unwrap();
+        unsafe {
+            file.write_at(0, b"head");
+            file.write_at(size - 4, b"tail");
+        }
+
+        // 未刷新的写入也会被复制
+        let copy = unsafe { file.copy_to(&dest) }.unwrap();
+        assert_eq!(copy.size().get(), size);
+        assert_eq!(copy.path(), Some(dest.as_path()));
+        assert_eq!(copy.high_watermark(), size);
+
+        let mut buf = [0u8; 4];
+        unsafe { copy.read_at(0, &mut buf).unwrap() };
+        assert_eq!(&buf, b"head");
+        unsafe { copy.read_at(size - 4, &mut buf).unwrap() };
+        assert_eq!(&buf, b"tail");
+
+        // 副本是独立的映射：修改任一方不会影响另一方
+        unsafe {
+            copy.write_at(0, b"COPY");
+            file.write_at(size - 4, b"SRC!");
+        }
+        unsafe { file.read_at(0, &mut buf).unwrap() };
+        assert_eq!(&buf, b"head");
+        unsafe { copy.read_at(size - 4, &mut buf).unwrap() };
+        assert_eq!(&buf, b"tail");
+
+        unsafe { copy.sync_all().unwrap() };
+        let on_disk = std::fs::read(&dest).unwrap();
+        assert_eq!(&on_disk[..4], b"COPY");
+        assert_eq!(on_disk.len() as u64, size);
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let dir = tempdir().unwrap();
+        let size = NonZeroU64::new(4099).unwrap();
+
+        let a = MmapFileInner::create(dir.path().join("inner_diff_a.bin"), size).unwrap();
+        let b = MmapFileInner::create(dir.path().join("inner_diff_b.bin"), size).unwrap();
+        unsafe {
+            a.write_at(100, b"same");
+            b.write_at(100, b"same");
+        }
+
+        assert_eq!(unsafe { a.diff(&b) }.unwrap(), None);
+        assert_eq!(unsafe { a.diff(&a) }.unwrap(), None);
+    }
+
+    #[test]
+    fn test_diff_single_byte() {
+        let dir = tempdir().unwrap();
+        let size = NonZeroU64::new(4099).unwrap();
+
+        let a = MmapFileInner::create(dir.path().join("inner_diff_a.bin"), size).unwrap();
+        let b = MmapFileInner::create(dir.path().join("inner_diff_b.bin"), size).unwrap();
+
+        // 字中间、字边界以及末尾不足一个字的部分
+        for offset in [13, 16, 4098] {
+            unsafe { b.write_at(offset, &[0xFF]) };
+            assert_eq!(unsafe { a.diff(&b) }.unwrap(), Some(offset));
+            unsafe { b.write_at(offset, &[0]) };
+        }
+        assert_eq!(unsafe { a.diff(&b) }.unwrap(), None);
+    }
+
+    #[test]
+    fn test_diff_size_mismatch() {
+        let dir = tempdir().unwrap();
+
+        let a = MmapFileInner::create(dir.path().join("inner_diff_a.bin"), NonZeroU64::new(1024).unwrap()).unwrap();
+        let b = MmapFileInner::create(dir.path().join("inner_diff_b.bin"), NonZeroU64::new(2048).unwrap()).unwrap();
+
+        match unsafe { a.diff(&b) } {
+            Err(Error::SizeMismatch { size, other }) => {
+                assert_eq!(size, 1024);
+                assert_eq!(other, 2048);
+            }
+            other => panic!("expected SizeMismatch, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_read_bytes_outlives_handle() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_read_bytes.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        unsafe { file.write_at(100, b"zero-copy") };
+
+        let bytes = unsafe { file.read_bytes(100, 9) }.unwrap();
+        let ptr = unsafe { file.as_ptr().add(100) };
+
+        // 丢弃原句柄后 Bytes 仍然有效，因为它共享同一个 Arc
+        drop(file);
+        assert_eq!(&bytes[..], b"zero-copy");
+        // 零拷贝：直接指向映射
+        assert_eq!(bytes.as_ptr(), ptr);
+
+        let sub = bytes.slice(5..);
+        drop(bytes);
+        assert_eq!(&sub[..], b"copy");
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_read_bytes_out_of_bounds() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_read_bytes_oob.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        assert!(matches!(
+            unsafe { file.read_bytes(1000, 100) },
+            Err(Error::OutOfBounds { .. })
+        ));
+    }
+
+    // 哈希基于标识而非内部可变的状态，因此作为键是安全的
+    #[allow(clippy::mutable_key_type)]
+    #[test]
+    fn test_id_identity() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("inner_id.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap()).unwrap();
+        let clone = file.clone();
+        assert_eq!(file.id(), clone.id());
+        assert_eq!(file, clone);
+
+        // 同一路径单独打开的映射是不同的标识
+        let other = MmapFileInner::open(&path).unwrap();
+        assert_ne!(file.id(), other.id());
+        assert_ne!(file, other);
+
+        // 可以作为 map 键，所有克隆对应同一个条目
+        let mut map = std::collections::HashMap::new();
+        map.insert(file.clone(), "first");
+        map.insert(clone, "second");
+        map.insert(other, "other");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map[&file], "second");
+    }
+}
+
+/// MmapFile 测试（Safe 版本）
+#[cfg(test)]
+mod mmap_file_tests {
+    use super::*;
+    use crate::allocator::{self, RangeAllocator, ALIGNMENT};
+    use std::num::NonZeroU64;
+
+    #[test]
+    fn test_create_with_allocator() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_create.bin");
+
+        let (file, allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 10).unwrap()).unwrap();
+        assert_eq!(file.size(), NonZeroU64::new(ALIGNMENT * 10).unwrap());
+        assert_eq!(allocator.total_size(), NonZeroU64::new(ALIGNMENT * 10).unwrap());
+        assert_eq!(allocator.next_pos(), 0);
+    }
+
+    #[test]
+    fn test_create_for_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("create_for_records.bin");
+
+        let (file, mut allocator) = MmapFile::create_for_records(
+            &path,
+            NonZeroU64::new(512).unwrap(),
+            NonZeroU64::new(10).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(file.size().get(), 5120);
+
+        // 每次分配恰好一个记录槽位
+        for i in 0..10u64 {
+            let record = allocator.allocate().unwrap();
+            assert_eq!((record.start(), record.len()), (i * 512, 512));
+            file.write_range(record, [i as u8; 512]);
+        }
+        // 第 11 次分配失败
+        assert!(allocator.allocate().is_none());
+
+        // 文件大小溢出时返回错误而不是 panic
+        let result = MmapFile::create_for_records(
+            dir.path().join("create_for_records_overflow.bin"),
+            NonZeroU64::new(u64::MAX / 2).unwrap(),
+            NonZeroU64::new(3).unwrap(),
+        );
+        assert!(matches!(result, Err(Error::SizeOverflow)));
+    }
+
+    #[test]
+    fn test_completion_marker() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("completion_marker.bin");
+
+        let (writer, mut allocator) =
+            MmapFile::create_with_completion_marker(&path, NonZeroU64::new(ALIGNMENT * 3).unwrap()).unwrap();
+        let (reader, _) = MmapFile::open_with_completion_marker(&path).unwrap();
+
+        // 数据范围从头部之后开始
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        assert_eq!(range.start(), MmapFile::COMPLETION_HEADER_LEN);
+
+        writer.write_range(range, vec![9u8; range.len() as usize]);
+        assert!(!reader.is_complete().unwrap());
+
+        // 标记完成后，读者可以看到标志
+        writer.mark_complete().unwrap();
+        assert!(reader.is_complete().unwrap());
+        assert!(writer.is_complete().unwrap());
+
+        // 没有头部的文件返回错误
+        let (plain, _) = MmapFile::create_default(dir.path().join("plain.bin"), NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        assert!(matches!(plain.mark_complete(), Err(Error::NoCompletionMarker)));
+        assert!(matches!(plain.is_complete(), Err(Error::NoCompletionMarker)));
+    }
+
+    #[test]
+    fn test_fill_range_pattern() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("fill_range_pattern.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let (head, _) = range.split_at(10).unwrap();
+
+        let receipt = file.fill_range_pattern(head, b"ABC");
+        assert_eq!(receipt.range(), head);
+
+        let mut buf = [0u8; 11];
+        file.read_range(AllocatedRange::from_range_unchecked(0, 11), &mut buf).unwrap();
+        assert_eq!(&buf, b"ABCABCABCA\0");
+    }
+
+    #[test]
+    fn test_dirty_pages() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dirty_pages.bin");
+
+        let (file, _) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 70).unwrap()).unwrap();
+        // 未启用跟踪时，所有页面都视为脏页
+        assert_eq!(file.dirty_pages().count(), 70);
+
+        // 启用之前创建的克隆同样会被跟踪
+        let clone = file.clone();
+        file.set_dirty_tracking(true);
+        assert_eq!(file.dirty_pages().count(), 0);
+
+        // 写入两个页面
+        let page = |i: u64| AllocatedRange::from_range_unchecked(i * ALIGNMENT, (i + 1) * ALIGNMENT);
+        file.write_range(page(1), vec![1u8; ALIGNMENT as usize]);
+        file.write_range(page(5), vec![5u8; ALIGNMENT as usize]);
+        assert_eq!(file.dirty_pages().collect::<Vec<_>>(), [1, 5]);
+
+        // 刷新后脏页集合被清空
+        file.sync_dirty().unwrap();
+        assert_eq!(file.dirty_pages().count(), 0);
+
+        // 跨越位图字边界的写入，以及共享位图的克隆
+        let range = AllocatedRange::from_range_unchecked(63 * ALIGNMENT + 100, 66 * ALIGNMENT + 1);
+        clone.write_range(range, vec![7u8; range.len() as usize]);
+        assert_eq!(file.dirty_pages().collect::<Vec<_>>(), [63, 64, 65, 66]);
+        file.sync_dirty().unwrap();
+        assert_eq!(clone.dirty_pages().count(), 0);
+
+        drop((file, clone));
+        let data = std::fs::read(&path).unwrap();
+        assert_eq!(data[ALIGNMENT as usize], 1);
+        assert_eq!(data[5 * ALIGNMENT as usize], 5);
+        assert_eq!(data[66 * ALIGNMENT as usize], 7);
+    }
+
+    #[test]
+    fn test_dirty_bitset_follows_resize() {
+        use std::sync::atomic::Ordering;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("dirty_resize.bin");
+
+        let file = MmapFileInner::create(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        file.set_dirty_tracking(true);
+        file.mark_dirty(ALIGNMENT, 1);
+
+        // 扩展后位图覆盖新页面，并保留已有的脏位
+        unsafe { file.resize(NonZeroU64::new(ALIGNMENT * 130).unwrap()).unwrap() };
+        file.mark_dirty(ALIGNMENT * 129, 1);
+        let words: Vec<u64> = file.with_dirty(|words| words.unwrap().iter().map(|w| w.load(Ordering::Relaxed)).collect());
+        assert_eq!(words, [0b10, 0, 0b10]);
+
+        // 缩小后超出文件的脏位被丢弃
+        unsafe { file.resize(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap() };
+        let words: Vec<u64> = file.with_dirty(|words| words.unwrap().iter().map(|w| w.load(Ordering::Relaxed)).collect());
+        assert_eq!(words, [0]);
+    }
+
+    #[test]
+    fn test_create_concurrent() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("create_concurrent.bin");
+
+        let (file, allocator) = MmapFile::create_concurrent(&path, NonZeroU64::new(ALIGNMENT * 16).unwrap()).unwrap();
+        // 通过 trait 对象在多个线程间共享分配器
+        let shared: std::sync::Arc<dyn allocator::ConcurrentRangeAllocator> = allocator.clone();
+
+        let written: u64 = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..4u8)
+                .map(|id| {
+                    let file = file.clone();
+                    let shared = std::sync::Arc::clone(&shared);
+                    s.spawn(move || {
+                        let mut count = 0;
+                        while let Some(range) = shared.allocate(NonZeroU64::new(ALIGNMENT).unwrap()) {
+                            file.write_range(range, vec![id + 1; range.len() as usize]);
+                            count += 1;
+                        }
+                        count
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+
+        // 所有范围都被分配且写入
+        assert_eq!(written, 16);
+        assert_eq!(allocator.remaining(), 0);
+
+        drop(file);
+        let data = std::fs::read(&path).unwrap();
+        assert!(data.iter().all(|&b| b != 0));
+    }
+
+    #[test]
+    #[allow(clippy::drop_non_drop)]
+    fn test_open_existing_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_open.bin");
+
+        // 先创建 (4K aligned)
+        let (file1, mut allocator1) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range = allocator1.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let mut data = vec![0u8; ALIGNMENT as usize];
+        data[..10].copy_from_slice(b"testdata!!");
+        file1.write_range(range, &data);
+        unsafe { file1.sync_all().unwrap(); }
+        drop(file1);
+        drop(allocator1);
+
+        // 重新打开
+        let (file2, mut allocator2) = MmapFile::open_default(&path).unwrap();
+        assert_eq!(file2.size(), NonZeroU64::new(ALIGNMENT).unwrap());
+        assert_eq!(allocator2.total_size(), NonZeroU64::new(ALIGNMENT).unwrap());
+
+        let range = allocator2.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file2.read_range(range, &mut buf).unwrap();
+        assert_eq!(&buf[..10], b"testdata!!");
+    }
+
+    #[test]
+    fn test_basic_write_and_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_basic.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+
+        // 分配并写入 (4K aligned)
+        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let data1 = vec![1u8; ALIGNMENT as usize];
+        let data2 = vec![2u8; ALIGNMENT as usize];
+
+        let receipt1 = file.write_range(range1, &data1);
+        let receipt2 = file.write_range(range2, &data2);
+
+        assert_eq!(receipt1.len(), ALIGNMENT);
+        assert_eq!(receipt2.len(), ALIGNMENT);
+
+        unsafe { file.sync_all().unwrap(); }
+
+        // 验证读取
+        let mut buf1 = vec![0u8; ALIGNMENT as usize];
+        let mut buf2 = vec![0u8; ALIGNMENT as usize];
+
+        file.read_range(range1, &mut buf1).unwrap();
+        file.read_range(range2, &mut buf2).unwrap();
+
+        assert_eq!(buf1, data1);
+        assert_eq!(buf2, data2);
+    }
+
+    #[test]
+    fn test_write_range_all() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_all.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let data = vec![0u8; ALIGNMENT as usize];
+        let receipt = file.write_range_all(range, &data);
+
+        assert_eq!(receipt.len(), ALIGNMENT);
+        assert_eq!(receipt.start(), 0);
+        assert_eq!(receipt.end(), ALIGNMENT);
+    }
+
+    #[test]
+    fn test_write_range_as_ref() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_as_ref.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let chunk = NonZeroU64::new(ALIGNMENT).unwrap();
+
+        // 直接传入 Vec 和数组，无需 `&` 或 `.as_ref()`
+        let range1 = allocator.allocate(chunk).unwrap();
+        file.write_range(range1, &vec![1u8; ALIGNMENT as usize]);
+
+        let range2 = allocator.allocate(chunk).unwrap();
+        file.write_range_all(range2, [2u8; ALIGNMENT as usize]);
+
+        unsafe { file.sync_all().unwrap() };
+        let on_disk = std::fs::read(&path).unwrap();
+        assert!(on_disk[..ALIGNMENT as usize].iter().all(|&b| b == 1));
+        assert!(on_disk[ALIGNMENT as usize..].iter().all(|&b| b == 2));
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn test_write_range_bytes() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_bytes.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let data = bytes::Bytes::from(vec![7u8; ALIGNMENT as usize]);
+        file.write_range(range, data);
+
+        unsafe { file.sync_all().unwrap() };
+        assert!(std::fs::read(&path).unwrap().iter().all(|&b| b == 7));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_async_range_writer() {
+        use tokio::io::AsyncWriteExt;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_async_writer.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 分多次写入，直到填满整个范围
+        let mut writer = file.async_range_writer(range);
+        writer.write_all(&[1u8; 1000]).await.unwrap();
+        writer.flush().await.unwrap();
+        writer.write_all(&vec![2u8; ALIGNMENT as usize - 1000]).await.unwrap();
+        writer.shutdown().await.unwrap();
+        assert_eq!(writer.written(), ALIGNMENT);
+
+        // 范围已满，再写入会失败
+        assert!(writer.write_all(&[3u8]).await.is_err());
+
+        let receipt = writer.into_receipt().unwrap();
+        assert_eq!(receipt.range(), range);
+
+        let data = std::fs::read(&path).unwrap();
+        assert!(data[..1000].iter().all(|&b| b == 1));
+        assert!(data[1000..ALIGNMENT as usize].iter().all(|&b| b == 2));
+        assert!(data[ALIGNMENT as usize..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_concurrent_writes_with_allocated_ranges() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_concurrent.bin");
+
+        // 10 * 4K = 40K
+        let num_ranges = 10;
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * num_ranges).unwrap()).unwrap();
+
+        // 预先分配所有范围
+        let mut ranges = Vec::new();
+        for _ in 0..num_ranges {
+            ranges.push(allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap());
+        }
+
+        // 并发写入（编译期安全！）
+        std::thread::scope(|s| {
+            for (i, range) in ranges.into_iter().enumerate() {
+                let f = file.clone();
+                s.spawn(move || {
+                    let data = vec![i as u8; ALIGNMENT as usize];
+                    let _receipt = f.write_range(range, &data);
+                });
+            }
+        });
+
+        unsafe { file.sync_all().unwrap(); }
+
+        // 验证
+        let mut allocator2 = allocator::sequential::Allocator::new(NonZeroU64::new(ALIGNMENT * num_ranges).unwrap());
+        for i in 0..num_ranges as usize {
+            let range = allocator2.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+            let mut buf = vec![0u8; ALIGNMENT as usize];
+            file.read_range(range, &mut buf).unwrap();
+            assert_eq!(buf, vec![i as u8; ALIGNMENT as usize]);
+        }
+    }
+
+    #[test]
+    fn test_high_concurrency() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_high_concurrency.bin");
+
+        // 100个并发写入, 每个4K
+        let num_threads = 100u64;
+        let chunk_size = ALIGNMENT;
+        let file_size = num_threads * chunk_size;
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(file_size).unwrap()).unwrap();
+
+        // 预先分配所有范围
+        let mut ranges = Vec::new();
+        for _ in 0..num_threads {
+            ranges.push(allocator.allocate(NonZeroU64::new(chunk_size).unwrap()).unwrap());
+        }
+
+        // 并发写入
+        std::thread::scope(|s| {
+            for (i, range) in ranges.into_iter().enumerate() {
+                let f = file.clone();
+                s.spawn(move || {
+                    let data = vec![i as u8; chunk_size as usize];
+                    let _receipt = f.write_range(range, &data);
+                });
+            }
+        });
+
+        unsafe { file.sync_all().unwrap(); }
+
+        // 验证
+        let mut allocator2 = allocator::sequential::Allocator::new(NonZeroU64::new(file_size).unwrap());
+        for i in 0..num_threads as usize {
+            let range = allocator2.allocate(NonZeroU64::new(chunk_size).unwrap()).unwrap();
+            let mut buf = vec![0u8; chunk_size as usize];
+            file.read_range(range, &mut buf).unwrap();
+            assert_eq!(buf, vec![i as u8; chunk_size as usize]);
+        }
+    }
+
+    #[test]
+    fn test_data_length_match() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_length_match.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // Allocate 4K
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 正确的长度
+        let receipt = file.write_range(range, &vec![0u8; ALIGNMENT as usize]);
+        assert_eq!(receipt.len(), ALIGNMENT);
+
+        // 数据长度不匹配的情况现在用 debug_assert 检查
+        // 在 release 模式下不会 panic，但在 debug 模式下会 panic
+    }
+
+    #[test]
+    fn test_read_range_buffer_too_small() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_read_buf.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let data = vec![0x42u8; ALIGNMENT as usize];
+        file.write_range(range, &data);
+
+        // buffer 太小
+        let mut buf = vec![0u8; 100];
+        let result = file.read_range(range, &mut buf);
+        assert!(result.is_err());
+
+        // buffer 大小正确
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        let result = file.read_range(range, &mut buf);
+        assert!(result.is_ok());
+        assert_eq!(buf, data);
+
+        // buffer 更大也可以
+        let mut buf = vec![0u8; ALIGNMENT as usize * 2];
+        let result = file.read_range(range, &mut buf);
+        assert!(result.is_ok());
+        assert_eq!(&buf[..ALIGNMENT as usize], &data[..]);
+    }
+
+    #[test]
+    fn test_write_partial() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_partial.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 3).unwrap()).unwrap();
+        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 先用 0xFF 填满两个范围
+        file.write_range(range1, &vec![0xFF; ALIGNMENT as usize]);
+        file.write_range(range2, &vec![0xFF; ALIGNMENT as usize]);
+
+        // 清零剩余部分
+        let receipt = file.write_partial(range1, &[1u8; 3000], true).unwrap();
+        assert_eq!(receipt.start(), 0);
+        assert_eq!(receipt.len(), 3000);
+
+        // 保持剩余部分不变
+        let receipt = file.write_partial(range2, &[2u8; 3000], false).unwrap();
+        assert_eq!(receipt.start(), ALIGNMENT);
+        assert_eq!(receipt.len(), 3000);
+
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(range1, &mut buf).unwrap();
+        assert!(buf[..3000].iter().all(|&b| b == 1));
+        assert!(buf[3000..].iter().all(|&b| b == 0));
+
+        file.read_range(range2, &mut buf).unwrap();
+        assert!(buf[..3000].iter().all(|&b| b == 2));
+        assert!(buf[3000..].iter().all(|&b| b == 0xFF));
+
+        // 数据比范围长
+        let range3 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let result = file.write_partial(range3, &vec![0u8; ALIGNMENT as usize + 1], true);
+        assert!(matches!(result, Err(Error::DataTooLarge { .. })));
+
+        // 超出文件大小的范围不会被清零
+        let range4 = AllocatedRange::from_range_unchecked(ALIGNMENT * 2, ALIGNMENT * 4);
+        let result = file.write_partial(range4, &[3u8; 16], true);
+        assert!(matches!(result, Err(Error::OutOfBounds { .. })));
+    }
+
+    #[test]
+    fn test_flush_operations() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_flush.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+
+        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let receipt1 = file.write_range(range1, &vec![1u8; ALIGNMENT as usize]);
+        let receipt2 = file.write_range(range2, &vec![2u8; ALIGNMENT as usize]);
+
+        // 测试异步刷新
+        file.flush().unwrap();
+
+        // 刷新特定范围
+        file.flush_range(receipt1).unwrap();
+        file.flush_range(receipt2).unwrap();
+
+        // 全局同步
+        unsafe { file.sync_all().unwrap(); }
+    }
+
+    #[test]
+    fn test_write_receipt_properties() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_receipt.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let receipt = file.write_range(range, &vec![0u8; ALIGNMENT as usize]);
+
+        assert_eq!(receipt.start(), 0);
+        assert_eq!(receipt.end(), ALIGNMENT);
+        assert_eq!(receipt.len(), ALIGNMENT);
+        assert!(!receipt.is_empty());
+        assert_eq!(receipt.range(), range);
+    }
+    
+    #[test]
+    fn test_clone_and_shared_access() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_clone.bin");
+
+        let (file1, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let file2 = file1.clone();
+
+        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
 
         // 两个克隆分别写入
         let mut data1 = vec![0u8; ALIGNMENT as usize];
@@ -674,6 +2418,668 @@ mod mmap_file_tests {
         assert_eq!(&buf1[..10], b"from_file1");
         assert_eq!(&buf2[..10], b"from_file2");
     }
+
+    #[test]
+    fn test_flush_receipts_iter() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_flush_receipts.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 8).unwrap()).unwrap();
+        let receipts: Vec<_> = (0..8)
+            .map(|i| {
+                let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+                file.write_range(range, &vec![i as u8; ALIGNMENT as usize])
+            })
+            .collect();
+
+        // 按顺序到达的凭据合并为一个区间
+        let mut spans = Vec::new();
+        super::mmap_file::coalesce_receipts(receipts.iter().copied(), 0, |start, end| {
+            spans.push((start, end));
+            Ok(())
+        }).unwrap();
+        assert_eq!(spans, vec![(0, ALIGNMENT * 8)]);
+
+        // 有空洞时拆分为多个区间
+        let mut spans = Vec::new();
+        let gapped = [receipts[0], receipts[1], receipts[3], receipts[4], receipts[7]];
+        super::mmap_file::coalesce_receipts(gapped, 0, |start, end| {
+            spans.push((start, end));
+            Ok(())
+        }).unwrap();
+        assert_eq!(spans, vec![(0, ALIGNMENT * 2), (ALIGNMENT * 3, ALIGNMENT * 5), (ALIGNMENT * 7, ALIGNMENT * 8)]);
+
+        // 通过迭代器刷新（来自通道）
+        let (tx, rx) = std::sync::mpsc::channel();
+        for receipt in &receipts {
+            tx.send(*receipt).unwrap();
+        }
+        drop(tx);
+        file.flush_receipts_iter(rx).unwrap();
+
+        // 乱序的切片版本排序后合并
+        let reversed: Vec<_> = receipts.iter().rev().copied().collect();
+        file.flush_receipts(&reversed).unwrap();
+    }
+
+    #[test]
+    fn test_flush_coalesce_gap() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_flush_gap.bin");
+
+        let gap = 64 * 1024;
+        let (mut file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(1024 * 1024).unwrap()).unwrap();
+
+        // 写入一页，然后跳过 `skip` 字节不写
+        let mut write_then_skip = |skip: u64| {
+            let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+            let receipt = file.write_range(range, [1u8; ALIGNMENT as usize]);
+            if let Some(skip) = NonZeroU64::new(skip) {
+                allocator.allocate(skip).unwrap();
+            }
+            receipt
+        };
+
+        // 间隙分别略小于、等于、略大于阈值
+        let receipts = [
+            write_then_skip(gap - ALIGNMENT),
+            write_then_skip(gap),
+            write_then_skip(gap + ALIGNMENT),
+            write_then_skip(0),
+        ];
+
+        let spans = |gap: u64| {
+            let mut spans = Vec::new();
+            super::mmap_file::coalesce_receipts(receipts, gap, |start, end| {
+                spans.push((start, end));
+                Ok(())
+            }).unwrap();
+            spans
+        };
+
+        // 默认只合并连续的范围
+        assert_eq!(spans(0).len(), 4);
+
+        // 不超过阈值的间隙被合并，超过阈值的间隙则拆分
+        assert_eq!(
+            spans(gap),
+            vec![
+                (receipts[0].start(), receipts[2].end()),
+                (receipts[3].start(), receipts[3].end()),
+            ]
+        );
+
+        file.set_flush_coalesce_gap(gap);
+        file.flush_receipts(&receipts).unwrap();
+        file.flush_all_ranges(&receipts).unwrap();
+    }
+
+    #[test]
+    fn test_modify_range() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_modify_range.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let data: Vec<u8> = (0..ALIGNMENT).map(|i| i as u8).collect();
+        file.write_range(range2, &data);
+
+        // 原地递增每个字节
+        let receipt = file.modify_range(range2, |bytes| {
+            for byte in bytes.iter_mut() {
+                *byte = byte.wrapping_add(1);
+            }
+        }).unwrap();
+        assert_eq!(receipt.range(), range2);
+
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(range2, &mut buf).unwrap();
+        assert!(buf.iter().enumerate().all(|(i, &b)| b == (i as u8).wrapping_add(1)));
+
+        // 其他范围不受影响
+        file.read_range(range1, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 0));
+
+        file.flush_range(receipt).unwrap();
+    }
+
+    #[test]
+    fn test_modify_range_out_of_bounds() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("modify_range_oob.bin");
+
+        let (file, _allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 来自更大文件的范围不能越界访问映射
+        let range = AllocatedRange::from_range_unchecked(0, ALIGNMENT * 2);
+        let result = file.modify_range(range, |_| panic!("closure must not run"));
+        assert!(matches!(result, Err(Error::OutOfBounds { .. })));
+    }
+
+    #[test]
+    fn test_write_scattered() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_scattered.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        file.write_range(range, &vec![0xAAu8; ALIGNMENT as usize]);
+
+        // 头部位于相对偏移 0，尾部位于相对偏移 4000
+        let receipt = file
+            .write_scattered(range, &[(4000, b"footer".as_slice()), (0, b"header".as_slice())])
+            .unwrap();
+        assert_eq!(receipt.start(), 0);
+        assert_eq!(receipt.end(), 4006);
+
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(range, &mut buf).unwrap();
+        assert_eq!(&buf[..6], b"header");
+        assert_eq!(&buf[4000..4006], b"footer");
+        // 中间部分保持不变
+        assert!(buf[6..4000].iter().all(|&b| b == 0xAA));
+        assert!(buf[4006..].iter().all(|&b| b == 0xAA));
+
+        // 超出范围
+        let result = file.write_scattered(range, &[(4090, b"too long".as_slice())]);
+        assert!(matches!(result, Err(Error::PieceOutOfRange { offset: 4090, len: 8, .. })));
+
+        // 片段重叠，且不写入任何数据
+        let result = file.write_scattered(range, &[(0, b"XXXX".as_slice()), (2, b"YYYY".as_slice())]);
+        assert!(matches!(result, Err(Error::OverlappingPieces { offset: 2 })));
+        file.read_range(range, &mut buf).unwrap();
+        assert_eq!(&buf[..6], b"header");
+    }
+
+    #[test]
+    fn test_rotating_writer() {
+        let dir = tempdir().unwrap();
+        let mut writer = RotatingMmapWriter::new(dir.path(), "segment", NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+
+        // 每个文件放得下两个 4K 槽位，第五条记录需要滚动两次
+        let mut placements = Vec::new();
+        for i in 0..5u8 {
+            let (index, receipt) = writer.append(vec![i + 1; 3000]).unwrap();
+            assert_eq!(receipt.len(), 3000);
+            placements.push((index, receipt.start()));
+        }
+        assert_eq!(
+            placements,
+            vec![(0, 0), (0, ALIGNMENT), (1, 0), (1, ALIGNMENT), (2, 0)]
+        );
+        assert_eq!(writer.current_index(), 2);
+        writer.flush().unwrap();
+
+        // 超出文件大小的记录被拒绝
+        let result = writer.append(vec![0u8; (ALIGNMENT * 2 + 1) as usize]);
+        assert!(matches!(result, Err(Error::DataTooLarge { .. })));
+
+        // 验证三个文件中的分段
+        for (i, &(index, start)) in placements.iter().enumerate() {
+            let bytes = std::fs::read(dir.path().join(format!("segment.{:06}", index))).unwrap();
+            assert_eq!(bytes.len() as u64, ALIGNMENT * 2);
+            let start = start as usize;
+            assert!(bytes[start..start + 3000].iter().all(|&b| b == i as u8 + 1));
+            assert!(bytes[start + 3000..start + ALIGNMENT as usize].iter().all(|&b| b == 0));
+        }
+        assert!(!writer.path(3).exists());
+    }
+
+    #[test]
+    fn test_prefetch_on_first_read() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_prefetch.bin");
+
+        let (mut file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 8).unwrap()).unwrap();
+        let first = allocator.allocate(NonZeroU64::new(ALIGNMENT * 4).unwrap()).unwrap();
+        let second = allocator.allocate(NonZeroU64::new(ALIGNMENT * 4).unwrap()).unwrap();
+        let mut buf = vec![0u8; (ALIGNMENT * 4) as usize];
+
+        // 默认不预取
+        file.read_range(first, &mut buf).unwrap();
+        assert_eq!(file.prefetch_advised_count(), 0);
+
+        file.set_prefetch_on_first_read(true);
+
+        // 每个范围只在首次读取时预取一次
+        file.read_range(first, &mut buf).unwrap();
+        file.read_range(first, &mut buf).unwrap();
+        assert_eq!(file.prefetch_advised_count(), 1);
+
+        // 克隆共享已预取集合
+        let clone = file.clone();
+        clone.read_range(first, &mut buf).unwrap();
+        clone.read_range(second, &mut buf).unwrap();
+        file.read_range(second, &mut buf).unwrap();
+        assert_eq!(file.prefetch_advised_count(), 2);
+
+        // 预取范围越界时报错
+        let inner = MmapFileInner::open(&path).unwrap();
+        assert!(matches!(inner.advise_will_need(ALIGNMENT * 8, 1), Err(Error::OutOfBounds { .. })));
+        inner.advise_will_need(100, 5000).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "bytemuck")]
+    fn test_write_typed() {
+        #[repr(C)]
+        #[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+        struct Header {
+            magic: u32,
+            version: u32,
+            entries: u64,
+        }
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_typed.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let header = Header { magic: 0xFEED, version: 1, entries: 0 };
+        let handle = file.write_typed(range, header).unwrap();
+        assert_eq!(handle.get(), header);
+
+        // 通过句柄修改字段
+        handle.update(|h| h.entries = 42);
+        handle.set(Header { version: 2, ..handle.get() });
+        assert_eq!(handle.get(), Header { magic: 0xFEED, version: 2, entries: 42 });
+
+        // 读回磁盘内容
+        file.flush_range(handle.receipt()).unwrap();
+        unsafe { file.sync_all().unwrap(); }
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(u64::from_le_bytes(bytes[8..16].try_into().unwrap()), 42);
+
+        // 类型放不下时报错
+        let small = AllocatedRange::from_range_unchecked(0, 8);
+        assert!(matches!(file.write_typed(small, header), Err(Error::DataTooLarge { data_len: 16, .. })));
+    }
+
+    #[test]
+    fn test_write_range_counted() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_counted.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let first = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let second = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 部分写入：计数等于 data.len()
+        let data = [7u8; 1000];
+        let (receipt, written) = file.write_range_counted(first, data);
+        assert_eq!(written, data.len());
+        assert_eq!(receipt.len(), 1000);
+        assert_eq!(receipt.start(), first.start());
+
+        // 数据过长时在范围末尾截断
+        let (receipt, written) = file.write_range_counted(second, vec![9u8; ALIGNMENT as usize + 10]);
+        assert_eq!(written, ALIGNMENT as usize);
+        assert_eq!(receipt.range(), second);
+
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(first, &mut buf).unwrap();
+        assert!(buf[..1000].iter().all(|&b| b == 7));
+        assert!(buf[1000..].iter().all(|&b| b == 0));
+    }
+
+    #[test]
+    fn test_write_range_sealed() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_sealed.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let sealed = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let unsealed = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        let receipt = file.write_range_sealed(sealed, b"payload").unwrap();
+        assert_eq!(receipt.range(), sealed);
+        file.write_range(unsealed, vec![0xABu8; ALIGNMENT as usize]);
+
+        assert!(file.is_sealed(sealed));
+        assert!(!file.is_sealed(unsealed));
+
+        // 数据与哨兵放不下
+        let result = file.write_range_sealed(unsealed, vec![0u8; ALIGNMENT as usize - 7]);
+        assert!(matches!(result, Err(Error::DataTooLarge { range_len, .. }) if range_len == ALIGNMENT - 8));
+
+        // 哨兵已落盘
+        file.flush_range(receipt).unwrap();
+        unsafe { file.sync_all().unwrap(); }
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..7], b"payload");
+        assert_eq!(&bytes[ALIGNMENT as usize - 8..ALIGNMENT as usize], &MmapFile::SEAL_SENTINEL.to_le_bytes());
+    }
+
+    #[test]
+    fn test_sequential_writer() {
+        use std::io::Write;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_seq_writer.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let header = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        file.write_range(header, vec![0xFFu8; ALIGNMENT as usize]);
+
+        // 多个缓冲区在头部之后连续写入
+        let mut writer = file.clone().sequential_writer(allocator);
+        writer.write_all(b"first;").unwrap();
+        writer.write_all(b"second;").unwrap();
+        writer.write_all(&[7u8; 100]).unwrap();
+        writer.flush().unwrap();
+        assert_eq!(writer.position(), ALIGNMENT + 113);
+
+        // 写满之后 write_all 失败
+        let rest = vec![1u8; writer.remaining() as usize];
+        writer.write_all(&rest).unwrap();
+        let err = writer.write_all(b"overflow").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+
+        let receipts = writer.finish();
+        let lens: Vec<u64> = receipts.iter().map(|r| r.len()).collect();
+        assert_eq!(lens, vec![6, 7, 100, ALIGNMENT - 113]);
+        assert_eq!(receipts[0].start(), ALIGNMENT);
+        file.assert_fully_written(ALIGNMENT * 2, &[&[WriteReceipt::new(header)], &receipts[..]].concat()).unwrap();
+
+        let mut buf = vec![0u8; 113];
+        file.read_range(AllocatedRange::from_range_unchecked(ALIGNMENT, ALIGNMENT + 113), &mut buf).unwrap();
+        assert_eq!(&buf[..13], b"first;second;");
+        assert!(buf[13..].iter().all(|&b| b == 7));
+    }
+
+    #[test]
+    fn test_assert_fully_written() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_fully_written.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 3).unwrap()).unwrap();
+        let receipts: Vec<WriteReceipt> = (0..3u8)
+            .map(|i| {
+                let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+                file.write_range(range, vec![i; ALIGNMENT as usize])
+            })
+            .collect();
+
+        // 完全覆盖
+        file.assert_fully_written(ALIGNMENT * 3, &receipts).unwrap();
+
+        // 缺少中间的块
+        match file.assert_fully_written(ALIGNMENT * 3, &[receipts[2], receipts[0]]) {
+            Err(Error::IncompleteWrite { gaps }) => assert_eq!(gaps, vec![ALIGNMENT..ALIGNMENT * 2]),
+            other => panic!("expected IncompleteWrite, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_write_batch() {
+        let dir = tempdir().unwrap();
+        let size = NonZeroU64::new(ALIGNMENT * 8).unwrap();
+
+        let (batched, mut allocator) = MmapFile::create_default(dir.path().join("safe_batch.bin"), size).unwrap();
+        let (sequential, _) = MmapFile::create_default(dir.path().join("safe_batch_seq.bin"), size).unwrap();
+
+        let data: Vec<Vec<u8>> = (0..8u8).map(|i| vec![i + 1; ALIGNMENT as usize]).collect();
+        let items: Vec<(AllocatedRange, &[u8])> = data
+            .iter()
+            .map(|d| (allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap(), d.as_slice()))
+            .collect();
+
+        // 凭据顺序与条目一致
+        let receipts = batched.write_batch(&items);
+        assert_eq!(receipts.len(), items.len());
+        for (receipt, (range, _)) in receipts.iter().zip(&items) {
+            assert_eq!(receipt.range(), *range);
+        }
+
+        // 与逐个顺序写入的结果相同
+        for &(range, d) in &items {
+            sequential.write_range(range, d);
+        }
+        let mut batched_buf = vec![0u8; size.get() as usize];
+        let mut sequential_buf = vec![0u8; size.get() as usize];
+        for (range, _) in &items {
+            let at = range.start() as usize..range.end() as usize;
+            batched.read_range(*range, &mut batched_buf[at.clone()]).unwrap();
+            sequential.read_range(*range, &mut sequential_buf[at]).unwrap();
+        }
+        assert_eq!(batched_buf, sequential_buf);
+        assert!(batched.write_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_read_written_range_while_writing() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_read_while_writing.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let range_a = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range_b = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let receipt_a = file.write_range(range_a, vec![0xA5u8; ALIGNMENT as usize]);
+
+        // 一个线程反复写入 B，另一个线程通过凭据读取 A
+        std::thread::scope(|s| {
+            let writer = file.clone();
+            s.spawn(move || {
+                for i in 0..200u8 {
+                    writer.write_range(range_b, vec![i; ALIGNMENT as usize]);
+                }
+            });
+
+            let reader = file.clone();
+            s.spawn(move || {
+                let mut buf = vec![0u8; ALIGNMENT as usize];
+                for _ in 0..200 {
+                    let n = reader.read_range(receipt_a.range(), &mut buf).unwrap();
+                    assert_eq!(n, ALIGNMENT as usize);
+                    assert!(buf.iter().all(|&b| b == 0xA5));
+                }
+            });
+        });
+
+        // 缓冲区太小
+        let mut small = [0u8; 16];
+        assert!(matches!(
+            file.read_range(receipt_a.range(), &mut small),
+            Err(Error::BufferTooSmall { .. })
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracing_flush_range_event() {
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+
+        /// 事件的消息和 `len` 字段
+        #[derive(Default)]
+        struct Fields {
+            message: String,
+            len: Option<u64>,
+        }
+
+        impl Visit for Fields {
+            fn record_u64(&mut self, field: &Field, value: u64) {
+                if field.name() == "len" {
+                    self.len = Some(value);
+                }
+            }
+
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "message" {
+                    self.message = format!("{:?}", value);
+                }
+            }
+        }
+
+        /// 记录每个事件的字段
+        struct Recorder(Arc<Mutex<Vec<Fields>>>);
+
+        impl<S: tracing::Subscriber> Layer<S> for Recorder {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                let mut fields = Fields::default();
+                event.record(&mut fields);
+                self.0.lock().unwrap().push(fields);
+            }
+        }
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let subscriber = tracing_subscriber::registry().with(Recorder(Arc::clone(&events)));
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_tracing.bin");
+
+        tracing::subscriber::with_default(subscriber, || {
+            let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+            let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+            let receipt = file.write_range(range, vec![1u8; ALIGNMENT as usize]);
+            file.flush_range(receipt).unwrap();
+        });
+
+        let events = events.lock().unwrap();
+        let messages: Vec<&str> = events.iter().map(|fields| fields.message.as_str()).collect();
+        assert_eq!(messages, vec!["allocate", "write_range", "flush_range"]);
+        assert_eq!(events[2].len, Some(ALIGNMENT));
+    }
+
+    #[test]
+    fn test_modify_byte() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_modify_byte.bin");
+
+        let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+        let _first = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+        // 范围内的第一个和最后一个字节
+        let receipt = file.modify_byte(range, 0, 0x11).unwrap();
+        assert_eq!((receipt.start(), receipt.end()), (ALIGNMENT, ALIGNMENT + 1));
+        let receipt = file.modify_byte(range, ALIGNMENT - 1, 0x22).unwrap();
+        assert_eq!(receipt.end(), ALIGNMENT * 2);
+
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(range, &mut buf).unwrap();
+        assert_eq!(buf[0], 0x11);
+        assert_eq!(buf[ALIGNMENT as usize - 1], 0x22);
+        assert!(buf[1..ALIGNMENT as usize - 1].iter().all(|&b| b == 0));
+
+        // 超出范围
+        let result = file.modify_byte(range, ALIGNMENT, 0x33);
+        assert!(matches!(result, Err(Error::PieceOutOfRange { offset, len: 1, .. }) if offset == ALIGNMENT));
+    }
+
+    #[cfg(feature = "debug-alloc-checks")]
+    #[test]
+    #[should_panic(expected = "overlaps previously written range")]
+    fn test_debug_alloc_checks_two_allocators() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_debug_alloc_checks.bin");
+
+        // 错误地对同一文件使用两个分配器
+        let (file, mut allocator1) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 4).unwrap()).unwrap();
+        let mut allocator2 = allocator::sequential::Allocator::new(NonZeroU64::new(ALIGNMENT * 4).unwrap());
+
+        let range1 = allocator1.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let range2 = allocator2.allocate(NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+
+        file.write_range(range1, &vec![1u8; ALIGNMENT as usize]);
+        // 重写同一范围是允许的
+        file.write_range(range1, &vec![2u8; ALIGNMENT as usize]);
+        file.write_range(range2, &vec![3u8; (ALIGNMENT * 2) as usize]);
+    }
+
+    #[test]
+    fn test_layout_header_and_data() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_layout.bin");
+
+        let size = NonZeroU64::new(ALIGNMENT * 4).unwrap();
+        let (file, _) = MmapFile::create_default(&path, size).unwrap();
+
+        let (header, mut data) = LayoutBuilder::new(size).header(ALIGNMENT).build().unwrap();
+        assert_eq!(header.start(), 0);
+        assert_eq!(header.end(), ALIGNMENT);
+
+        // 写入魔数
+        file.write_partial(header, b"MAGIC\x01", false).unwrap();
+
+        // 数据范围从 4096 开始
+        let range = data.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        assert_eq!(range.start(), 4096);
+        file.write_range(range, &vec![7u8; ALIGNMENT as usize]);
+
+        let mut buf = vec![0u8; 6];
+        file.read_range(AllocatedRange::from_range_unchecked(0, 6), &mut buf).unwrap();
+        assert_eq!(&buf, b"MAGIC\x01");
+
+        // 头部过大
+        let result = LayoutBuilder::new(size).header(ALIGNMENT * 4 + 1).build();
+        assert!(matches!(result, Err(Error::OutOfBounds { .. })));
+
+        // 对齐时溢出 u64 的头部长度同样返回错误而不是 panic
+        let result = LayoutBuilder::new(size).header(u64::MAX).build();
+        assert!(matches!(result, Err(Error::OutOfBounds { len: u64::MAX, .. })));
+    }
+
+    #[test]
+    fn test_write_and_sync_range() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_write_and_sync.bin");
+
+        {
+            let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap()).unwrap();
+            let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+
+            let receipt = file.write_and_sync_range(range, vec![0x5A; ALIGNMENT as usize]).unwrap();
+            assert_eq!(receipt.range(), range);
+        }
+
+        // 重新打开后记录仍然存在
+        let (file, _) = MmapFile::open_default(&path).unwrap();
+        let mut allocator = allocator::sequential::Allocator::new(file.size());
+        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+        let mut buf = vec![0u8; ALIGNMENT as usize];
+        file.read_range(range, &mut buf).unwrap();
+        assert!(buf.iter().all(|&b| b == 0x5A));
+    }
+
+    #[test]
+    fn test_flush_all_ranges_across_threads() {
+        use allocator::concurrent::Allocator as ConcurrentAllocator;
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("safe_flush_all_ranges.bin");
+
+        const N: u64 = 8;
+        let (file, allocator) = MmapFile::create::<ConcurrentAllocator>(&path, NonZeroU64::new(ALIGNMENT * N).unwrap()).unwrap();
+
+        // 多个线程写入不同范围并收集凭据
+        let receipts: Vec<WriteReceipt> = std::thread::scope(|s| {
+            let handles: Vec<_> = (0..N)
+                .map(|_| {
+                    let file = file.clone();
+                    let allocator = &allocator;
+                    s.spawn(move || {
+                        let range = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+                        let byte = (range.start() / ALIGNMENT) as u8 + 1;
+                        file.write_range(range, &vec![byte; ALIGNMENT as usize])
+                    })
+                })
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        assert_eq!(receipts.len(), N as usize);
+
+        file.flush_all_ranges(&receipts).unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        for (i, chunk) in bytes.chunks(ALIGNMENT as usize).enumerate() {
+            assert!(chunk.iter().all(|&b| b == i as u8 + 1));
+        }
+    }
 }
 
 /// AllocatedRange 和 WriteReceipt 测试
@@ -0,0 +1,28 @@
+//! Optional `tracing` instrumentation
+//!
+//! 可选的 `tracing` 插桩
+//!
+//! The macros expand to nothing unless the `tracing` feature is enabled, so instrumented
+//! code paths cost nothing by default.
+//!
+//! 除非启用 `tracing` 特性，否则这些宏展开为空，因此插桩的代码路径默认没有任何开销。
+
+/// Emit a `TRACE` level event
+///
+/// 发出 `TRACE` 级别的事件
+macro_rules! trace_event {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($arg)*);
+    };
+}
+
+/// Enter a `TRACE` level span until the end of the enclosing block
+///
+/// 进入 `TRACE` 级别的 span，直到所在代码块结束
+macro_rules! trace_span {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::trace_span!($($arg)*).entered();
+    };
+}
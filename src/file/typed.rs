@@ -0,0 +1,109 @@
+//! Typed view over a value written into an allocated range
+//!
+//! 对写入已分配范围中的值的类型化视图
+
+use super::mmap_file_inner::MmapFileInner;
+use super::range::{AllocatedRange, WriteReceipt};
+use bytemuck::Pod;
+use std::marker::PhantomData;
+
+/// Handle to a `T` stored at the start of an allocated range
+///
+/// 指向存放在已分配范围起始处的 `T` 的句柄
+///
+/// Returned by [`MmapFile::write_typed`](super::MmapFile::write_typed). The value is copied in
+/// and out with unaligned reads and writes, so no reference into the mapping is ever handed
+/// out. Since the range is exclusively allocated, no other writer touches these bytes and the
+/// in-place updates are safe.
+///
+/// 由 [`MmapFile::write_typed`](super::MmapFile::write_typed) 返回。值通过非对齐读写复制进出，
+/// 因此永远不会交出指向映射的引用。由于范围是独占分配的，没有其他写入者会触及这些字节，
+/// 因此原地更新是安全的。
+pub struct TypedHandle<'a, T: Pod> {
+    /// Mapping holding the value
+    ///
+    /// 存放该值的映射
+    file: &'a MmapFileInner,
+
+    /// Range holding the value, at least `size_of::<T>()` bytes
+    ///
+    /// 存放该值的范围，至少 `size_of::<T>()` 字节
+    range: AllocatedRange,
+
+    _marker: PhantomData<T>,
+}
+
+impl<'a, T: Pod> TypedHandle<'a, T> {
+    /// Wrap a range that already holds a `T`
+    ///
+    /// 包装已存放 `T` 的范围
+    #[inline]
+    pub(crate) fn new(file: &'a MmapFileInner, range: AllocatedRange) -> Self {
+        debug_assert!(size_of::<T>() as u64 <= range.len());
+        Self {
+            file,
+            range,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Read the current value
+    ///
+    /// 读取当前值
+    pub fn get(&self) -> T {
+        let mut value = T::zeroed();
+        // Safety: the range is exclusively owned, so no other thread writes to it
+        // Safety: 范围为独占所有，因此没有其他线程写入
+        unsafe {
+            self.file
+                .read_at(self.range.start(), bytemuck::bytes_of_mut(&mut value))
+                .expect("range lies within the file");
+        }
+        value
+    }
+
+    /// Overwrite the value
+    ///
+    /// 覆盖该值
+    ///
+    /// # Parameters
+    /// - `value`: New value
+    ///
+    /// # 参数
+    /// - `value`: 新值
+    pub fn set(&self, value: T) {
+        // Safety: the range is exclusively owned, so no other thread accesses it
+        // Safety: 范围为独占所有，因此没有其他线程访问
+        unsafe {
+            self.file.write_at(self.range.start(), bytemuck::bytes_of(&value));
+        }
+        self.file.mark_dirty(self.range.start(), size_of::<T>() as u64);
+    }
+
+    /// Modify the value in place through a closure
+    ///
+    /// 通过闭包原地修改该值
+    ///
+    /// Reads the value, passes it to `f`, then writes it back.
+    ///
+    /// 读取该值，传给 `f`，然后写回。
+    ///
+    /// # Parameters
+    /// - `f`: Closure modifying the value
+    ///
+    /// # 参数
+    /// - `f`: 修改该值的闭包
+    pub fn update<F: FnOnce(&mut T)>(&self, f: F) {
+        let mut value = self.get();
+        f(&mut value);
+        self.set(value);
+    }
+
+    /// Get a receipt for the range, e.g. for [`MmapFile::flush_range`](super::MmapFile::flush_range)
+    ///
+    /// 获取该范围的凭据，例如用于 [`MmapFile::flush_range`](super::MmapFile::flush_range)
+    #[inline]
+    pub fn receipt(&self) -> WriteReceipt {
+        WriteReceipt::new(self.range)
+    }
+}
@@ -36,21 +36,20 @@
 //! 使用 [`MmapFile`] 和 [`RangeAllocator`] 获得编译期安全：
 //!
 //! ```
-//! use ranged_mmap::{MmapFile, Result, allocator::ALIGNMENT};
+//! use ranged_mmap::{MmapFile, Result, nz, allocator::ALIGNMENT};
 //! # use tempfile::tempdir;
 //! # fn main() -> Result<()> {
 //! # let dir = tempdir()?;
 //! # let path = dir.path().join("output.bin");
-//! # use std::num::NonZeroU64;
 //!
 //! // Create file and allocator (file size should be 4K aligned)
 //! // 创建文件和分配器（文件大小应为4K对齐）
-//! let (file, mut allocator) = MmapFile::create_default(&path, NonZeroU64::new(ALIGNMENT * 2).unwrap())?;
+//! let (file, mut allocator) = MmapFile::create_default(&path, nz(ALIGNMENT * 2))?;
 //!
 //! // Allocate ranges (allocations are 4K aligned)
 //! // 分配范围（分配是4K对齐的）
-//! let range1 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
-//! let range2 = allocator.allocate(NonZeroU64::new(ALIGNMENT).unwrap()).unwrap();
+//! let range1 = allocator.allocate(nz(ALIGNMENT)).unwrap();
+//! let range2 = allocator.allocate(nz(ALIGNMENT)).unwrap();
 //!
 //! // Concurrent writes (compile-time safe!)
 //! // 并发写入（编译期安全！）
@@ -75,14 +74,13 @@
 //! 当你能保证安全时使用 [`MmapFileInner`]：
 //!
 //! ```
-//! use ranged_mmap::{MmapFileInner, Result};
+//! use ranged_mmap::{MmapFileInner, Result, nz};
 //! # use tempfile::tempdir;
 //! # fn main() -> Result<()> {
 //! # let dir = tempdir()?;
 //! # let path = dir.path().join("download.bin");
-//! # use std::num::NonZeroU64;
 //!
-//! let file = MmapFileInner::create(&path, NonZeroU64::new(1024).unwrap())?;
+//! let file = MmapFileInner::create(&path, nz(1024))?;
 //!
 //! // ⚠️ You must ensure non-overlapping writes
 //! // ⚠️ 你必须确保写入不重叠
@@ -110,6 +108,9 @@
 //! - [`WriteReceipt`]: Proof that a range has been written
 //! - [`SplitUpResult`]: Result of splitting with 4K upper alignment
 //! - [`SplitDownResult`]: Result of splitting with 4K lower alignment
+//! - [`Protection`]: Memory protection of a mapping
+//! - [`BatchedWriter`]: Coalesces many small writes
+//! - [`LayoutBuilder`]: Reserves a header before the data space
 //!
 //! # 主要类型
 //!
@@ -120,6 +121,9 @@
 //! - [`WriteReceipt`][]: 证明范围已被写入的凭据
 //! - [`SplitUpResult`][]: 4K上对齐拆分的结果
 //! - [`SplitDownResult`][]: 4K下对齐拆分的结果
+//! - [`Protection`][]: 映射的内存保护
+//! - [`BatchedWriter`][]: 合并大量小写入
+//! - [`LayoutBuilder`][]: 在数据空间之前保留头部
 
 mod file;
 